@@ -0,0 +1,147 @@
+use crate::can_message::CANMessage;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, LineError};
+use crate::error::CANParserError;
+use crate::error_policy::ErrorPolicy;
+use crate::CANParser;
+use futures_core::Stream;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Split};
+
+/// A `Stream` of the lines of an `AsyncRead`, split on `\n`.
+pub struct ReaderLines<R> {
+    lines: Split<BufReader<R>>,
+}
+
+impl<R: AsyncRead + Unpin> ReaderLines<R> {
+    /// Creates a `ReaderLines` reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: BufReader::new(reader).split(b'\n'),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for ReaderLines<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.lines)
+            .poll_next_segment(cx)
+            .map(Result::transpose)
+    }
+}
+
+/// A `Stream` of the messages decoded from a `Stream` of lines, created by
+/// `CANParser::stream_lines` or `CANParser::stream_reader`.
+///
+/// Lines are only pulled from the source when the next message is polled, so a slow consumer
+/// slows down the reading of the source instead of buffering decoded messages. Dropping the
+/// stream cancels the parse and drops the source.
+///
+/// Lines that cannot be parsed are handled according to the parser's `ErrorPolicy`: their
+/// diagnostics are collected in `diagnostics`, and once the policy stops the parse the stream
+/// yields a `CANParserError::ParseAborted` and ends. An error reading the source is yielded as a
+/// `CANParserError::IOError` and also ends the stream.
+pub struct MessageStream<S> {
+    parser: Arc<CANParser>,
+    lines: S,
+    line_number: u64,
+    diagnostics: Diagnostics,
+    done: bool,
+}
+
+impl<S> MessageStream<S> {
+    /// Creates a `MessageStream` decoding `lines` with `parser`.
+    pub(crate) fn new(parser: Arc<CANParser>, lines: S) -> Self {
+        Self {
+            parser,
+            lines,
+            line_number: 0,
+            diagnostics: Diagnostics::default(),
+            done: false,
+        }
+    }
+
+    /// Returns the parser decoding the stream, whose `filtered_spec` holds the specification
+    /// entries used by the messages decoded so far.
+    pub fn parser(&self) -> &Arc<CANParser> {
+        &self.parser
+    }
+
+    /// Returns the problems found in the lines read so far.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Records a line that could not be parsed.
+    ///
+    /// # Returns
+    ///
+    /// The error ending the stream if the error policy stops the parse.
+    fn record(&mut self, error: LineError, line: String) -> Option<CANParserError> {
+        if self.parser.error_handling == ErrorPolicy::Ignore {
+            return None;
+        }
+        self.diagnostics
+            .push(Diagnostic::new(error, line, self.line_number, None, None));
+        match self.parser.error_handling.limit() {
+            Some(limit) if self.diagnostics.len() > limit => Some(CANParserError::ParseAborted(
+                Arc::new(self.diagnostics.clone()),
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl<S, L> Stream for MessageStream<S>
+where
+    S: Stream<Item = io::Result<L>> + Unpin,
+    L: AsRef<[u8]>,
+{
+    type Item = Result<CANMessage, CANParserError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let line = match ready!(Pin::new(&mut this.lines).poll_next(cx)) {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                None => {
+                    this.done = true;
+                    return Poll::Ready(None);
+                }
+            };
+            this.line_number += 1;
+
+            let bytes = line.as_ref();
+            let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+            let (error, text) = match std::str::from_utf8(bytes) {
+                Ok(text) => match this
+                    .parser
+                    .parse_line_inner(text, this.parser.filter.as_ref())
+                {
+                    Ok(Some(message)) => return Poll::Ready(Some(Ok(message))),
+                    Ok(None) => continue,
+                    Err(e) => (e, text.to_string()),
+                },
+                Err(e) => (
+                    LineError::new(DiagnosticKind::Io, e.to_string()),
+                    String::from_utf8_lossy(bytes).into_owned(),
+                ),
+            };
+            if let Some(aborted) = this.record(error, text) {
+                this.done = true;
+                return Poll::Ready(Some(Err(aborted)));
+            }
+        }
+    }
+}
@@ -1,26 +1,37 @@
+use crate::error_frame::CANErrorFrame;
 use crate::specification::SpecSPN;
+use crate::timestamp::nanos_to_secs;
 use crate::utils::process_string;
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 #[cfg(feature = "python")]
 use pyo3::types::PyDict;
-use regex::Match;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
+use std::sync::Arc;
 
-// const CAN_EFF_FLAG: u32 = 0x80000000;
+pub const CAN_EFF_FLAG: u32 = 0x80000000;
+pub const CAN_EFF_MASK: u32 = 0x1FFFFFFF;
 pub const CAN_SFF_MASK: u32 = 0x000007FF;
+/// The number of hex digits candump and similar loggers use to write extended IDs.
+pub const CAN_EFF_ID_DIGITS: usize = 8;
 pub const CAN_RTR_FLAG: u32 = 0x40000000;
 pub const CAN_ERR_FLAG: u32 = 0x20000000;
+pub const CAN_ERR_MASK: u32 = 0x1FFFFFFF;
 pub const PRIORITY_MASK: u32 = 0x1C000000;
 pub const PRIORITY_SHIFT: u32 = 26;
 pub const PDU_FORMAT_MASK: u32 = 0x3FF0000;
 pub const PDU_FORMAT_SHIFT: u16 = 16;
 pub const PDU_SPECIFIC_MASK: u32 = 0xFF00;
 pub const PDU_SPECIFIC_SHIFT: u16 = 8;
+pub const CANFD_BRS: u8 = 0x01;
+pub const CANFD_ESI: u8 = 0x02;
+pub const CAN_MAX_DLEN: usize = 8;
+pub const CANFD_MAX_DLEN: usize = 64;
+const CANFD_DLC_TO_LEN: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
 
 /// Represents the flags associated with a CAN message.
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 pub struct CANMessageFlags {
     /// Indicates whether the message has an extended identifier.
     pub ext: bool,
@@ -28,6 +39,46 @@ pub struct CANMessageFlags {
     pub err: bool,
     /// Indicates whether the message is a remote transmission request.
     pub rtr: bool,
+    /// Indicates whether the message is a CAN FD frame.
+    #[serde(default)]
+    pub fd: bool,
+    /// Indicates whether the CAN FD frame was sent with a bit rate switch.
+    #[serde(default)]
+    pub brs: bool,
+    /// Indicates whether the CAN FD sender was error passive.
+    #[serde(default)]
+    pub esi: bool,
+}
+
+/// The direction of a CAN message relative to the logging interface.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The message was received by the logging interface.
+    Rx,
+    /// The message was transmitted by the logging interface.
+    Tx,
+}
+
+impl std::str::FromStr for Direction {
+    type Err = String;
+
+    /// Parses a direction from `Rx`/`Tx` or the single letter `R`/`T`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rx" | "r" => Ok(Direction::Rx),
+            "tx" | "t" => Ok(Direction::Tx),
+            _ => Err(format!("Failed to parse direction: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Direction::Rx => write!(f, "Rx"),
+            Direction::Tx => write!(f, "Tx"),
+        }
+    }
 }
 
 /// A struct representing a CAN message's data.
@@ -102,17 +153,15 @@ impl<'de> Deserialize<'de> for CANData {
                             if data.is_some() {
                                 return Err(Error::duplicate_field("data"));
                             }
-                            let processed = process_string(map.next_value()?, 64);
-
-                            let array: [u8; 64] = processed
-                                .as_bytes()
-                                .chunks(2)
-                                .map(|b| {
-                                    u8::from_str_radix(std::str::from_utf8(b).unwrap(), 16).unwrap()
-                                })
-                                .collect::<Vec<u8>>()
-                                .try_into()
-                                .unwrap();
+                            let processed = process_string(map.next_value()?, CANFD_MAX_DLEN * 2);
+
+                            let mut array = [0u8; CANFD_MAX_DLEN];
+                            for (i, b) in processed.as_bytes().chunks(2).enumerate() {
+                                array[i] = std::str::from_utf8(b)
+                                    .ok()
+                                    .and_then(|b| u8::from_str_radix(b, 16).ok())
+                                    .ok_or_else(|| Error::custom("invalid hex in data"))?;
+                            }
                             data = Some(array);
                         }
                         "spns" => {
@@ -152,7 +201,7 @@ impl FromPyObject<'_> for CANData {
 }
 
 /// A struct representing a CAN message ID.
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 pub struct CANID {
     /// The message ID.
     #[serde(
@@ -185,7 +234,7 @@ pub struct CANID {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// use serde::Serializer;
 /// use can_parser::can_message::serialize_id;
 ///
@@ -246,18 +295,33 @@ where
 #[cfg_attr(feature = "python", pyo3::prelude::pyclass)]
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct CANMessage {
-    /// Timestamp of the message.
-    pub ts: f64,
+    /// Timestamp of the message in nanoseconds.
+    pub ts: i64,
+    /// The channel or interface the message was logged on, e.g. `can0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Whether the message was received or transmitted by the logging interface.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    /// The log file the message was read from, when several files were merged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Arc<str>>,
     /// ID of the message.
     pub id: CANID,
     /// Data of the message.
     pub data: CANData,
+    /// The decoded error classes, if the message is an error frame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<CANErrorFrame>,
 }
 
 impl Default for CANMessage {
     fn default() -> Self {
         Self {
-            ts: 0.0,
+            ts: 0,
+            channel: None,
+            direction: None,
+            source: None,
             id: CANID {
                 id: 0,
                 pri: 0,
@@ -268,6 +332,9 @@ impl Default for CANMessage {
                     ext: false,
                     err: false,
                     rtr: false,
+                    fd: false,
+                    brs: false,
+                    esi: false,
                 },
             },
             data: CANData {
@@ -275,29 +342,87 @@ impl Default for CANMessage {
                 data: [0; 64],
                 spns: HashMap::with_capacity(0),
             },
+            error: None,
         }
     }
 }
 
-/// Parses the given `Match` into a `CANID` struct.
+impl CANMessage {
+    /// Returns the timestamp of the message in seconds.
+    pub fn ts_secs(&self) -> f64 {
+        nanos_to_secs(self.ts)
+    }
+}
+
+/// Converts a data length code to the number of data bytes it represents.
+///
+/// Classic CAN frames cap the length at 8 bytes, while CAN FD frames use the extended
+/// 12, 16, 20, 24, 32, 48 and 64 byte lengths for codes 9 through 15.
+pub fn dlc_to_len(dlc: u8, fd: bool) -> usize {
+    let len = CANFD_DLC_TO_LEN[(dlc & 0x0F) as usize] as usize;
+    if fd {
+        len
+    } else {
+        len.min(CAN_MAX_DLEN)
+    }
+}
+
+/// Converts a number of data bytes to its data length code.
+///
+/// # Returns
+///
+/// The data length code, or `None` if the length is not valid for the frame type.
+pub fn len_to_dlc(len: usize, fd: bool) -> Option<u8> {
+    if !fd && len > CAN_MAX_DLEN {
+        return None;
+    }
+    CANFD_DLC_TO_LEN
+        .iter()
+        .position(|&l| l as usize == len)
+        .map(|dlc| dlc as u8)
+}
+
+/// Parses a hexadecimal CAN ID into a `CANID` struct.
+///
+/// Whether the ID is extended is taken from how it was written in the log rather than from its
+/// value alone: an ID with the `CAN_EFF_FLAG` set, written with 8 hex digits, or too large for 11
+/// bits is extended. This keeps 29-bit IDs with small values such as `00000123` extended.
 ///
 /// # Arguments
 ///
-/// * `id` - A `Match` object representing the ID to be parsed.
+/// * `id` - A string slice holding the ID as hex digits.
 /// * `can_id` - A mutable reference to a `CANID` struct where the parsed ID will be stored.
-pub fn parse_id(id: Match, can_id: &mut CANID) {
-    // let extended = (id & CAN_EFF_FLAG) == CAN_EFF_FLAG; // Not working
-    can_id.id = u32::from_str_radix(id.as_str(), 16).unwrap();
-    can_id.flags.ext = if can_id.id > CAN_SFF_MASK {
-        true
-    } else {
-        false
-    };
-    can_id.flags.err = (can_id.id & CAN_ERR_FLAG) == CAN_ERR_FLAG;
-    can_id.flags.rtr = (can_id.id & CAN_RTR_FLAG) == CAN_RTR_FLAG;
+/// * `extended` - Overrides the detection, forcing the ID to be extended (`Some(true)`) or standard (`Some(false)`).
+///
+/// # Errors
+///
+/// Returns an error message if `id` is not valid hexadecimal, or if it is forced to be standard but does not fit in 11 bits.
+pub fn parse_id(id: &str, can_id: &mut CANID, extended: Option<bool>) -> Result<(), String> {
+    let raw = u32::from_str_radix(id, 16).map_err(|_| format!("Failed to parse ID {}", id))?;
+    can_id.flags.err = (raw & CAN_ERR_FLAG) == CAN_ERR_FLAG;
+    can_id.flags.rtr = (raw & CAN_RTR_FLAG) == CAN_RTR_FLAG;
+    if can_id.flags.err {
+        // Error frames carry their error class in the ID rather than an address.
+        can_id.id = raw & CAN_ERR_MASK;
+        can_id.flags.ext = false;
+        return Ok(());
+    }
+    can_id.id = raw & CAN_EFF_MASK;
+    can_id.flags.ext = extended.unwrap_or(
+        (raw & CAN_EFF_FLAG) == CAN_EFF_FLAG
+            || id.len() >= CAN_EFF_ID_DIGITS
+            || can_id.id > CAN_SFF_MASK,
+    );
+    if !can_id.flags.ext && can_id.id > CAN_SFF_MASK {
+        return Err(format!(
+            "ID {} does not fit in a standard 11-bit identifier",
+            id
+        ));
+    }
     if can_id.flags.ext {
         parse_j1939_id(can_id);
     }
+    Ok(())
 }
 
 /// Parses a J1939 CAN ID and updates the fields of the given `CANID` struct accordingly.
@@ -318,6 +443,121 @@ pub fn parse_j1939_id(can_id: &mut CANID) {
     }
 }
 
+/// Builds the extended CAN ID of a J1939 message, the inverse of `parse_j1939_id`.
+///
+/// # Arguments
+///
+/// * `pgn` - The PGN of the message.
+/// * `priority` - The priority of the message, from 0 (highest) to 7.
+/// * `sa` - The source address.
+/// * `da` - The destination address. Only used by PDU1 PGNs, whose PDU format is below 240.
+pub fn build_j1939_id(pgn: u16, priority: u8, sa: u8, da: u8) -> CANID {
+    let pdu_fmt = (pgn >> 8) as u32;
+    let pdu_spec = if pdu_fmt >= 240 { pgn as u8 } else { da };
+    let mut can_id = CANID {
+        id: ((priority as u32 & 0x7) << PRIORITY_SHIFT)
+            | (pdu_fmt << PDU_FORMAT_SHIFT)
+            | ((pdu_spec as u32) << PDU_SPECIFIC_SHIFT)
+            | sa as u32,
+        pri: 0,
+        da: 0,
+        sa: 0,
+        pgn: 0,
+        flags: CANMessageFlags {
+            ext: true,
+            err: false,
+            rtr: false,
+            fd: false,
+            brs: false,
+            esi: false,
+        },
+    };
+    parse_j1939_id(&mut can_id);
+    can_id
+}
+
+/// Encodes physical SPN values into the payload of a J1939 message, the inverse of
+/// `parse_j1939_data`.
+///
+/// The bits of SPNs without a value, and the bits no SPN covers, are left untouched, so a payload
+/// initialized to `0xFF` marks them as "not available".
+///
+/// # Arguments
+///
+/// * `data` - The payload to encode the values into.
+/// * `spn_info` - The SPNs of the message.
+/// * `values` - The physical value of each SPN to encode.
+///
+/// # Errors
+///
+/// Returns an error message if an SPN is not part of the message, does not fit in the payload,
+/// or if its value cannot be represented by the SPN.
+pub fn encode_j1939_data(
+    data: &mut [u8],
+    spn_info: &HashMap<u16, SpecSPN>,
+    values: &HashMap<u16, f64>,
+) -> Result<(), String> {
+    for (spn, value) in values {
+        let spec = spn_info
+            .get(spn)
+            .ok_or_else(|| format!("SPN {} is not part of the message", spn))?;
+        let start_bit = spec.start_bit as usize;
+        let length = spec.length as usize;
+        if length == 0 || length > 64 || start_bit + length > data.len() * 8 {
+            return Err(format!(
+                "SPN {} of {} bits at bit {} does not fit in a {} byte payload",
+                spn,
+                length,
+                start_bit,
+                data.len()
+            ));
+        }
+        if spec.resolution == 0.0 {
+            return Err(format!("SPN {} has no resolution", spn));
+        }
+        let raw = ((value - spec.offset as f64) / spec.resolution as f64).round();
+        let max_raw = 2f64.powi(length as i32) - 1.0;
+        if !(0.0..=max_raw).contains(&raw) {
+            return Err(format!(
+                "Value {} is out of the range of SPN {}",
+                value, spn
+            ));
+        }
+        write_bits_lsb(data, start_bit, length, raw as u64);
+    }
+    Ok(())
+}
+
+/// Writes the `length` lowest bits of `raw` into `data`, least significant bit first, starting
+/// at bit `start_bit`. Bits are numbered from the least significant bit of the first byte.
+pub(crate) fn write_bits_lsb(data: &mut [u8], start_bit: usize, length: usize, raw: u64) {
+    for i in 0..length {
+        let position = start_bit + i;
+        let mask = 1u8 << (position % 8);
+        if (raw >> i) & 1 == 1 {
+            data[position / 8] |= mask;
+        } else {
+            data[position / 8] &= !mask;
+        }
+    }
+}
+
+/// Reads `length` bits of `data`, least significant bit first, starting at bit `start_bit`, the
+/// inverse of `write_bits_lsb`.
+///
+/// # Returns
+///
+/// The raw value of the bits, or `None` if they do not fit in `data` or in a `u64`.
+pub(crate) fn read_bits_lsb(data: &[u8], start_bit: usize, length: usize) -> Option<u64> {
+    if length > 64 || start_bit + length > data.len() * 8 {
+        return None;
+    }
+    Some((0..length).fold(0, |raw, i| {
+        let position = start_bit + i;
+        raw | (((data[position / 8] >> (position % 8)) & 1) as u64) << i
+    }))
+}
+
 /// Parses J1939 data from a CAN message and populates the given `CANData` struct with the parsed SPNs.
 ///
 /// # Arguments
@@ -0,0 +1,105 @@
+use crate::error::CANParserError;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+const XZ_MAGIC: &[u8] = &[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00];
+const BZIP2_MAGIC: &[u8] = &[0x42, 0x5A, 0x68];
+
+/// The compression formats that can be detected on an input log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl Compression {
+    /// Determines the compression format from the leading bytes of a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The first bytes of the file. Six bytes are enough to detect every supported format.
+    ///
+    /// # Returns
+    ///
+    /// The detected `Compression`, or `Compression::None` if no known magic bytes were found.
+    pub fn from_magic(header: &[u8]) -> Self {
+        if header.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if header.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else if header.starts_with(BZIP2_MAGIC) {
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Opens a log file for line based reading, transparently decompressing it if it is compressed.
+///
+/// The compression format is detected from the magic bytes at the start of the file rather than
+/// from the file extension. Decompression is streamed so the decompressed log is never staged on
+/// disk or held in memory as a whole. Gzip files made of several concatenated members are read
+/// to the end.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice that holds the path to the file to be opened.
+///
+/// # Errors
+///
+/// Returns a `CANParserError` if the file cannot be opened, if the decoder fails to initialize, or
+/// if the file is compressed and the `compression` feature is not enabled.
+pub fn open_log_reader(file_path: &str) -> Result<Box<dyn BufRead + Send>, CANParserError> {
+    let mut reader = BufReader::new(File::open(file_path)?);
+    let compression = Compression::from_magic(reader.fill_buf()?);
+    match compression {
+        Compression::None => Ok(Box::new(reader)),
+        #[cfg(feature = "compression")]
+        Compression::Gzip => Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(reader),
+        ))),
+        #[cfg(feature = "compression")]
+        Compression::Zstd => Ok(Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader)?,
+        ))),
+        #[cfg(feature = "compression")]
+        Compression::Xz => Ok(Box::new(BufReader::new(
+            xz2::bufread::XzDecoder::new_multi_decoder(reader),
+        ))),
+        #[cfg(feature = "compression")]
+        Compression::Bzip2 => Ok(Box::new(BufReader::new(
+            bzip2::bufread::MultiBzDecoder::new(reader),
+        ))),
+        #[cfg(not(feature = "compression"))]
+        _ => Err(CANParserError::ParserError(format!(
+            "{:?} compressed input detected. Please enable the compression feature.",
+            compression
+        ))),
+    }
+}
+
+/// Returns the size in bytes of a log file as read by `open_log_reader`.
+///
+/// # Returns
+///
+/// The size of the file, or `None` if it is compressed, since the decompressed size is not known
+/// before reading it. Also `None` if the file cannot be read, leaving the error to `open_log_reader`.
+pub fn log_size(file_path: &str) -> Option<u64> {
+    let mut reader = BufReader::new(File::open(file_path).ok()?);
+    match Compression::from_magic(reader.fill_buf().ok()?) {
+        Compression::None => reader
+            .get_ref()
+            .metadata()
+            .ok()
+            .map(|metadata| metadata.len()),
+        _ => None,
+    }
+}
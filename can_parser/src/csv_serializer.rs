@@ -1,10 +1,16 @@
 use crate::SpecSPN;
-use crate::can_message::CANMessage;
+use crate::can_message::{CANMessage, Direction};
 use crate::error::CANParserError;
+use crate::error_frame::CANErrorFrame;
+use crate::sink::MessageSink;
 use crate::specification::{FilteredSpec, SpecPGN};
 use csv::Writer;
+use std::borrow::Borrow;
 use std::collections::HashMap;
-use std::path::Path;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use serde_json::{Map, Value};
 
 /// Converts the filtered CAN specification and messages to a CSV format.
@@ -18,21 +24,13 @@ use serde_json::{Map, Value};
 /// * `Ok(None)` - If the `output_path` is provided and the CSV file is successfully saved.
 /// * `Ok(Some(csv_string))` - If the `output_path` is not provided and the CSV data is successfully combined into a single string.
 /// * `Err(CANParserError)` - If there is an error during the serialization or saving process.
-pub fn to_csv(
+pub fn to_csv<M: Borrow<CANMessage>>(
     output_path: Option<String>,
     filtered_spec: &FilteredSpec,
-    messages: &Vec<CANMessage>,
+    messages: impl IntoIterator<Item = M>,
 ) -> Result<Option<String>, CANParserError> {
-    let mut csv_collection = HashMap::new();
-
-    for (key, value) in (*filtered_spec).clone() {
-        csv_collection.insert(key.to_string(), serialize_to_csv(&value)?);
-    }
-
-    csv_collection.insert(
-        "messages".to_string(),
-        serialize_messages_to_csv(&messages)?,
-    );
+    let mut csv_collection = serialize_spec_to_csv(filtered_spec)?;
+    csv_collection.insert("messages".to_string(), serialize_messages_to_csv(messages)?);
 
     if let Some(output_path) = output_path {
         save_to_files(&csv_collection, &output_path)?;
@@ -76,6 +74,21 @@ fn convert_spns_to_array(pgn: &mut Value, spec: bool) {
     }
 } 
 
+/// Serializes each specification of the filtered specification to CSV format.
+///
+/// # Returns
+///
+/// A Result containing the CSV content keyed by specification type, or a CANParserError if an error occurs.
+fn serialize_spec_to_csv(
+    filtered_spec: &FilteredSpec,
+) -> Result<HashMap<String, Vec<u8>>, CANParserError> {
+    let mut csv_collection = HashMap::new();
+    for (key, value) in (*filtered_spec).clone() {
+        csv_collection.insert(key.to_string(), serialize_to_csv(&value)?);
+    }
+    Ok(csv_collection)
+}
+
 /// Serializes a HashMap of SpecPGN to CSV format.
 ///
 /// # Arguments
@@ -87,7 +100,7 @@ fn convert_spns_to_array(pgn: &mut Value, spec: bool) {
 /// A Result containing a vector of bytes representing the CSV content, or a CANParserError if an error occurs.
 fn serialize_to_csv(data: &HashMap<u16, SpecPGN>) -> Result<Vec<u8>, CANParserError> {
     let mut wtr = Writer::from_writer(vec![]);
-    let mut first = true;
+    let mut columns: Option<Vec<String>> = None;
     for (key, value) in data {
         let mut value_json = serde_json::to_value(value)?;
         convert_spns_to_array(&mut value_json, true);
@@ -95,21 +108,13 @@ fn serialize_to_csv(data: &HashMap<u16, SpecPGN>) -> Result<Vec<u8>, CANParserEr
             CANParserError::ParserError("Failed to convert JSON to object".to_string())
         })?;
         let obj = flatten_serde_json::flatten(obj);
-        if first {
-            let mut keys = vec![];
-            keys.push("id".to_string());
-            for key in obj.keys() {
-                keys.push(key.clone());
-            }
-            wtr.write_record(&keys)?;
-            first = false;
+        if columns.is_none() {
+            let keys: Vec<String> = obj.keys().cloned().collect();
+            wtr.write_record(std::iter::once("id").chain(keys.iter().map(String::as_str)))?;
+            columns = Some(keys);
         }
-        let mut values = vec![];
-        values.push(key.to_string());
-        for value in obj.values() {
-            values.push(value.to_string().replace("\"", ""));
-        }
-        wtr.write_record(&values)?;
+        let columns = columns.as_deref().unwrap_or_default();
+        wtr.write_record(std::iter::once(key.to_string()).chain(record(&obj, columns)))?;
     }
     Ok(wtr.into_inner()?)
 }
@@ -118,7 +123,7 @@ fn serialize_to_csv(data: &HashMap<u16, SpecPGN>) -> Result<Vec<u8>, CANParserEr
 ///
 /// # Arguments
 ///
-/// * `messages` - A vector of `CANMessage` structs to be serialized.
+/// * `messages` - The `CANMessage`s to be serialized, owned or borrowed.
 ///
 /// # Returns
 ///
@@ -126,7 +131,7 @@ fn serialize_to_csv(data: &HashMap<u16, SpecPGN>) -> Result<Vec<u8>, CANParserEr
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use can_parser::CANMessage;
 ///
 /// let messages = vec![
@@ -146,26 +151,82 @@ fn serialize_to_csv(data: &HashMap<u16, SpecPGN>) -> Result<Vec<u8>, CANParserEr
 ///
 /// let csv_content = can_parser::serialize_messages_to_csv(&messages).unwrap();
 /// ```
-fn serialize_messages_to_csv(messages: &Vec<CANMessage>) -> Result<Vec<u8>, CANParserError> {
+fn serialize_messages_to_csv<M: Borrow<CANMessage>>(
+    messages: impl IntoIterator<Item = M>,
+) -> Result<Vec<u8>, CANParserError> {
     let mut wtr = Writer::from_writer(vec![]);
-    let mut first = true;
+    let columns = message_columns()?;
+    wtr.write_record(&columns)?;
     for message in messages {
-        let mut value_json = serde_json::to_value(message)?;
-        convert_spns_to_array(&mut value_json.get_mut("data").unwrap(), false);
-        let obj = value_json.as_object().ok_or_else(|| {
-            CANParserError::ParserError("Failed to convert to object".to_string())
-        })?;
-        let obj = flatten_serde_json::flatten(obj);
-        if first {
-            wtr.write_record(obj.keys())?;
-            first = false;
-        }
-        
-        wtr.write_record(obj.values().map(|v| v.to_string().replace("\"", "")))?;
+        write_message_record(&mut wtr, message.borrow(), &columns)?;
     }
     Ok(wtr.into_inner()?)
 }
 
+/// Flattens a CAN message into its CSV fields, keyed by column.
+fn flatten_message(message: &CANMessage) -> Result<Map<String, Value>, CANParserError> {
+    let mut value_json = serde_json::to_value(message)?;
+    if let Some(data) = value_json.get_mut("data") {
+        convert_spns_to_array(data, false);
+    }
+    let obj = value_json
+        .as_object()
+        .ok_or_else(|| CANParserError::ParserError("Failed to convert to object".to_string()))?;
+    Ok(flatten_serde_json::flatten(obj))
+}
+
+/// Returns the columns of the messages CSV.
+///
+/// The optional fields of a message are only serialized when they are set, so the columns are
+/// taken from a message that has all of them, and are the same whichever messages are written.
+fn message_columns() -> Result<Vec<String>, CANParserError> {
+    let message = CANMessage {
+        channel: Some(String::new()),
+        direction: Some(Direction::Rx),
+        source: Some(Arc::from("")),
+        error: Some(CANErrorFrame::default()),
+        ..Default::default()
+    };
+    Ok(flatten_message(&message)?
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// Writes a CAN message as a CSV record with the fields of `columns`.
+///
+/// # Arguments
+///
+/// * `wtr` - The CSV writer to write to.
+/// * `message` - The `CANMessage` to be serialized.
+/// * `columns` - The columns of the header, as returned by `message_columns`.
+///
+/// # Errors
+///
+/// Returns a `CANParserError` if the message cannot be serialized or written.
+fn write_message_record<W: Write>(
+    wtr: &mut Writer<W>,
+    message: &CANMessage,
+    columns: &[String],
+) -> Result<(), CANParserError> {
+    let obj = flatten_message(message)?;
+    wtr.write_record(record(&obj, columns))?;
+    Ok(())
+}
+
+/// Returns the fields of a flattened object in the order of `columns`, leaving the missing ones
+/// empty. Strings are written as they are, and quoted by the CSV writer when needed.
+fn record<'a>(
+    obj: &'a Map<String, Value>,
+    columns: &'a [String],
+) -> impl Iterator<Item = String> + 'a {
+    columns.iter().map(move |column| match obj.get(column) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    })
+}
+
 /// Saves the given CSV data to files in the specified output directory.
 ///
 /// # Arguments
@@ -179,7 +240,7 @@ fn serialize_messages_to_csv(messages: &Vec<CANMessage>) -> Result<Vec<u8>, CANP
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use std::collections::HashMap;
 /// use can_parser::CANParserError;
 ///
@@ -198,22 +259,30 @@ fn save_to_files(
     csv_collection: &HashMap<String, Vec<u8>>,
     output_path: &str,
 ) -> Result<(), CANParserError> {
-    let output_path = Path::new(output_path);
-    if output_path.extension().is_none() {
-        return Err(CANParserError::ParserError(
-            "Output path must include file extension".to_string(),
-        ));
-    }
     for (key, value) in csv_collection {
-        let mod_output_path = output_path.with_file_name(&format!(
+        std::fs::write(&keyed_path(output_path, key)?, value)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of the CSV file holding `key`, named `<stem>_<key>.<extension>` after `output_path`.
+///
+/// # Errors
+///
+/// Returns a CANParserError if `output_path` has no file extension.
+fn keyed_path(output_path: &str, key: &str) -> Result<PathBuf, CANParserError> {
+    let output_path = Path::new(output_path);
+    match (output_path.file_stem(), output_path.extension()) {
+        (Some(stem), Some(extension)) => Ok(output_path.with_file_name(format!(
             "{}_{}.{}",
-            output_path.file_stem().unwrap().to_str().unwrap(),
+            stem.to_string_lossy(),
             key,
-            output_path.extension().unwrap().to_str().unwrap()
-        ));
-        std::fs::write(&mod_output_path, value)?;
+            extension.to_string_lossy()
+        ))),
+        _ => Err(CANParserError::ParserError(
+            "Output path must include file extension".to_string(),
+        )),
     }
-    Ok(())
 }
 
 /// Combines a collection of CSV strings into a single string, with each CSV string
@@ -242,3 +311,43 @@ fn combine_csv_strings(
     }
     Ok(csv_string)
 }
+
+/// A `MessageSink` writing the messages to CSV while they are parsed.
+///
+/// The files are laid out as by `CANParser::to_csv`: the messages are streamed to
+/// `<stem>_messages.<extension>` and the specification files are written when parsing finishes.
+pub struct CsvSink {
+    output_path: String,
+    messages: Writer<File>,
+    columns: Vec<String>,
+}
+
+impl CsvSink {
+    /// Creates a `CsvSink` writing the files named after `output_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if `output_path` has no file extension or the messages file
+    /// cannot be created.
+    pub fn create(output_path: &str) -> Result<Self, CANParserError> {
+        let mut messages = Writer::from_path(keyed_path(output_path, "messages")?)?;
+        let columns = message_columns()?;
+        messages.write_record(&columns)?;
+        Ok(Self {
+            output_path: output_path.to_string(),
+            messages,
+            columns,
+        })
+    }
+}
+
+impl MessageSink for CsvSink {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        write_message_record(&mut self.messages, &message, &self.columns)
+    }
+
+    fn on_finish(&mut self, filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        self.messages.flush()?;
+        save_to_files(&serialize_spec_to_csv(filtered_spec)?, &self.output_path)
+    }
+}
@@ -0,0 +1,149 @@
+use crate::can_message::{parse_j1939_id, write_bits_lsb, CANMessageFlags, CANID};
+use can_dbc::{ByteOrder, Message, ValueType, DBC};
+use std::collections::HashMap;
+
+/// The bit DBC files set in the ID of messages with an extended (29-bit) ID.
+const DBC_EXTENDED_ID_FLAG: u32 = 0x8000_0000;
+
+/// Finds a message of a DBC annex by name.
+pub(crate) fn find_message<'a>(dbc: &'a DBC, name: &str) -> Option<&'a Message> {
+    dbc.messages()
+        .iter()
+        .find(|message| message.message_name() == name)
+}
+
+/// Builds the CAN ID of a DBC message. Extended IDs are also split into their J1939 fields, with
+/// the source address replaced by `sa` if one is given.
+pub(crate) fn message_id(message: &Message, sa: Option<u8>) -> CANID {
+    let raw = message.message_id().0;
+    let ext = raw & DBC_EXTENDED_ID_FLAG != 0;
+    let mut id = raw & !DBC_EXTENDED_ID_FLAG;
+    if let (true, Some(sa)) = (ext, sa) {
+        id = (id & !0xFF) | sa as u32;
+    }
+    let mut can_id = CANID {
+        id,
+        pri: 0,
+        da: 0,
+        sa: 0,
+        pgn: 0,
+        flags: CANMessageFlags {
+            ext,
+            err: false,
+            rtr: false,
+            fd: false,
+            brs: false,
+            esi: false,
+        },
+    };
+    if ext {
+        parse_j1939_id(&mut can_id);
+    }
+    can_id
+}
+
+/// Encodes physical signal values into the payload of a DBC message.
+///
+/// Little endian (Intel) signals are packed from their start bit upwards, big endian (Motorola)
+/// signals from their most significant bit at the start bit downwards, in DBC bit numbering.
+/// The bits of signals without a value are left untouched.
+///
+/// # Arguments
+///
+/// * `data` - The payload to encode the values into.
+/// * `message` - The DBC message.
+/// * `values` - The physical value of each signal to encode, by signal name.
+///
+/// # Errors
+///
+/// Returns an error message if a signal is not part of the message, does not fit in the payload,
+/// or if its value cannot be represented by the signal.
+pub(crate) fn encode_dbc_data(
+    data: &mut [u8],
+    message: &Message,
+    values: &HashMap<String, f64>,
+) -> Result<(), String> {
+    for (name, value) in values {
+        let signal = message
+            .signals()
+            .iter()
+            .find(|signal| signal.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Signal {} is not part of message {}",
+                    name,
+                    message.message_name()
+                )
+            })?;
+        let start_bit = *signal.start_bit() as usize;
+        let length = *signal.signal_size() as usize;
+        let fits = match signal.byte_order() {
+            ByteOrder::LittleEndian => start_bit + length <= data.len() * 8,
+            ByteOrder::BigEndian => motorola_end_bit(start_bit, length) < data.len() * 8,
+        };
+        if length == 0 || length > 64 || !fits {
+            return Err(format!(
+                "Signal {} of {} bits at bit {} does not fit in a {} byte payload",
+                name,
+                length,
+                start_bit,
+                data.len()
+            ));
+        }
+        if *signal.factor() == 0.0 {
+            return Err(format!("Signal {} has no factor", name));
+        }
+        let raw = ((value - signal.offset()) / signal.factor()).round();
+        let (min_raw, max_raw) = match signal.value_type() {
+            ValueType::Signed => (
+                -(2f64.powi(length as i32 - 1)),
+                2f64.powi(length as i32 - 1) - 1.0,
+            ),
+            ValueType::Unsigned => (0.0, 2f64.powi(length as i32) - 1.0),
+        };
+        if !(min_raw..=max_raw).contains(&raw) {
+            return Err(format!(
+                "Value {} is out of the range of signal {}",
+                value, name
+            ));
+        }
+        // Two's complement of negative values, truncated to the signal length below
+        let raw = raw as i64 as u64;
+        match signal.byte_order() {
+            ByteOrder::LittleEndian => write_bits_lsb(data, start_bit, length, raw),
+            ByteOrder::BigEndian => write_bits_motorola(data, start_bit, length, raw),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the position of the next, less significant, bit of a big endian signal.
+fn next_motorola_bit(position: usize) -> usize {
+    if position.is_multiple_of(8) {
+        position + 15
+    } else {
+        position - 1
+    }
+}
+
+/// Returns the position of the least significant bit of a big endian signal.
+fn motorola_end_bit(start_bit: usize, length: usize) -> usize {
+    (1..length).fold(start_bit, |position, _| next_motorola_bit(position))
+}
+
+/// Writes the `length` lowest bits of `raw` into `data` as a big endian signal whose most
+/// significant bit is at bit `start_bit`.
+fn write_bits_motorola(data: &mut [u8], start_bit: usize, length: usize, raw: u64) {
+    let mut position = start_bit;
+    for i in (0..length).rev() {
+        let mask = 1u8 << (position % 8);
+        if (raw >> i) & 1 == 1 {
+            data[position / 8] |= mask;
+        } else {
+            data[position / 8] &= !mask;
+        }
+        if i > 0 {
+            position = next_motorola_bit(position);
+        }
+    }
+}
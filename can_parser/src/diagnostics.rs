@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The number of diagnostics listed when a `Diagnostics` summary is displayed.
+const DISPLAY_LIMIT: usize = 10;
+
+/// The number of diagnostics of each kind whose lines are kept. Further ones are only counted, so
+/// a corrupt log does not keep every one of its lines in memory.
+pub const MAX_DIAGNOSTICS_PER_KIND: usize = 10_000;
+
+/// The category of a problem found while parsing a line.
+#[cfg_attr(feature = "python", pyo3::prelude::pyclass)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// The line did not match the line regex.
+    RegexMiss,
+    /// The CAN ID could not be parsed.
+    BadId,
+    /// The data field contained invalid hex.
+    BadHex,
+    /// The data length is not valid for the frame type.
+    BadLength,
+    /// The timestamp could not be parsed.
+    BadTimestamp,
+    /// Another captured field, such as the direction or the CAN FD flags, could not be parsed.
+    BadField,
+    /// The message could not be looked up in the specification.
+    SpecLookup,
+    /// The line could not be read from the input.
+    Io,
+}
+
+impl DiagnosticKind {
+    /// Returns the name of the kind as used in serialized output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticKind::RegexMiss => "regex_miss",
+            DiagnosticKind::BadId => "bad_id",
+            DiagnosticKind::BadHex => "bad_hex",
+            DiagnosticKind::BadLength => "bad_length",
+            DiagnosticKind::BadTimestamp => "bad_timestamp",
+            DiagnosticKind::BadField => "bad_field",
+            DiagnosticKind::SpecLookup => "spec_lookup",
+            DiagnosticKind::Io => "io",
+        }
+    }
+}
+
+impl Display for DiagnosticKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An error found while parsing a single line, before the position of the line is attached.
+#[derive(Clone, Debug)]
+pub struct LineError {
+    /// The category of the error.
+    pub kind: DiagnosticKind,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl LineError {
+    /// Creates a new `LineError` of the given kind.
+    pub fn new(kind: DiagnosticKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Returns a function wrapping an error message in a `LineError` of the given kind, for use with `map_err`.
+    pub fn of(kind: DiagnosticKind) -> impl Fn(String) -> LineError {
+        move |message| LineError { kind, message }
+    }
+}
+
+impl Display for LineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A problem found while parsing a line of a log.
+#[cfg_attr(feature = "python", pyo3::prelude::pyclass(get_all))]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Diagnostic {
+    /// The file the line was read from, if it was read from a file.
+    pub source: Option<String>,
+    /// The 1-based number of the line.
+    pub line_number: u64,
+    /// The byte offset of the start of the line, if it was read from a file.
+    pub byte_offset: Option<u64>,
+    /// The raw line.
+    pub line: String,
+    /// The category of the problem.
+    pub kind: DiagnosticKind,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a new `Diagnostic` from the error found in a line and the position of the line.
+    pub fn new(
+        error: LineError,
+        line: String,
+        line_number: u64,
+        byte_offset: Option<u64>,
+        source: Option<&str>,
+    ) -> Self {
+        Self {
+            source: source.map(str::to_string),
+            line_number,
+            byte_offset,
+            line,
+            kind: error.kind,
+            message: error.message,
+        }
+    }
+}
+
+/// The problems found while parsing, along with the number found of each kind.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct Diagnostics {
+    /// The problems found, ordered by source and line number, up to `MAX_DIAGNOSTICS_PER_KIND` of
+    /// each kind.
+    pub entries: Vec<Diagnostic>,
+    /// The number of problems found of each kind.
+    pub counts: BTreeMap<DiagnosticKind, usize>,
+}
+
+impl Diagnostics {
+    /// Counts a diagnostic towards its kind, and keeps it unless `MAX_DIAGNOSTICS_PER_KIND` of
+    /// its kind are kept already.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        let count = self.counts.entry(diagnostic.kind).or_default();
+        *count += 1;
+        if *count <= MAX_DIAGNOSTICS_PER_KIND {
+            self.entries.push(diagnostic);
+        }
+    }
+
+    /// Returns the number of diagnostics found, including those that were only counted.
+    pub fn len(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Returns `true` if no problems were found.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the number of diagnostics of the given kind.
+    pub fn count(&self, kind: DiagnosticKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Adds the diagnostics of another parse, keeping the entries ordered.
+    ///
+    /// Only the incoming diagnostics are sorted, then merged into the entries, which are already
+    /// ordered, so repeated calls in append mode do not re-sort everything found so far. The
+    /// incoming entries are kept up to `MAX_DIAGNOSTICS_PER_KIND` of each kind.
+    pub fn extend(&mut self, mut other: Diagnostics) {
+        other.sort();
+        let mut kept = self.counts.clone();
+        other.entries.retain(|diagnostic| {
+            let count = kept.entry(diagnostic.kind).or_default();
+            *count += 1;
+            *count <= MAX_DIAGNOSTICS_PER_KIND
+        });
+        for (kind, count) in other.counts {
+            *self.counts.entry(kind).or_default() += count;
+        }
+        let entries = std::mem::take(&mut self.entries);
+        self.entries.reserve(entries.len() + other.entries.len());
+        let mut entries = entries.into_iter().peekable();
+        let mut incoming = other.entries.into_iter().peekable();
+        while let (Some(a), Some(b)) = (entries.peek(), incoming.peek()) {
+            // Ties keep the existing entry first, as a stable sort would
+            let next = if Self::order(b) < Self::order(a) {
+                incoming.next()
+            } else {
+                entries.next()
+            };
+            self.entries.extend(next);
+        }
+        self.entries.extend(entries);
+        self.entries.extend(incoming);
+    }
+
+    /// Orders the diagnostics by source and line number, since parallel parsing records them out of order.
+    pub fn sort(&mut self) {
+        self.entries
+            .sort_by(|a, b| Self::order(a).cmp(&Self::order(b)));
+    }
+
+    /// Returns the key diagnostics are ordered by.
+    fn order(diagnostic: &Diagnostic) -> (&Option<String>, u64) {
+        (&diagnostic.source, diagnostic.line_number)
+    }
+}
+
+impl Display for Diagnostics {
+    /// Displays the counts per kind followed by the first few diagnostics.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} line(s) could not be parsed:", self.len())?;
+        for (kind, count) in &self.counts {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        let shown = self.entries.len().min(DISPLAY_LIMIT);
+        for diagnostic in &self.entries[..shown] {
+            write!(f, "  ")?;
+            if let Some(source) = &diagnostic.source {
+                write!(f, "{}:", source)?;
+            }
+            write!(f, "line {}", diagnostic.line_number)?;
+            if let Some(byte_offset) = diagnostic.byte_offset {
+                write!(f, " (byte {})", byte_offset)?;
+            }
+            writeln!(
+                f,
+                ": {}: {}: {}",
+                diagnostic.kind, diagnostic.message, diagnostic.line
+            )?;
+        }
+        if self.len() > shown {
+            writeln!(f, "  ... and {} more", self.len() - shown)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::can_message::{
+    build_j1939_id, encode_j1939_data, parse_j1939_data, CANMessage, CANID, CAN_MAX_DLEN,
+};
+use crate::dbc::{encode_dbc_data, find_message, message_id};
+use crate::error::CANParserError;
+use crate::specification::{Annex, SpecPGN};
+use crate::CANParser;
+use std::collections::HashMap;
+
+/// The priority of encoded J1939 messages whose PGN has no default priority in the specification.
+pub const J1939_DEFAULT_PRIORITY: u8 = 6;
+
+/// The global destination address, used by encoded PDU1 messages without a destination address.
+pub const J1939_GLOBAL_ADDRESS: u8 = 255;
+
+impl CANParser {
+    /// Encodes physical SPN values into a J1939 message of the loaded J1939 specification.
+    ///
+    /// Each value is scaled by the resolution and offset of its SPN and packed at its start bit.
+    /// The SPNs without a value, and the bits no SPN covers, are set to all ones, J1939's "not
+    /// available". The payload is as long as the PGN, and its decoded values are filled in as by
+    /// `parse_line`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn` - The PGN of the message.
+    /// * `values` - The physical value of each SPN to encode, by SPN.
+    /// * `source_address` - The source address of the message.
+    /// * `destination_address` - The destination address of a PDU1 message. Defaults to the global
+    ///   address.
+    /// * `priority` - The priority of the message. Defaults to the priority of the PGN in the
+    ///   specification, or `J1939_DEFAULT_PRIORITY` if it has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::SpecError` if the PGN cannot be read from the specification, or a
+    /// `CANParserError::EncodeError` if no J1939 specification is loaded, the PGN is longer than a
+    /// single frame or a value cannot be encoded into its SPN.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, SPEC_TYPE_J1939};
+    /// use std::collections::HashMap;
+    ///
+    /// let annex = std::fs::read_to_string("path/to/annex.json").unwrap();
+    /// let annexes = HashMap::from([(SPEC_TYPE_J1939.to_string(), annex)]);
+    /// let parser = CANParser::new(ErrorPolicy::Strict, None, Some(annexes)).unwrap();
+    /// let message = parser
+    ///     .encode_j1939(61444, &HashMap::from([(190, 1500.0)]), 0x00, None, None)
+    ///     .unwrap();
+    /// ```
+    pub fn encode_j1939(
+        &self,
+        pgn: u16,
+        values: &HashMap<u16, f64>,
+        source_address: u8,
+        destination_address: Option<u8>,
+        priority: Option<u8>,
+    ) -> Result<CANMessage, CANParserError> {
+        let destination_address = destination_address.unwrap_or(J1939_GLOBAL_ADDRESS);
+        let id = build_j1939_id(
+            pgn,
+            J1939_DEFAULT_PRIORITY,
+            source_address,
+            destination_address,
+        );
+        let spec = self.j1939_pgn(&id)?;
+        let priority = priority.unwrap_or(match spec.priority {
+            0 => J1939_DEFAULT_PRIORITY,
+            priority => priority,
+        });
+
+        let length = match spec.length as usize {
+            0 => CAN_MAX_DLEN,
+            length => length,
+        };
+        if length > CAN_MAX_DLEN {
+            return Err(CANParserError::EncodeError(format!(
+                "PGN {} is {} bytes long and needs the transport protocol",
+                pgn, length
+            )));
+        }
+
+        let mut msg = CANMessage {
+            id: build_j1939_id(pgn, priority, source_address, destination_address),
+            ..Default::default()
+        };
+        msg.data.len = length as u8;
+        msg.data.data[..length].fill(0xFF);
+        encode_j1939_data(&mut msg.data.data[..length], &spec.spns, values)
+            .map_err(CANParserError::EncodeError)?;
+        parse_j1939_data(&mut msg.data, &spec.spns);
+        Ok(msg)
+    }
+
+    /// Encodes physical signal values into a message of the loaded DBC annex.
+    ///
+    /// Each value is scaled by the factor and offset of its signal and packed with the byte order
+    /// of the signal. The bits no encoded signal covers are set to all ones. The payload is as long
+    /// as the message. DBC messages are not decoded, so the message carries no signal values.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The name of the message in the DBC annex.
+    /// * `values` - The physical value of each signal to encode, by signal name.
+    /// * `source_address` - Replaces the source address of messages with an extended ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::EncodeError` if no DBC annex is loaded, it has no such message, the
+    /// message is longer than a single frame or a value cannot be encoded into its signal.
+    pub fn encode_dbc(
+        &self,
+        message: &str,
+        values: &HashMap<String, f64>,
+        source_address: Option<u8>,
+    ) -> Result<CANMessage, CANParserError> {
+        let annex = self
+            .specs
+            .as_ref()
+            .and_then(|specs| specs.j1939.as_ref())
+            .map(|spec| &spec.annex);
+        let dbc = match annex {
+            Some(Annex::Dbc(dbc)) => dbc,
+            _ => {
+                return Err(CANParserError::EncodeError(
+                    "No DBC annex is loaded".to_string(),
+                ))
+            }
+        };
+        let dbc_message = find_message(dbc, message).ok_or_else(|| {
+            CANParserError::EncodeError(format!("Message {} is not in the DBC annex", message))
+        })?;
+
+        let length = *dbc_message.message_size() as usize;
+        if length > CAN_MAX_DLEN {
+            return Err(CANParserError::EncodeError(format!(
+                "Message {} is {} bytes long and needs the transport protocol",
+                message, length
+            )));
+        }
+
+        let mut msg = CANMessage {
+            id: message_id(dbc_message, source_address),
+            ..Default::default()
+        };
+        msg.data.len = length as u8;
+        msg.data.data[..length].fill(0xFF);
+        encode_dbc_data(&mut msg.data.data[..length], dbc_message, values)
+            .map_err(CANParserError::EncodeError)?;
+        Ok(msg)
+    }
+
+    /// Returns the specification of the PGN of `id`, from `filtered_spec` or the J1939
+    /// specification.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::SpecError` if the PGN cannot be read from the specification, or a
+    /// `CANParserError::EncodeError` if no J1939 specification is loaded.
+    pub(crate) fn j1939_pgn(&self, id: &CANID) -> Result<SpecPGN, CANParserError> {
+        let cached = self
+            .filtered_spec
+            .j1939
+            .read()
+            .unwrap()
+            .get(&id.pgn)
+            .cloned();
+        match cached {
+            Some(spec) => Ok(spec),
+            None => self.load_j1939_pgn(id)?.ok_or_else(|| {
+                CANParserError::EncodeError("No J1939 specification is loaded".to_string())
+            }),
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 use csv::{IntoInnerError, Writer};
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+use crate::diagnostics::Diagnostics;
 use crate::specification::SpecError;
 
 
@@ -11,8 +13,15 @@ use crate::specification::SpecError;
 pub enum CANParserError {
     /// A fatal error occurred during parsing.
     ParserError(String),
-    /// A warning occurred during parsing.
-    ParserWarning(Vec<String>),
+    /// The line was not decoded because its message does not match the filter or it is a header
+    /// or comment line of the log format. It is not a failure.
+    LineSkipped(String),
+    /// Some lines could not be parsed. The other lines were parsed successfully.
+    ParserWarning(Arc<Diagnostics>),
+    /// Parsing was stopped by the error policy. Carries the diagnostics of the lines that failed.
+    ParseAborted(Arc<Diagnostics>),
+    /// Parsing was cancelled. Carries the diagnostics of the lines that failed before it stopped.
+    ParseCancelled(Arc<Diagnostics>),
     /// An error occurred during serialization.
     SerializationError(serde_json::Error),
     /// An error occurred during input/output operations.
@@ -27,9 +36,11 @@ pub enum CANParserError {
     /// An error occurred during JSON parsing.
     JsonError(String),
     /// An error occurred during CSV writing.
-    WriterError(IntoInnerError<Writer<Vec<u8>>>),
+    WriterError(Box<IntoInnerError<Writer<Vec<u8>>>>),
     /// An error occurred during regular expression parsing.
     RegexError(regex::Error),
+    /// Signal values could not be encoded into a frame.
+    EncodeError(String),
 }
 
 impl std::error::Error for CANParserError {}
@@ -38,13 +49,11 @@ impl Display for CANParserError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             CANParserError::ParserError(s) => write!(f, "Parser Error: {}", s),
-            CANParserError::ParserWarning(v) => {
-                writeln!(f, "Parser Warning:")?;
-                for s in v {
-                    writeln!(f, "{}", s)?;
-                }
-                Ok(())
-            },
+            CANParserError::LineSkipped(s) => write!(f, "Line Skipped: {}", s),
+            CANParserError::ParserWarning(d) => write!(f, "Parser Warning: {}", d),
+            CANParserError::ParseAborted(d) => write!(f, "Parsing Aborted: {}", d),
+            CANParserError::ParseCancelled(d) if d.is_empty() => write!(f, "Parsing Cancelled"),
+            CANParserError::ParseCancelled(d) => write!(f, "Parsing Cancelled: {}", d),
             CANParserError::SerializationError(e) => write!(f, "Serialization Error: {}", e),
             CANParserError::IOError(e) => write!(f, "IO Error: {}", e),
             CANParserError::CsvError(e) => write!(f, "CSV Error: {}", e),
@@ -54,6 +63,7 @@ impl Display for CANParserError {
             CANParserError::JsonError(s) => write!(f, "JSON Error: {}", s),
             CANParserError::WriterError(e) => write!(f, "Writer Error: {}", e),
             CANParserError::RegexError(e) => write!(f, "Regex Error: {}", e),
+            CANParserError::EncodeError(s) => write!(f, "Encode Error: {}", s),
         }
     }
 }
@@ -63,9 +73,10 @@ impl Into<JsValue> for CANParserError {
     fn into(self) -> JsValue {
         match self {
             CANParserError::ParserError(s) => s.into(),
-            CANParserError::ParserWarning(v) => {
-                v.into_iter().map(|s| s.to_string()).collect::<Vec<String>>().join("\n").into()
-            },
+            CANParserError::LineSkipped(s) => s.into(),
+            CANParserError::ParserWarning(d) => d.to_string().into(),
+            CANParserError::ParseAborted(d) => d.to_string().into(),
+            CANParserError::ParseCancelled(d) => d.to_string().into(),
             CANParserError::SerializationError(e) => e.to_string().into(),
             CANParserError::IOError(e) => e.to_string().into(),
             CANParserError::CsvError(e) => e.to_string().into(),
@@ -75,6 +86,7 @@ impl Into<JsValue> for CANParserError {
             CANParserError::JsonError(s) => s.into(),
             CANParserError::WriterError(e) => e.to_string().into(),
             CANParserError::RegexError(e) => e.to_string().into(),
+            CANParserError::EncodeError(s) => s.into(),
         }
     }
 }
@@ -106,7 +118,7 @@ impl From<rusqlite::Error> for CANParserError {
 
 impl From<IntoInnerError<Writer<Vec<u8>>>> for CANParserError {
     fn from(err: IntoInnerError<Writer<Vec<u8>>>) -> CANParserError {
-        CANParserError::WriterError(err)
+        CANParserError::WriterError(Box::new(err))
     }
 }
 
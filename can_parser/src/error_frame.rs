@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+// Error classes carried in the CAN ID of an error frame (see linux/can/error.h).
+pub const CAN_ERR_TX_TIMEOUT: u32 = 0x00000001;
+pub const CAN_ERR_LOSTARB: u32 = 0x00000002;
+pub const CAN_ERR_CRTL: u32 = 0x00000004;
+pub const CAN_ERR_PROT: u32 = 0x00000008;
+pub const CAN_ERR_TRX: u32 = 0x00000010;
+pub const CAN_ERR_ACK: u32 = 0x00000020;
+pub const CAN_ERR_BUSOFF: u32 = 0x00000040;
+pub const CAN_ERR_BUSERROR: u32 = 0x00000080;
+pub const CAN_ERR_RESTARTED: u32 = 0x00000100;
+pub const CAN_ERR_CNT: u32 = 0x00000200;
+
+// Controller problems, stored in data[1].
+const CAN_ERR_CRTL_STATUS: [(u8, &str); 7] = [
+    (0x01, "rx_overflow"),
+    (0x02, "tx_overflow"),
+    (0x04, "rx_warning"),
+    (0x08, "tx_warning"),
+    (0x10, "rx_passive"),
+    (0x20, "tx_passive"),
+    (0x40, "active"),
+];
+
+// Protocol violation types, stored in data[2].
+const CAN_ERR_PROT_TYPES: [(u8, &str); 8] = [
+    (0x01, "bit"),
+    (0x02, "form"),
+    (0x04, "stuff"),
+    (0x08, "bit0"),
+    (0x10, "bit1"),
+    (0x20, "overload"),
+    (0x40, "active"),
+    (0x80, "tx"),
+];
+
+// Protocol violation locations, stored in data[3].
+const CAN_ERR_PROT_LOCATIONS: [(u8, &str); 20] = [
+    (0x03, "sof"),
+    (0x02, "id28_21"),
+    (0x06, "id20_18"),
+    (0x04, "srtr"),
+    (0x05, "ide"),
+    (0x07, "id17_13"),
+    (0x0F, "id12_05"),
+    (0x0E, "id04_00"),
+    (0x0C, "rtr"),
+    (0x0D, "res1"),
+    (0x09, "res0"),
+    (0x0B, "dlc"),
+    (0x0A, "data"),
+    (0x08, "crc_seq"),
+    (0x18, "crc_del"),
+    (0x19, "ack"),
+    (0x1B, "ack_del"),
+    (0x1A, "eof"),
+    (0x12, "interm"),
+    (0x00, "unspecified"),
+];
+
+/// A decoded Linux SocketCAN error frame.
+///
+/// The error classes are taken from the CAN ID of the frame and the details from its data bytes,
+/// following the layout in `linux/can/error.h`.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct CANErrorFrame {
+    /// The raw error class bits from the CAN ID.
+    pub class: u32,
+    /// The transmission timed out.
+    pub tx_timeout: bool,
+    /// Arbitration was lost, at the bit given by `lost_arbitration_bit`.
+    pub lost_arbitration: bool,
+    /// The bit at which arbitration was lost. Zero if unspecified.
+    pub lost_arbitration_bit: u8,
+    /// The controller reported a problem, detailed in `controller_status`.
+    pub controller: bool,
+    /// The controller problems, e.g. `rx_overflow` or `tx_passive`.
+    pub controller_status: Vec<String>,
+    /// A protocol violation occurred, detailed in `protocol_types` and `protocol_location`.
+    pub protocol_violation: bool,
+    /// The kinds of protocol violation, e.g. `bit`, `form` or `stuff`.
+    pub protocol_types: Vec<String>,
+    /// Where in the frame the protocol violation occurred.
+    pub protocol_location: String,
+    /// The transceiver reported a problem. The raw status is kept in `transceiver_status`.
+    pub transceiver: bool,
+    /// The raw transceiver status byte.
+    pub transceiver_status: u8,
+    /// No acknowledgement was received on transmission.
+    pub no_ack: bool,
+    /// The controller went bus-off.
+    pub bus_off: bool,
+    /// A bus error occurred.
+    pub bus_error: bool,
+    /// The controller restarted.
+    pub restarted: bool,
+    /// The transmit error counter, if reported.
+    pub tx_error_count: Option<u8>,
+    /// The receive error counter, if reported.
+    pub rx_error_count: Option<u8>,
+}
+
+impl CANErrorFrame {
+    /// Decodes an error frame from its error class and data bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `class` - The CAN ID of the error frame with the error flag removed.
+    /// * `data` - The data bytes of the error frame. Missing bytes are treated as zero.
+    ///
+    /// # Returns
+    ///
+    /// The decoded `CANErrorFrame`.
+    pub fn from_frame(class: u32, data: &[u8]) -> Self {
+        let byte = |i: usize| data.get(i).copied().unwrap_or_default();
+        let mut frame = Self {
+            class,
+            tx_timeout: (class & CAN_ERR_TX_TIMEOUT) != 0,
+            lost_arbitration: (class & CAN_ERR_LOSTARB) != 0,
+            controller: (class & CAN_ERR_CRTL) != 0,
+            protocol_violation: (class & CAN_ERR_PROT) != 0,
+            transceiver: (class & CAN_ERR_TRX) != 0,
+            no_ack: (class & CAN_ERR_ACK) != 0,
+            bus_off: (class & CAN_ERR_BUSOFF) != 0,
+            bus_error: (class & CAN_ERR_BUSERROR) != 0,
+            restarted: (class & CAN_ERR_RESTARTED) != 0,
+            ..Default::default()
+        };
+        if frame.lost_arbitration {
+            frame.lost_arbitration_bit = byte(0);
+        }
+        if frame.controller {
+            frame.controller_status = Self::decode_bits(byte(1), &CAN_ERR_CRTL_STATUS);
+        }
+        if frame.protocol_violation {
+            frame.protocol_types = Self::decode_bits(byte(2), &CAN_ERR_PROT_TYPES);
+            frame.protocol_location = CAN_ERR_PROT_LOCATIONS
+                .iter()
+                .find(|(value, _)| *value == byte(3))
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("{:#04X}", byte(3)));
+        }
+        if frame.transceiver {
+            frame.transceiver_status = byte(4);
+        }
+        if (class & CAN_ERR_CNT) != 0 {
+            frame.tx_error_count = Some(byte(6));
+            frame.rx_error_count = Some(byte(7));
+        }
+        frame
+    }
+
+    /// Returns the names of the bits that are set in `value`.
+    fn decode_bits(value: u8, names: &[(u8, &str)]) -> Vec<String> {
+        names
+            .iter()
+            .filter(|(bit, _)| (value & bit) != 0)
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+}
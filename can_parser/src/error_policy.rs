@@ -0,0 +1,75 @@
+use crate::error::CANParserError;
+
+pub const ERROR_IGNORE: &str = "ignore";
+pub const ERROR_WARN: &str = "warn";
+pub const ERROR_STRICT: &str = "strict";
+pub const ERROR_WARN_UP_TO: &str = "warn_up_to";
+
+/// How the parser handles lines that cannot be parsed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Skip lines that cannot be parsed without recording them.
+    Ignore,
+    /// Skip lines that cannot be parsed and return their diagnostics in a `CANParserError::ParserWarning`.
+    #[default]
+    Warn,
+    /// Stop at the first line that cannot be parsed and return a `CANParserError::ParseAborted`.
+    Strict,
+    /// Warn for up to the given number of lines that cannot be parsed, then stop and return a
+    /// `CANParserError::ParseAborted`.
+    WarnUpTo(usize),
+}
+
+impl ErrorPolicy {
+    /// Creates an `ErrorPolicy` from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of "ignore", "warn", "strict" or "warn_up_to".
+    /// * `limit` - The number of lines "warn_up_to" warns about before stopping. Required by "warn_up_to" and ignored otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the name is unknown or "warn_up_to" has no limit.
+    pub fn from_name(name: &str, limit: Option<usize>) -> Result<Self, CANParserError> {
+        match name.to_ascii_lowercase().as_str() {
+            ERROR_IGNORE => Ok(ErrorPolicy::Ignore),
+            ERROR_WARN => Ok(ErrorPolicy::Warn),
+            ERROR_STRICT => Ok(ErrorPolicy::Strict),
+            ERROR_WARN_UP_TO => limit.map(ErrorPolicy::WarnUpTo).ok_or_else(|| {
+                CANParserError::ParserError(format!(
+                    "The \"{}\" error handling mode requires an error limit",
+                    ERROR_WARN_UP_TO
+                ))
+            }),
+            _ => Err(CANParserError::ParserError(format!(
+                "Unknown error handling mode: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Checks that the policy can be used by a parser.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the policy is `WarnUpTo(0)`, which is `Strict` under another name.
+    pub fn validate(&self) -> Result<(), CANParserError> {
+        match self {
+            ErrorPolicy::WarnUpTo(0) => Err(CANParserError::ParserError(format!(
+                "The error limit of \"{}\" must be at least 1, use \"{}\" to stop at the first error",
+                ERROR_WARN_UP_TO, ERROR_STRICT
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of lines that may fail before the parse is stopped, or `None` if it is never stopped.
+    pub fn limit(&self) -> Option<usize> {
+        match self {
+            ErrorPolicy::Ignore | ErrorPolicy::Warn => None,
+            ErrorPolicy::Strict => Some(0),
+            ErrorPolicy::WarnUpTo(limit) => Some(*limit),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::can_message::{
+    CANMessage, CANID, CAN_EFF_FLAG, CAN_EFF_ID_DIGITS, CAN_ERR_FLAG, CAN_RTR_FLAG,
+};
+use crate::error::CANParserError;
+use crate::LogFile;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// A SocketCAN style ID filter: a frame matches if `frame_id & mask == id & mask`.
+///
+/// Like SocketCAN, `id` and the frame ID include the `CAN_EFF_FLAG`, `CAN_RTR_FLAG` and
+/// `CAN_ERR_FLAG` bits, so a mask of `0x7FF` matches standard and extended frames alike, while
+/// `0xC00007FF` matches standard data frames only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdFilter {
+    /// The ID to match, with its flags.
+    pub id: u32,
+    /// The bits of the ID that must match.
+    pub mask: u32,
+    /// Whether the filter matches the frames that do not match `id` and `mask`.
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+impl IdFilter {
+    /// Returns `true` if the ID matches the filter.
+    pub fn matches(&self, id: &CANID) -> bool {
+        let mut raw = id.id;
+        if id.flags.ext {
+            raw |= CAN_EFF_FLAG;
+        }
+        if id.flags.rtr {
+            raw |= CAN_RTR_FLAG;
+        }
+        if id.flags.err {
+            raw |= CAN_ERR_FLAG;
+        }
+        (raw & self.mask == self.id & self.mask) != self.inverted
+    }
+}
+
+impl FromStr for IdFilter {
+    type Err = CANParserError;
+
+    /// Parses a filter in the syntax of candump: `<id>:<mask>` matches, `<id>~<mask>` is
+    /// inverted. Both are hexadecimal, and IDs written with 8 digits are extended.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || CANParserError::ParserError(format!("Invalid ID filter {}", s));
+        let (id, mask, inverted) = match (s.split_once(':'), s.split_once('~')) {
+            (Some((id, mask)), None) => (id, mask, false),
+            (None, Some((id, mask))) => (id, mask, true),
+            _ => return Err(err()),
+        };
+        let mut filter = IdFilter {
+            id: u32::from_str_radix(id, 16).map_err(|_| err())?,
+            mask: u32::from_str_radix(mask, 16).map_err(|_| err())?,
+            inverted,
+        };
+        if id.len() == CAN_EFF_ID_DIGITS {
+            filter.id |= CAN_EFF_FLAG;
+        }
+        Ok(filter)
+    }
+}
+
+/// Selects messages by ID, J1939 fields, timestamp and channel.
+///
+/// A message matches if it matches every criterion that is set. Set on a parser with
+/// `CANParser::set_filter`, it drops the other lines right after their ID is parsed, before any
+/// specification lookup or decoding.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageFilter {
+    /// ID filters of which at least one must match.
+    pub ids: Option<Vec<IdFilter>>,
+    /// The selected PGNs. Only extended frames have a PGN.
+    pub pgns: Option<Vec<u16>>,
+    /// The selected source addresses. Only extended frames have a source address.
+    pub source_addresses: Option<Vec<u8>>,
+    /// The selected destination addresses. Only extended frames have a destination address,
+    /// which is the global address 255 for PDU2 PGNs.
+    pub destination_addresses: Option<Vec<u8>>,
+    /// The first selected timestamp, in nanoseconds since the Unix epoch.
+    pub start: Option<i64>,
+    /// The timestamp the selection ends before, in nanoseconds since the Unix epoch.
+    pub end: Option<i64>,
+    /// The selected channels. Messages without a channel do not match.
+    pub channels: Option<Vec<String>>,
+}
+
+impl MessageFilter {
+    /// Returns `true` if the message matches the filter.
+    pub fn matches(&self, message: &CANMessage) -> bool {
+        self.matches_fields(&message.id, message.ts, message.channel.as_deref())
+    }
+
+    /// Returns `true` if a message with this ID, timestamp and channel matches the filter.
+    pub(crate) fn matches_fields(&self, id: &CANID, ts: i64, channel: Option<&str>) -> bool {
+        if let Some(ids) = &self.ids {
+            if !ids.iter().any(|filter| filter.matches(id)) {
+                return false;
+            }
+        }
+        let j1939 = id.flags.ext && !id.flags.err;
+        if !selects(&self.pgns, &id.pgn, j1939)
+            || !selects(&self.source_addresses, &id.sa, j1939)
+            || !selects(&self.destination_addresses, &id.da, j1939)
+        {
+            return false;
+        }
+        if let Some(channels) = &self.channels {
+            match channel {
+                Some(channel) if channels.iter().any(|c| c == channel) => {}
+                _ => return false,
+            }
+        }
+        self.start.is_none_or(|start| ts >= start) && self.end.is_none_or(|end| ts < end)
+    }
+
+    /// Returns the filter to apply to the lines of `file`, whose time offset and channel are
+    /// applied after the lines are parsed.
+    pub(crate) fn for_file(&self, file: &LogFile) -> MessageFilter {
+        let mut filter = self.clone();
+        // A bound the offset moves out of range is beyond every timestamp of the file
+        filter.start = self
+            .start
+            .map(|start| start.saturating_sub(file.time_offset));
+        filter.end = self.end.map(|end| end.saturating_sub(file.time_offset));
+        if let (Some(channels), Some(channel)) = (&self.channels, &file.channel) {
+            // Every message of the file gets its channel, so it matches all or none of them
+            filter.channels = match channels.contains(channel) {
+                true => None,
+                false => Some(Vec::new()),
+            };
+        }
+        filter
+    }
+}
+
+/// Returns `true` if no `values` are selected, or if a J1939 field has one of them.
+fn selects<T: PartialEq>(values: &Option<Vec<T>>, value: &T, j1939: bool) -> bool {
+    match values {
+        Some(values) => j1939 && values.contains(value),
+        None => true,
+    }
+}
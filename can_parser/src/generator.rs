@@ -0,0 +1,380 @@
+use crate::can_message::{build_j1939_id, CANMessage};
+use crate::encode::{J1939_DEFAULT_PRIORITY, J1939_GLOBAL_ADDRESS};
+use crate::error::CANParserError;
+use crate::log_writer::{write_log, LogWriterFormat};
+use crate::timestamp::{nanos_to_secs, NANOS_PER_SEC};
+use crate::CANParser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+/// The most frames generated for one message of a `TrafficConfig`, so that a long duration or a
+/// short period fails instead of exhausting memory.
+pub const MAX_FRAMES_PER_MESSAGE: i64 = 10_000_000;
+
+/// How the value of a generated signal evolves. Times are in seconds from the start of the log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "profile", rename_all = "snake_case")]
+pub enum SignalProfile {
+    /// A fixed value.
+    Constant { value: f64 },
+    /// Rises linearly from `start` to `end` over `period` seconds, then starts over.
+    Ramp { start: f64, end: f64, period: f64 },
+    /// Oscillates around `offset` as `offset + amplitude * sin(2π t / period)`.
+    Sine {
+        offset: f64,
+        amplitude: f64,
+        period: f64,
+    },
+    /// Starts at `start` and moves by a uniformly distributed step of at most `step` each frame,
+    /// staying within `min` and `max`.
+    RandomWalk {
+        start: f64,
+        step: f64,
+        min: f64,
+        max: f64,
+    },
+}
+
+impl SignalProfile {
+    /// Checks that the parameters of the profile are usable.
+    fn validate(&self) -> Result<(), String> {
+        match *self {
+            SignalProfile::Ramp { period, .. } | SignalProfile::Sine { period, .. }
+                if period.is_nan() || period <= 0.0 =>
+            {
+                Err(format!("The period {} must be positive", period))
+            }
+            SignalProfile::RandomWalk { min, max, .. }
+                if min.is_nan() || max.is_nan() || min > max =>
+            {
+                Err(format!("The minimum {} is above the maximum {}", min, max))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the value of the signal at `t`. Random walks move on from `previous`, their value in
+    /// the previous frame.
+    fn value(&self, t: f64, previous: Option<f64>, rng: &mut Rng) -> f64 {
+        match *self {
+            SignalProfile::Constant { value } => value,
+            SignalProfile::Ramp { start, end, period } => {
+                start + (end - start) * (t / period).fract()
+            }
+            SignalProfile::Sine {
+                offset,
+                amplitude,
+                period,
+            } => offset + amplitude * (2.0 * PI * t / period).sin(),
+            SignalProfile::RandomWalk {
+                start,
+                step,
+                min,
+                max,
+            } => match previous {
+                Some(previous) => (previous + step * (2.0 * rng.next_f64() - 1.0)).clamp(min, max),
+                None => start.clamp(min, max),
+            },
+        }
+    }
+}
+
+/// A message sent periodically in generated traffic.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TrafficMessage {
+    /// The PGN of a message of the J1939 annex. Set either `pgn` or `message`.
+    pub pgn: Option<u16>,
+    /// The name of a message of the DBC annex.
+    pub message: Option<String>,
+    /// The source address. Required by J1939 PGNs, and replaces the source address of DBC messages
+    /// with an extended ID.
+    pub source_address: Option<u8>,
+    /// The destination address of a PDU1 PGN. Defaults to the global address.
+    pub destination_address: Option<u8>,
+    /// The priority of a J1939 PGN. Defaults to the priority of the PGN in the annex.
+    pub priority: Option<u8>,
+    /// The time between two frames in milliseconds. Defaults to the transmission rate of the PGN
+    /// in the J1939 annex.
+    pub period_ms: Option<f64>,
+    /// The channel the frames are logged on.
+    pub channel: Option<String>,
+    /// The profile of each signal, by SPN for J1939 PGNs or by signal name for DBC messages.
+    /// Signals without a profile are sent as all ones, "not available" in J1939.
+    #[serde(default)]
+    pub signals: BTreeMap<String, SignalProfile>,
+}
+
+/// The configuration of generated traffic.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TrafficConfig {
+    /// The time of the first frames in nanoseconds since the Unix epoch.
+    #[serde(default)]
+    pub start: i64,
+    /// The length of the generated traffic in seconds.
+    pub duration: f64,
+    /// The seed of the random walks. A configuration always generates the same traffic.
+    #[serde(default)]
+    pub seed: u64,
+    /// The messages sent, each with its own period.
+    pub messages: Vec<TrafficMessage>,
+}
+
+impl TrafficConfig {
+    /// Reads a `TrafficConfig` from JSON, such as
+    /// `{"duration": 10, "messages": [{"pgn": 61444, "source_address": 0, "signals": {"190": {"profile": "ramp", "start": 600, "end": 2000, "period": 10}}}]}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::JsonError` if the JSON is not a valid configuration.
+    pub fn from_json(json: &str) -> Result<Self, CANParserError> {
+        serde_json::from_str(json).map_err(|e| CANParserError::JsonError(e.to_string()))
+    }
+}
+
+/// A small xorshift generator, so generated traffic is reproducible without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Mixed so that a seed of 0 does not give the all-zero state xorshift is stuck in
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Reads the period in milliseconds from a transmission rate such as `100 ms`, `1 s` or
+/// `engine speed dependent (10 ms)`, taking the first number followed by a unit.
+fn parse_period_ms(rate: &str) -> Option<f64> {
+    let bytes = rate.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+            i += 1;
+        }
+        let number = &rate[start..i];
+        let unit_start = i + rate[i..].len() - rate[i..].trim_start().len();
+        let unit: String = rate[unit_start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let scale = match unit.as_str() {
+            "ms" | "msec" => 1.0,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1000.0,
+            _ => continue,
+        };
+        if let Ok(value) = number.parse::<f64>() {
+            return Some(value * scale);
+        }
+    }
+    None
+}
+
+impl CANParser {
+    /// Generates periodic traffic from the loaded J1939 annex or DBC annex.
+    ///
+    /// Each message of `config` is sent every `period_ms` from `config.start` for
+    /// `config.duration` seconds, with its signals following their profiles and encoded as by
+    /// `encode_j1939` or `encode_dbc`. The frames of all messages are ordered by timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The messages to send, their periods and signal profiles.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::ParserError` if the configuration is invalid, a period is
+    /// missing or a message would have more than `MAX_FRAMES_PER_MESSAGE` frames, or the errors
+    /// of `encode_j1939` and `encode_dbc` if a value cannot be encoded.
+    pub fn generate_traffic(
+        &self,
+        config: &TrafficConfig,
+    ) -> Result<Vec<CANMessage>, CANParserError> {
+        if !config.duration.is_finite() || config.duration < 0.0 {
+            return Err(CANParserError::ParserError(format!(
+                "Invalid traffic duration: {}",
+                config.duration
+            )));
+        }
+        let mut rng = Rng::new(config.seed);
+        let mut messages = Vec::new();
+        for message in &config.messages {
+            self.generate_message(message, config, &mut rng, &mut messages)?;
+        }
+        // A stable sort keeps the messages in configuration order at equal times
+        messages.sort_by_key(|message| message.ts);
+        Ok(messages)
+    }
+
+    /// Generates traffic as by `generate_traffic` and writes it to a log file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The messages to send, their periods and signal profiles.
+    /// * `output_path` - The path of the log file, which is replaced if it exists.
+    /// * `format` - The format of the log file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `generate_traffic`, or a `CANParserError::IOError` if the log cannot
+    /// be written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LogWriterFormat, TrafficConfig, SPEC_TYPE_J1939};
+    /// use std::collections::HashMap;
+    ///
+    /// let annex = std::fs::read_to_string("path/to/annex.json").unwrap();
+    /// let annexes = HashMap::from([(SPEC_TYPE_J1939.to_string(), annex)]);
+    /// let parser = CANParser::new(ErrorPolicy::Strict, None, Some(annexes)).unwrap();
+    /// let config = TrafficConfig::from_json(
+    ///     r#"{"duration": 60, "messages": [{"pgn": 61444, "source_address": 0, "period_ms": 10,
+    ///         "signals": {"190": {"profile": "sine", "offset": 1200, "amplitude": 400, "period": 20}}}]}"#,
+    /// )
+    /// .unwrap();
+    /// parser.generate_log(&config, "path/to/traffic.log", LogWriterFormat::Candump).unwrap();
+    /// ```
+    pub fn generate_log(
+        &self,
+        config: &TrafficConfig,
+        output_path: &str,
+        format: LogWriterFormat,
+    ) -> Result<(), CANParserError> {
+        let messages = self.generate_traffic(config)?;
+        write_log(Some(output_path.to_string()), format, messages)?;
+        Ok(())
+    }
+
+    /// Generates the frames of one message of the traffic into `messages`.
+    fn generate_message(
+        &self,
+        message: &TrafficMessage,
+        config: &TrafficConfig,
+        rng: &mut Rng,
+        messages: &mut Vec<CANMessage>,
+    ) -> Result<(), CANParserError> {
+        let invalid = |e: String| CANParserError::ParserError(e);
+        let name = match (message.pgn, &message.message) {
+            (Some(pgn), None) => format!("PGN {}", pgn),
+            (None, Some(name)) => format!("message {}", name),
+            _ => {
+                return Err(invalid(
+                    "Each traffic message needs either a pgn or a message".to_string(),
+                ))
+            }
+        };
+        for (signal, profile) in &message.signals {
+            profile
+                .validate()
+                .map_err(|e| invalid(format!("Signal {} of {}: {}", signal, name, e)))?;
+        }
+        let spns = match message.pgn {
+            Some(_) => message
+                .signals
+                .keys()
+                .map(|spn| {
+                    spn.parse::<u16>()
+                        .map_err(|_| invalid(format!("Invalid SPN {} of {}", spn, name)))
+                })
+                .collect::<Result<Vec<u16>, CANParserError>>()?,
+            None => Vec::new(),
+        };
+        let source_address = match (message.pgn, message.source_address) {
+            (Some(_), None) => {
+                return Err(invalid(format!("{} needs a source_address", name)));
+            }
+            (_, source_address) => source_address,
+        };
+
+        let period_ms = match (message.period_ms, message.pgn) {
+            (Some(period_ms), _) => period_ms,
+            (None, Some(pgn)) => {
+                let id = build_j1939_id(pgn, J1939_DEFAULT_PRIORITY, 0, J1939_GLOBAL_ADDRESS);
+                let spec = self.j1939_pgn(&id)?;
+                let rate = String::from_utf8_lossy(&spec.transmission_rate)
+                    .trim_end_matches('\0')
+                    .to_string();
+                parse_period_ms(&rate).ok_or_else(|| {
+                    invalid(format!(
+                        "The transmission rate {} of {} has no period, set period_ms",
+                        rate, name
+                    ))
+                })?
+            }
+            (None, None) => {
+                return Err(invalid(format!(
+                    "DBC annexes have no transmission rates, set the period_ms of {}",
+                    name
+                )))
+            }
+        };
+        let period = (period_ms * 1_000_000.0).round() as i64;
+        if period <= 0 {
+            return Err(invalid(format!(
+                "Invalid period {} ms of {}",
+                period_ms, name
+            )));
+        }
+        let duration = (config.duration * NANOS_PER_SEC as f64).round() as i64;
+        let frames = duration / period + (duration % period != 0) as i64;
+        if frames > MAX_FRAMES_PER_MESSAGE {
+            return Err(invalid(format!(
+                "{} would send {} frames in {} s every {} ms, more than the {} allowed",
+                name, frames, config.duration, period_ms, MAX_FRAMES_PER_MESSAGE
+            )));
+        }
+
+        let mut previous: Vec<Option<f64>> = vec![None; message.signals.len()];
+        let mut offset = 0;
+        while offset < duration {
+            let t = nanos_to_secs(offset);
+            let values: Vec<f64> = message
+                .signals
+                .values()
+                .zip(previous.iter_mut())
+                .map(|(profile, previous)| {
+                    let value = profile.value(t, *previous, rng);
+                    *previous = Some(value);
+                    value
+                })
+                .collect();
+            let mut frame = match (message.pgn, &message.message) {
+                (Some(pgn), _) => {
+                    let values: HashMap<u16, f64> = spns.iter().copied().zip(values).collect();
+                    self.encode_j1939(
+                        pgn,
+                        &values,
+                        source_address.unwrap_or_default(),
+                        message.destination_address,
+                        message.priority,
+                    )?
+                }
+                (None, Some(dbc_message)) => {
+                    let values: HashMap<String, f64> =
+                        message.signals.keys().cloned().zip(values).collect();
+                    self.encode_dbc(dbc_message, &values, source_address)?
+                }
+                (None, None) => unreachable!(),
+            };
+            frame.ts = config.start + offset;
+            frame.channel = message.channel.clone();
+            messages.push(frame);
+            offset = offset.saturating_add(period);
+        }
+        Ok(())
+    }
+}
@@ -13,18 +13,22 @@ use std::io::{BufReader, Read};
 /// A struct representing the J1939 specification, which includes an annex.
 pub struct J1939Spec {
     pub annex: Annex,
+    /// The file the annex was read from, if it was read from a file.
+    pub source: Option<String>,
 }
 
 impl Default for J1939Spec {
     fn default() -> Self {
         Self {
             annex: Annex::Json(Map::new()),
+            source: None,
         }
     }
 }
 
 const PGN_DB_KEY: &str = "J1939PGNdb";
 const SPN_DB_KEY: &str = "J1939SPNdb";
+const PGN_LENGTH_VARIABLE: &str = "Variable";
 #[cfg(feature = "xlsx")]
 const SPG_SHEET_NAME: &str = "SPs & PGs";
 
@@ -41,7 +45,7 @@ impl Specification for J1939Spec {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```ignore
     /// use can_parser::j1939_spec::J1939Spec;
     ///
     /// let spec_path = "/path/to/spec.json";
@@ -51,70 +55,97 @@ impl Specification for J1939Spec {
     /// ```
     fn new(spec: &String) -> Result<Self, SpecError> {
         let (is_path, file_type) = determine_path_and_file_type(spec)?;
+        let source = if is_path { Some(spec.clone()) } else { None };
         match file_type {
             FileType::Json => {
                 let json_str;
                 if is_path {
                     json_str = read_to_string(&spec).map_err(|e| {
-                        SpecError(format!("Error reading specification file: {}", e))
+                        SpecError::new(format!("Error reading specification file: {}", e))
+                            .with_file(source.as_deref())
                     })?;
                 } else {
                     json_str = spec.clone();
                 }
-                let json: Value = serde_json::from_str(&json_str)
-                    .map_err(|e| SpecError(format!("Error parsing specification file: {}", e)))?;
+                let json: Value = serde_json::from_str(&json_str).map_err(|e| {
+                    SpecError::new(format!("Error parsing specification file: {}", e))
+                        .with_file(source.as_deref())
+                        .at(format!("line {}, column {}", e.line(), e.column()))
+                })?;
+                let json = match json {
+                    Value::Object(json) => json,
+                    _ => {
+                        return Err(SpecError::new("Expected a JSON object at the top level")
+                            .with_file(source.as_deref()))
+                    }
+                };
                 return Ok(Self {
-                    annex: Annex::Json(json.as_object().unwrap().clone()),
+                    annex: Annex::Json(json),
+                    source,
                 });
             }
             FileType::Xlsx => {
                 #[cfg(feature = "xlsx")]
                 {
                     if !is_path {
-                        return Err(SpecError(
-                            "Xlsx can only be read from file not from string.".to_string(),
+                        return Err(SpecError::new(
+                            "Xlsx can only be read from file not from string.",
                         ));
                     }
                     let mut workbook: Xlsx<_> = open_workbook(spec).map_err(|e| {
-                        SpecError(format!("Could not open Xlsx Digital Annex: {}", e))
+                        SpecError::new(format!("Could not open Xlsx Digital Annex: {}", e))
+                            .with_file(source.as_deref())
                     })?;
                     let range = workbook
                         .worksheet_range(SPG_SHEET_NAME)
                         .ok_or_else(|| {
-                            SpecError(format!(
+                            SpecError::new(format!(
                                 "Could not find {} sheet in Xlsx Digital Annex",
                                 SPG_SHEET_NAME
                             ))
+                            .with_file(source.as_deref())
                         })?
-                        .unwrap();
-                    return Ok(J1939Spec {
+                        .map_err(|e| {
+                            SpecError::new(format!(
+                                "Could not read {} sheet in Xlsx Digital Annex: {}",
+                                SPG_SHEET_NAME, e
+                            ))
+                            .with_file(source.as_deref())
+                        })?;
+                    Ok(J1939Spec {
                         annex: Annex::Xlsx(range),
-                    });
+                        source,
+                    })
                 }
                 #[cfg(not(feature = "xlsx"))]
                 {
-                    return Err(SpecError(
-                        "Xlsx support is not enabled. Please enable the Xlsx feature.".to_string(),
-                    ));
+                    Err(SpecError::new(
+                        "Xlsx support is not enabled. Please enable the Xlsx feature.",
+                    ))
                 }
             }
             FileType::Dbc => {
                 let mut dbc_slice = vec![];
                 if is_path {
                     let dbc_file = File::open(spec).map_err(|e| {
-                        SpecError(format!("Could not open DBC Digital Annex: {}", e))
+                        SpecError::new(format!("Could not open DBC Digital Annex: {}", e))
+                            .with_file(source.as_deref())
                     })?;
                     let mut dbc_reader = BufReader::new(dbc_file);
                     dbc_reader.read_to_end(&mut dbc_slice).map_err(|e| {
-                        SpecError(format!("Could not read DBC Digital Annex: {}", e))
+                        SpecError::new(format!("Could not read DBC Digital Annex: {}", e))
+                            .with_file(source.as_deref())
                     })?;
                 } else {
                     dbc_slice = spec.as_bytes().to_vec();
                 }
-                let dbc_annex = DBC::from_slice(&dbc_slice)
-                    .map_err(|_| SpecError("Could not parse DBC Digital Annex.".to_string()))?;
+                let dbc_annex = DBC::from_slice(&dbc_slice).map_err(|_| {
+                    SpecError::new("Could not parse DBC Digital Annex.")
+                        .with_file(source.as_deref())
+                })?;
                 return Ok(J1939Spec {
                     annex: Annex::Dbc(dbc_annex),
+                    source,
                 });
             }
         }
@@ -129,108 +160,207 @@ impl Specification for J1939Spec {
     /// # Returns
     ///
     /// * `Ok(Metadata)` - A `Metadata` enum variant containing the parsed J1939 metadata.
-    /// * `Err(SpecError)` - A `SpecError` struct containing an error message if parsing fails.
+    /// * `Err(SpecError)` - A `SpecError` naming the PGN, SPN and field that could not be read.
     fn get_id_metadata(&self, id: &CANID) -> Result<Metadata, SpecError> {
-        let mut aux_info = Metadata::J1939(SpecPGN::default());
-        if let Metadata::J1939(ref mut spec_pgn) = aux_info {
-            match &self.annex {
-                #[cfg(feature = "xlsx")]
-                Annex::Xlsx(a) => {
-                    let mut got_pgn_info = false;
-                    for i in a.rows() {
-                        // Check if pgn is equal to any cell in the row
-                        if let Some(cell) = i.get(4) {
-                            if let Some(read_pgn) = cell.get_float() {
-                                if i64::from(id.pgn) == (read_pgn as i64) {
-                                    self.parse_row_for_pgn_info_xlsx(
-                                        &mut got_pgn_info,
-                                        spec_pgn,
-                                        i,
-                                    );
-                                    spec_pgn.spns.insert(
-                                        i.get(19).unwrap().get_int().unwrap_or_default() as u16,
-                                        self.parse_j1939_spns_xlsx(i),
-                                    );
-                                    continue;
-                                }
-                                if got_pgn_info {
-                                    break;
+        self.lookup_id_metadata(id, None)
+    }
+
+    /// Retrieves metadata for a given CAN ID, skipping the SPNs that cannot be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A reference to a `CANID` struct representing the CAN ID to retrieve metadata for.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((Metadata, Vec<SpecError>))` - The parsed J1939 metadata and the errors of the skipped SPNs.
+    /// * `Err(SpecError)` - A `SpecError` if the PGN itself could not be read.
+    fn get_id_metadata_skipping_invalid(
+        &self,
+        id: &CANID,
+    ) -> Result<(Metadata, Vec<SpecError>), SpecError> {
+        let mut skipped = Vec::new();
+        let metadata = self.lookup_id_metadata(id, Some(&mut skipped))?;
+        Ok((metadata, skipped))
+    }
+}
+
+impl J1939Spec {
+    /// Retrieves metadata for a given CAN ID by parsing the J1939 specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A reference to a `CANID` struct representing the CAN ID to retrieve metadata for.
+    /// * `skipped` - Collects the errors of SPNs that could not be read. If `None`, the first such error is returned.
+    fn lookup_id_metadata(
+        &self,
+        id: &CANID,
+        mut skipped: Option<&mut Vec<SpecError>>,
+    ) -> Result<Metadata, SpecError> {
+        let mut spec_pgn = SpecPGN::default();
+        let with_pgn = |e: SpecError| e.with_pgn(id.pgn as u32);
+        match &self.annex {
+            #[cfg(feature = "xlsx")]
+            Annex::Xlsx(a) => {
+                let first_row = a.start().map(|(row, _)| row as usize).unwrap_or_default();
+                let mut got_pgn_info = false;
+                for (index, i) in a.rows().enumerate() {
+                    // Spreadsheet rows are numbered from 1
+                    let row = first_row + index + 1;
+                    // Check if pgn is equal to any cell in the row
+                    if let Some(cell) = i.get(4) {
+                        if let Some(read_pgn) = cell.get_float() {
+                            if i64::from(id.pgn) == (read_pgn as i64) {
+                                self.parse_row_for_pgn_info_xlsx(
+                                    &mut got_pgn_info,
+                                    &mut spec_pgn,
+                                    i,
+                                    row,
+                                )
+                                .map_err(with_pgn)?;
+                                let spn_number = self
+                                    .xlsx_cell(i, row, 19, "SPN")
+                                    .map_err(with_pgn)?
+                                    .get_int()
+                                    .unwrap_or_default()
+                                    as u16;
+                                match self.parse_j1939_spns_xlsx(i, row) {
+                                    Ok(spn) => {
+                                        spec_pgn.spns.insert(spn_number, spn);
+                                    }
+                                    Err(e) => skip_or_fail(
+                                        skipped.as_deref_mut(),
+                                        with_pgn(e.with_spn(spn_number as u32)),
+                                    )?,
                                 }
+                                continue;
+                            }
+                            if got_pgn_info {
+                                break;
                             }
                         }
                     }
                 }
-                Annex::Json(a) => {
-                    let pgn_annex = a.get(PGN_DB_KEY).ok_or_else(|| {
-                        SpecError("Could not find PGN database in JSON Digital Annex".to_string())
-                    })?;
-                    let pgn_annex = pgn_annex.as_object().ok_or_else(|| {
-                        SpecError("Could not find PGN database in JSON Digital Annex".to_string())
-                    })?;
-                    if let Some(pgn_data) = pgn_annex.get(id.pgn.to_string().as_str()) {
-                        self.parse_row_for_pgn_info_json(pgn_data, spec_pgn);
-                        let spns = pgn_data.get("SPNs").unwrap().as_array().unwrap();
-                        let spn_annex: &Value = a.get(SPN_DB_KEY).ok_or_else(|| {
-                            SpecError(
-                                "Could not find SPN database in JSON Digital Annex".to_string(),
-                            )
-                        })?;
-                        let spn_annex = spn_annex.as_object().ok_or_else(|| {
-                            SpecError(
-                                "Could not find SPN database in JSON Digital Annex".to_string(),
-                            )
-                        })?;
-                        if let Some(spn_start_bit) = pgn_data.get("SPNStartBits") {
-                            self.parse_j1939_spns_json(
-                                &spn_annex,
-                                spec_pgn,
-                                spns,
-                                spn_start_bit.as_array().unwrap(),
-                            );
-                        } else {
-                            self.parse_j1939_spns_json(&spn_annex, spec_pgn, spns, &Vec::new());
+            }
+            Annex::Json(a) => {
+                let pgn_annex = self.json_object(a, "", PGN_DB_KEY)?;
+                let pgn_key = id.pgn.to_string();
+                if let Some(pgn_data) = pgn_annex.get(pgn_key.as_str()) {
+                    let pgn_path = format!("{}.{}", PGN_DB_KEY, pgn_key);
+                    let pgn_data = pgn_data
+                        .as_object()
+                        .ok_or_else(|| self.json_error("Expected an object", PGN_DB_KEY, &pgn_key))
+                        .map_err(with_pgn)?;
+                    self.parse_row_for_pgn_info_json(
+                        pgn_data,
+                        &pgn_path,
+                        &mut spec_pgn,
+                        skipped.as_deref_mut(),
+                    )
+                    .map_err(with_pgn)?;
+                    let spns = self
+                        .json_array(pgn_data, &pgn_path, "SPNs")
+                        .map_err(with_pgn)?;
+                    let spn_annex = self.json_object(a, "", SPN_DB_KEY)?;
+                    let spn_start_bits: &[Value] = match pgn_data.get("SPNStartBits") {
+                        Some(_) => self
+                            .json_array(pgn_data, &pgn_path, "SPNStartBits")
+                            .map_err(with_pgn)?,
+                        None if spns.is_empty() => &[],
+                        None => {
+                            let e = self.json_error("Missing field", &pgn_path, "SPNStartBits");
+                            skip_or_fail(skipped.as_deref_mut(), with_pgn(e))?;
+                            &[]
+                        }
+                    };
+                    if !spn_start_bits.is_empty() && spn_start_bits.len() != spns.len() {
+                        let e = self.json_error(
+                            &format!(
+                                "Expected one start bit per SPN, found {} start bits for {} SPNs",
+                                spn_start_bits.len(),
+                                spns.len()
+                            ),
+                            &pgn_path,
+                            "SPNStartBits",
+                        );
+                        skip_or_fail(skipped.as_deref_mut(), with_pgn(e))?;
+                    }
+                    for (index, (spn, start_bit)) in spns.iter().zip(spn_start_bits).enumerate() {
+                        let result =
+                            self.json_spn_number(spn, &pgn_path, index)
+                                .and_then(|spn_number| {
+                                    self.parse_j1939_spn_json(
+                                        spn_annex, &pgn_path, index, spn_number, start_bit,
+                                    )
+                                    .map(|spec_spn| (spn_number, spec_spn))
+                                    .map_err(|e| e.with_spn(spn_number as u32))
+                                });
+                        match result {
+                            Ok((spn_number, spec_spn)) => {
+                                spec_pgn.spns.insert(spn_number, spec_spn);
+                            }
+                            Err(e) => skip_or_fail(skipped.as_deref_mut(), with_pgn(e))?,
                         }
                     }
-                }
-                _ => {
-                    return Err(SpecError("Annex type not supported".to_string()));
                 }
             }
+            _ => {
+                return Err(
+                    SpecError::new("Annex type not supported").with_file(self.source.as_deref())
+                );
+            }
         }
-        return Ok(aux_info);
+        Ok(Metadata::J1939(spec_pgn))
     }
-}
 
-impl J1939Spec {
     /// Parses a row of PGN information in JSON format and updates the `SpecPGN` struct with the parsed information.
     ///
     /// # Arguments
     ///
-    /// * `pgn_data` - A reference to a `Value` object containing the PGN information in JSON format.
+    /// * `pgn_data` - A reference to the JSON object containing the PGN information.
+    /// * `path` - The JSON path of `pgn_data`, used in errors.
     /// * `aux_info` - A mutable reference to a `SpecPGN` object that will be updated with the parsed information.
-    fn parse_row_for_pgn_info_json(&self, pgn_data: &Value, aux_info: &mut SpecPGN) {
+    /// * `skipped` - Collects the error of an invalid `PGNLength`, which is then read as 0. If `None`, the error is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SpecError` naming the field if a field is missing or has the wrong type.
+    fn parse_row_for_pgn_info_json(
+        &self,
+        pgn_data: &Map<String, Value>,
+        path: &str,
+        aux_info: &mut SpecPGN,
+        skipped: Option<&mut Vec<SpecError>>,
+    ) -> Result<(), SpecError> {
         self.string_to_slice(
-            pgn_data.get("Name").unwrap().to_owned().to_string(),
+            self.json_field(pgn_data, path, "Name")?.to_string(),
             &mut aux_info.label,
             32,
         );
         self.string_to_slice(
-            pgn_data.get("Label").unwrap().to_owned().to_string(),
+            self.json_field(pgn_data, path, "Label")?.to_string(),
             &mut aux_info.acronym,
             10,
         );
-        aux_info.length = pgn_data
-            .get("PGNLength")
-            .unwrap()
+        let length = self
+            .json_field(pgn_data, path, "PGNLength")?
             .as_str()
-            .unwrap()
-            .parse()
-            .unwrap_or_default();
+            .ok_or_else(|| self.json_error("Expected a string", path, "PGNLength"));
+        // Variable length PGNs have no fixed length
+        aux_info.length = match length {
+            Ok(PGN_LENGTH_VARIABLE) => Ok(0),
+            Ok(length) => length
+                .parse()
+                .map_err(|_| self.json_error("Expected a length in bytes", path, "PGNLength")),
+            Err(e) => Err(e),
+        }
+        .or_else(|e| skip_or_fail(skipped, e).map(|_| 0))?;
         self.string_to_slice(
-            pgn_data.get("Rate").unwrap().to_owned().to_string(),
+            self.json_field(pgn_data, path, "Rate")?.to_string(),
             &mut aux_info.transmission_rate,
             50,
         );
+        Ok(())
     }
 
     /// Parses a row for PGN information from an xlsx file.
@@ -240,168 +370,188 @@ impl J1939Spec {
     /// * `got_pgn_info` - A mutable reference to a boolean indicating whether PGN information has been obtained.
     /// * `aux_info` - A mutable reference to a `SpecPGN` struct containing auxiliary PGN information.
     /// * `i` - A slice of `DataType` containing the row data to be parsed.
+    /// * `row` - The spreadsheet row number of `i`, used in errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SpecError` naming the cell if a cell is missing or has the wrong type.
     #[cfg(feature = "xlsx")]
     fn parse_row_for_pgn_info_xlsx(
         &self,
         got_pgn_info: &mut bool,
         aux_info: &mut SpecPGN,
         i: &[DataType],
-    ) {
+        row: usize,
+    ) -> Result<(), SpecError> {
         if !got_pgn_info.to_owned() {
             self.string_to_slice(
-                i.get(5).unwrap().get_string().unwrap().to_owned(),
+                self.xlsx_string(i, row, 5, "Parameter Group Label")?
+                    .to_owned(),
                 &mut aux_info.label,
                 32,
             );
             self.string_to_slice(
-                i.get(6).unwrap().get_string().unwrap().to_owned(),
+                self.xlsx_string(i, row, 6, "Acronym")?.to_owned(),
                 &mut aux_info.acronym,
                 10,
             );
-            aux_info.description = i.get(7).unwrap().get_string().unwrap_or_default().to_owned();
-            aux_info.pdu_format = i.get(10).unwrap().get_int().unwrap_or_default() as u8;
-            aux_info.pdu_specific = i.get(11).unwrap().get_int().unwrap_or_default() as u8;
-            aux_info.priority = i.get(15).unwrap().get_int().unwrap_or_default() as u8;
-            aux_info.length = i.get(14).unwrap().get_int().unwrap_or_default() as u8;
+            let cell = |column: usize, field: &str| self.xlsx_cell(i, row, column, field);
+            aux_info.description = cell(7, "PGN Description")?
+                .get_string()
+                .unwrap_or_default()
+                .to_owned();
+            aux_info.pdu_format = cell(10, "PDU Format")?.get_int().unwrap_or_default() as u8;
+            aux_info.pdu_specific = cell(11, "PDU Specific")?.get_int().unwrap_or_default() as u8;
+            aux_info.priority = cell(15, "Default Priority")?.get_int().unwrap_or_default() as u8;
+            aux_info.length = cell(14, "PGN Data Length")?.get_int().unwrap_or_default() as u8;
             self.string_to_slice(
-                i.get(13).unwrap().get_string().unwrap_or_default().to_owned(),
+                cell(13, "Transmission Rate")?
+                    .get_string()
+                    .unwrap_or_default()
+                    .to_owned(),
                 &mut aux_info.transmission_rate,
                 50,
             );
             *got_pgn_info = true;
         }
+        Ok(())
     }
 
-    /// Parses a list of J1939 SPNs in JSON format and updates the provided `SpecPGN` object with the parsed information.
+    /// Parses a J1939 SPN in JSON format.
     ///
     /// # Arguments
     ///
     /// * `annex` - A reference to a `Map<String, Value>` object containing the annex information for the SPNs.
-    /// * `aux_info` - A mutable reference to a `SpecPGN` object that will be updated with the parsed SPN information.
-    /// * `spns` - A reference to a `Vec<Value>` object containing the SPNs to be parsed.
-    /// * `spn_start_bit` - A reference to a `Vec<Value>` object containing the start bit information for each SPN.
-    fn parse_j1939_spns_json(
+    /// * `pgn_path` - The JSON path of the PGN listing the SPN, used in errors.
+    /// * `index` - The position of the SPN in the PGN's `SPNs` list.
+    /// * `spn_number` - The SPN number as listed by the PGN.
+    /// * `start_bit` - The start bit of the SPN as listed by the PGN, negative if it has no fixed
+    ///   position.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SpecError` naming the field if the SPN is not in the SPN database, or a field is
+    /// missing or has the wrong type.
+    fn parse_j1939_spn_json(
         &self,
         annex: &Map<String, Value>,
-        aux_info: &mut SpecPGN,
-        spns: &Vec<Value>,
-        spn_start_bit: &Vec<Value>,
-    ) {
-        for (spn, start_bit) in spns.iter().zip(spn_start_bit.iter()) {
-            let spn_name = spn.to_string();
-            let start;
-            if let Some(start_t) = start_bit.as_array() {
-                start = start_t.get(0).unwrap().as_i64().unwrap_or_default();
-            } else {
-                start = start_bit.as_i64().unwrap_or_default();
-            }
-            let spn_t;
-            if let Some(spn_tt) = annex.get(&spn_name) {
-                spn_t = spn_tt.as_object().unwrap_or_else(|| {
-                    panic!("SPN {} not object in J1939SPNdb", spn_name.as_str())
-                });
-            } else {
-                continue;
-            }
-            let mut spn = SpecSPN {
-                label: [0u8; 32],
-                description: "".to_string(),
-                units: [0u8; 10],
-                length: spn_t.get("SPNLength").unwrap().as_i64().unwrap_or_default() as u8,
-                resolution: spn_t
-                    .get("Resolution")
-                    .unwrap()
-                    .as_f64()
-                    .unwrap_or_default() as f32,
-                offset: spn_t.get("Offset").unwrap().as_f64().unwrap_or_default() as f32,
-                max: spn_t
-                    .get("OperationalHigh")
-                    .unwrap()
-                    .as_f64()
-                    .unwrap_or_default() as f32,
-                start_bit: 0,
-                spn_type: [0; 8],
-            };
-            self.string_to_slice(
-                spn_t
-                    .get("Name")
-                    .unwrap()
-                    .as_str()
-                    .unwrap_or_default()
-                    .to_owned(),
-                &mut spn.label,
-                32,
-            );
-            self.string_to_slice(
-                spn_t
-                    .get("Units")
-                    .unwrap()
-                    .as_str()
-                    .unwrap_or_default()
-                    .to_owned(),
-                &mut spn.units,
-                10,
-            );
-            if start >= 0 {
-                spn.start_bit = start as u8;
-            }
-            aux_info
-                .spns
-                .insert(spn_name.parse::<u16>().unwrap_or_default(), spn);
+        pgn_path: &str,
+        index: usize,
+        spn_number: u16,
+        start_bit: &Value,
+    ) -> Result<SpecSPN, SpecError> {
+        let start_field = format!("SPNStartBits[{}]", index);
+        let start_bit = match start_bit.as_array() {
+            Some(start_bits) => start_bits.first().ok_or_else(|| {
+                self.json_error("Expected a non-empty list", pgn_path, &start_field)
+            })?,
+            None => start_bit,
+        };
+        // The annex lists -1 for SPNs without a fixed position, which keep start bit 0
+        let start = start_bit
+            .as_i64()
+            .and_then(|start| u8::try_from(start.max(0)).ok())
+            .ok_or_else(|| {
+                self.json_error("Expected a start bit up to 255", pgn_path, &start_field)
+            })?;
+        let spn_name = spn_number.to_string();
+        let spn_t = self.json_object(annex, SPN_DB_KEY, &spn_name)?;
+        let path = format!("{}.{}", SPN_DB_KEY, spn_name);
+        let length = self.json_number(spn_t, &path, "SPNLength")?;
+        if !(0.0..=u8::MAX as f64).contains(&length) || length.fract() != 0.0 {
+            return Err(self.json_error(
+                "Expected a length from 0 to 255 bits",
+                &path,
+                "SPNLength",
+            ));
         }
-    }
-
-    /// Parses J1939 SPNs from an XLSX file.
-    #[cfg(feature = "xlsx")]
-    fn parse_j1939_spns_xlsx(&self, i: &[DataType]) -> SpecSPN {
         let mut spn = SpecSPN {
             label: [0u8; 32],
-            description: i
-                .get(21)
-                .unwrap()
-                .get_string()
-                .unwrap_or_default()
-                .to_owned(),
+            description: "".to_string(),
             units: [0u8; 10],
-            length: i.get(35).unwrap().get_int().unwrap_or_default() as u8,
-            resolution: i.get(32).unwrap().get_float().unwrap_or_default() as f32,
-            offset: i.get(33).unwrap().get_float().unwrap_or_default() as f32,
-            max: i.get(34).unwrap().get_float().unwrap_or_default() as f32,
-            start_bit: 0,
-            spn_type: [0u8; 8],
+            length: length as u8,
+            resolution: self.json_number(spn_t, &path, "Resolution")? as f32,
+            offset: self.json_number(spn_t, &path, "Offset")? as f32,
+            max: self.json_number(spn_t, &path, "OperationalHigh")? as f32,
+            start_bit: start,
+            spn_type: [0; 8],
         };
         self.string_to_slice(
-            i.get(20)
-                .unwrap()
-                .get_string()
-                .unwrap_or_default()
-                .to_owned(),
+            self.json_string(spn_t, &path, "Name")?.to_owned(),
             &mut spn.label,
             32,
         );
         self.string_to_slice(
-            i.get(27)
-                .unwrap()
-                .get_string()
-                .unwrap_or_default()
-                .to_owned(),
+            self.json_string(spn_t, &path, "Units")?.to_owned(),
             &mut spn.units,
             10,
         );
-        self.string_to_slice(
-            i.get(30)
-                .unwrap()
+        Ok(spn)
+    }
+
+    /// Returns the SPN number at `index` of a PGN's `SPNs` list, or a `SpecError` if it is not an
+    /// integer from 0 to 65535.
+    fn json_spn_number(&self, spn: &Value, pgn_path: &str, index: usize) -> Result<u16, SpecError> {
+        spn.as_u64()
+            .and_then(|spn| u16::try_from(spn).ok())
+            .ok_or_else(|| {
+                self.json_error(
+                    "Expected an SPN number from 0 to 65535",
+                    pgn_path,
+                    &format!("SPNs[{}]", index),
+                )
+            })
+    }
+
+    /// Parses J1939 SPNs from an XLSX file.
+    ///
+    /// # Arguments
+    ///
+    /// * `i` - A slice of `DataType` containing the row data to be parsed.
+    /// * `row` - The spreadsheet row number of `i`, used in errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SpecError` naming the cell if a cell is missing or the start position is invalid.
+    #[cfg(feature = "xlsx")]
+    fn parse_j1939_spns_xlsx(&self, i: &[DataType], row: usize) -> Result<SpecSPN, SpecError> {
+        let cell = |column: usize, field: &str| self.xlsx_cell(i, row, column, field);
+        let text = |column: usize, field: &str| -> Result<String, SpecError> {
+            Ok(cell(column, field)?
                 .get_string()
                 .unwrap_or_default()
-                .to_owned(),
-            &mut spn.spn_type,
-            8,
-        );
-        let start_bit = i.get(18).unwrap().get_float().unwrap_or_default();
+                .to_owned())
+        };
+        let mut spn = SpecSPN {
+            label: [0u8; 32],
+            description: text(21, "SP Description")?,
+            units: [0u8; 10],
+            length: cell(35, "SP Length")?.get_int().unwrap_or_default() as u8,
+            resolution: cell(32, "Scaling")?.get_float().unwrap_or_default() as f32,
+            offset: cell(33, "Offset")?.get_float().unwrap_or_default() as f32,
+            max: cell(34, "Operational High")?
+                .get_float()
+                .unwrap_or_default() as f32,
+            start_bit: 0,
+            spn_type: [0u8; 8],
+        };
+        self.string_to_slice(text(20, "SP Label")?, &mut spn.label, 32);
+        self.string_to_slice(text(27, "Units")?, &mut spn.units, 10);
+        self.string_to_slice(text(30, "SP Type")?, &mut spn.spn_type, 8);
+        let start_bit = cell(18, "SP Position in PG")?
+            .get_float()
+            .unwrap_or_default();
         if start_bit != 0.0 {
-            spn.start_bit = self.start_bit_to_offset(start_bit);
+            spn.start_bit = self.start_bit_to_offset(start_bit).ok_or_else(|| {
+                self.xlsx_error(
+                    &format!("Invalid start position {}", start_bit),
+                    row,
+                    18,
+                    "SP Position in PG",
+                )
+            })?;
         }
-        return spn;
+        Ok(spn)
     }
 
     /// Calculates the bit offset of a given start bit.
@@ -412,12 +562,131 @@ impl J1939Spec {
     ///
     /// # Returns
     ///
-    /// The bit offset of the given start bit.
+    /// The bit offset of the given start bit, or `None` if the start bit is before the first bit.
     #[cfg(feature = "xlsx")]
-    fn start_bit_to_offset(&self, start_bit: f64) -> u8 {
-        let byte_offset = (start_bit.trunc() as u8) - 1;
-        let bit_offset = ((start_bit.fract() * 8.0).round() as u8) - 1;
-        byte_offset * 8 + bit_offset
+    fn start_bit_to_offset(&self, start_bit: f64) -> Option<u8> {
+        let byte_offset = (start_bit.trunc() as u8).checked_sub(1)?;
+        let bit_offset = ((start_bit.fract() * 8.0).round() as u8).checked_sub(1)?;
+        byte_offset.checked_mul(8)?.checked_add(bit_offset)
+    }
+
+    /// Returns a `SpecError` naming the annex file and the JSON path of `field`.
+    fn json_error(&self, message: &str, path: &str, field: &str) -> SpecError {
+        let location = if path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", path, field)
+        };
+        SpecError::new(message)
+            .with_file(self.source.as_deref())
+            .at(location)
+            .with_field(field)
+    }
+
+    /// Returns the `field` of a JSON object, or a `SpecError` if it is missing.
+    fn json_field<'a>(
+        &self,
+        object: &'a Map<String, Value>,
+        path: &str,
+        field: &str,
+    ) -> Result<&'a Value, SpecError> {
+        object
+            .get(field)
+            .ok_or_else(|| self.json_error("Missing field", path, field))
+    }
+
+    /// Returns the `field` of a JSON object as a number, 0 if it is null, or a `SpecError` if it is
+    /// missing or not a number.
+    fn json_number(
+        &self,
+        object: &Map<String, Value>,
+        path: &str,
+        field: &str,
+    ) -> Result<f64, SpecError> {
+        match self.json_field(object, path, field)? {
+            Value::Null => Ok(0.0),
+            value => value
+                .as_f64()
+                .ok_or_else(|| self.json_error("Expected a number", path, field)),
+        }
+    }
+
+    /// Returns the `field` of a JSON object as a string, "" if it is null, or a `SpecError` if it is
+    /// missing or not a string.
+    fn json_string<'a>(
+        &self,
+        object: &'a Map<String, Value>,
+        path: &str,
+        field: &str,
+    ) -> Result<&'a str, SpecError> {
+        match self.json_field(object, path, field)? {
+            Value::Null => Ok(""),
+            value => value
+                .as_str()
+                .ok_or_else(|| self.json_error("Expected a string", path, field)),
+        }
+    }
+
+    /// Returns the `field` of a JSON object as an object, or a `SpecError` if it is missing or not an object.
+    fn json_object<'a>(
+        &self,
+        object: &'a Map<String, Value>,
+        path: &str,
+        field: &str,
+    ) -> Result<&'a Map<String, Value>, SpecError> {
+        self.json_field(object, path, field)?
+            .as_object()
+            .ok_or_else(|| self.json_error("Expected an object", path, field))
+    }
+
+    /// Returns the `field` of a JSON object as an array, or a `SpecError` if it is missing or not an array.
+    fn json_array<'a>(
+        &self,
+        object: &'a Map<String, Value>,
+        path: &str,
+        field: &str,
+    ) -> Result<&'a [Value], SpecError> {
+        self.json_field(object, path, field)?
+            .as_array()
+            .map(|array| array.as_slice())
+            .ok_or_else(|| self.json_error("Expected an array", path, field))
+    }
+
+    /// Returns a `SpecError` naming the annex file and the cell at `row` and `column`.
+    #[cfg(feature = "xlsx")]
+    fn xlsx_error(&self, message: &str, row: usize, column: usize, field: &str) -> SpecError {
+        SpecError::new(message)
+            .with_file(self.source.as_deref())
+            .at(format!("row {}, column {}", row, column_name(column)))
+            .with_field(field)
+    }
+
+    /// Returns the cell of a row at `column`, or a `SpecError` if the row is too short.
+    #[cfg(feature = "xlsx")]
+    fn xlsx_cell<'a>(
+        &self,
+        cells: &'a [DataType],
+        row: usize,
+        column: usize,
+        field: &str,
+    ) -> Result<&'a DataType, SpecError> {
+        cells
+            .get(column)
+            .ok_or_else(|| self.xlsx_error("Missing cell", row, column, field))
+    }
+
+    /// Returns the text of the cell of a row at `column`, or a `SpecError` if it is missing or not text.
+    #[cfg(feature = "xlsx")]
+    fn xlsx_string<'a>(
+        &self,
+        cells: &'a [DataType],
+        row: usize,
+        column: usize,
+        field: &str,
+    ) -> Result<&'a str, SpecError> {
+        self.xlsx_cell(cells, row, column, field)?
+            .get_string()
+            .ok_or_else(|| self.xlsx_error("Expected text", row, column, field))
     }
 
     /// Converts a given string to a byte slice of specified length, with additional modifications if necessary.
@@ -434,7 +703,7 @@ impl J1939Spec {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```ignore
     /// let mut output = [0; 8];
     /// let input = String::from("Hello, World!");
     /// let len = 8;
@@ -474,3 +743,29 @@ impl J1939Spec {
         output[..input_chars.len()].copy_from_slice(&input_chars);
     }
 }
+
+/// Adds `e` to `skipped` when skipping invalid entries, or returns it otherwise.
+fn skip_or_fail(skipped: Option<&mut Vec<SpecError>>, e: SpecError) -> Result<(), SpecError> {
+    match skipped {
+        Some(skipped) => {
+            skipped.push(e);
+            Ok(())
+        }
+        None => Err(e),
+    }
+}
+
+/// Returns the spreadsheet name of a 0-based column index, e.g. `T` for 19 and `AJ` for 35.
+#[cfg(feature = "xlsx")]
+fn column_name(mut column: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (column % 26) as u8);
+        if column < 26 {
+            break;
+        }
+        column = column / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
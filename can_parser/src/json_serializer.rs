@@ -1,9 +1,11 @@
 use crate::can_message::CANMessage;
 use crate::error::CANParserError;
+use crate::sink::MessageSink;
 use crate::specification::FilteredSpec;
 use serde_json::{Map, Value};
+use std::borrow::Borrow;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 
 /// Converts the CANParser object to a JSON string.
 ///
@@ -22,10 +24,10 @@ use std::io::BufWriter;
 ///
 /// Returns a CANParserError if there is an error serializing the CANParser object to JSON or writing
 /// the JSON string to a file.
-pub fn to_json(
+pub fn to_json<M: Borrow<CANMessage>>(
     output_path: Option<String>,
     filtered_spec: &FilteredSpec,
-    messages: &Vec<CANMessage>,
+    messages: impl IntoIterator<Item = M>,
 ) -> Result<Option<String>, CANParserError> {
     let mut json = Map::new();
 
@@ -35,7 +37,12 @@ pub fn to_json(
     );
     json.insert(
         "results".to_string(),
-        serde_json::to_value(messages.as_slice())?,
+        Value::Array(
+            messages
+                .into_iter()
+                .map(|message| serde_json::to_value(message.borrow()))
+                .collect::<Result<_, _>>()?,
+        ),
     );
 
     if let Some(output_path) = output_path {
@@ -70,3 +77,69 @@ fn write_json_to_file(
 
     Ok(None)
 }
+
+/// A `MessageSink` writing the messages as JSON while they are parsed.
+///
+/// The output has the same layout as `CANParser::to_json`: the messages are streamed into the
+/// "results" array and the "spec" object is written when parsing finishes, so a sink holds a
+/// single document and is used for a single parse.
+pub struct JsonSink<W: Write> {
+    writer: W,
+    started: bool,
+}
+
+impl JsonSink<BufWriter<File>> {
+    /// Creates a `JsonSink` writing to the file at `output_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the file cannot be created.
+    pub fn create(output_path: &str) -> Result<Self, CANParserError> {
+        Ok(Self::new(BufWriter::new(File::create(output_path)?)))
+    }
+}
+
+impl<W: Write> JsonSink<W> {
+    /// Creates a `JsonSink` writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            started: false,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes the opening of the document the first time it is called.
+    fn start(&mut self) -> Result<(), CANParserError> {
+        if !self.started {
+            self.writer.write_all(b"{\"results\":[")?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> MessageSink for JsonSink<W> {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        if self.started {
+            self.writer.write_all(b",")?;
+        } else {
+            self.start()?;
+            self.started = true;
+        }
+        serde_json::to_writer(&mut self.writer, &message)?;
+        Ok(())
+    }
+
+    fn on_finish(&mut self, filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        self.start()?;
+        self.writer.write_all(b"],\"spec\":")?;
+        serde_json::to_writer(&mut self.writer, filtered_spec)?;
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -1,20 +1,74 @@
+#[cfg(feature = "async")]
+mod async_stream;
 mod can_message;
+mod compression;
+mod diagnostics;
 mod error;
+mod error_frame;
+mod error_policy;
+mod filter;
+mod generator;
 mod j1939_spec;
 #[macro_use]
 mod utils;
 mod csv_serializer;
+mod dbc;
+mod encode;
 mod json_serializer;
+mod log_format;
+mod log_writer;
+mod message_store;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod progress;
+mod signal_csv;
+mod signal_selection;
+mod sink;
 mod specification;
 #[cfg(feature = "sqlite")]
 mod sqlite_serializer;
-pub use can_message::{parse_id, parse_j1939_data, CANMessage, CANID};
+mod timestamp;
+#[cfg(feature = "async")]
+pub use async_stream::{MessageStream, ReaderLines};
+pub use can_message::{
+    build_j1939_id, dlc_to_len, encode_j1939_data, len_to_dlc, parse_id, parse_j1939_data,
+    CANMessage, Direction, CANFD_BRS, CANFD_ESI, CANID,
+};
+use can_message::{CANFD_MAX_DLEN, CAN_MAX_DLEN};
+pub use compression::{log_size, open_log_reader, Compression};
 use csv_serializer::to_csv;
+pub use csv_serializer::CsvSink;
+use diagnostics::LineError;
+pub use diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, MAX_DIAGNOSTICS_PER_KIND};
+pub use encode::{J1939_DEFAULT_PRIORITY, J1939_GLOBAL_ADDRESS};
 pub use error::CANParserError;
+pub use error_frame::CANErrorFrame;
+pub use error_policy::{ErrorPolicy, ERROR_IGNORE, ERROR_STRICT, ERROR_WARN, ERROR_WARN_UP_TO};
+pub use filter::{IdFilter, MessageFilter};
+pub use generator::{SignalProfile, TrafficConfig, TrafficMessage, MAX_FRAMES_PER_MESSAGE};
 use json_serializer::to_json;
-pub use specification::{Metadata, SpecPGN, SpecSPN, Specification, FilteredSpec};
+pub use json_serializer::JsonSink;
+use log_format::{compact_hex, decode_hex, LineFields, MAX_HEX_DIGITS};
+pub use log_format::{LogFormat, LOG_FORMAT_ASC, LOG_FORMAT_CANDUMP, LOG_FORMAT_TRC};
+pub use log_writer::{write_log, LogSink, LogWriterFormat, LOG_FORMAT_BLF};
+pub use message_store::{FrameTable, MessageStore};
+use progress::ProgressTracker;
+pub use progress::{CancellationToken, Progress, ProgressCallback};
+use signal_csv::{to_long_csv, to_wide_csv};
+pub use signal_csv::{
+    Resampling, LONG_CSV_COLUMNS, MAX_WIDE_CSV_ROWS, RESAMPLING_FFILL, RESAMPLING_MEAN,
+};
+pub use signal_selection::SignalSelection;
+pub use sink::MessageSink;
+pub use specification::{FilteredSpec, Metadata, SpecError, SpecPGN, SpecSPN, Specification};
 #[cfg(feature = "sqlite")]
 use sqlite_serializer::to_sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite_serializer::SqliteSink;
+pub use timestamp::{
+    nanos_to_secs, parse_seconds, parse_utc_offset, TimestampFormat, NANOS_PER_SEC,
+    TIMESTAMP_DATETIME, TIMESTAMP_EPOCH, TIMESTAMP_ISO8601, TIMESTAMP_RELATIVE,
+};
 
 use crate::j1939_spec::J1939Spec;
 
@@ -22,11 +76,12 @@ use regex::Regex;
 #[cfg(feature = "sqlite")]
 use rusqlite::Result;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::BufRead;
 #[cfg(feature = "debug")]
 use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 #[cfg(feature = "wasm")]
 use web_sys::console;
@@ -42,17 +97,25 @@ impl IntoIterator for FilteredSpec {
     }
 }
 
+/// The number of lines read from a file before they are parsed in parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_LINES: usize = 1 << 16;
+
 pub const LOG_TYPE_BINARY: &'static str = "binary";
 pub const LOG_TYPE_TEXT: &'static str = "text";
 
-pub const ERROR_IGNORE: &'static str = "ignore";
-pub const ERROR_WARN: &'static str = "warn";
-
 pub const SPEC_TYPE_J1939: &'static str = "j1939";
 pub const SPEC_TYPE_CAN: &'static str = "can";
 pub const SPEC_TYPE_UDS: &'static str = "uds";
 pub const SPEC_TYPE_TRANSPORT: &'static str = "transport";
 
+const KNOWN_SPEC_TYPES: [&str; 4] = [
+    SPEC_TYPE_J1939,
+    SPEC_TYPE_CAN,
+    SPEC_TYPE_UDS,
+    SPEC_TYPE_TRANSPORT,
+]; // ... add other known keys as necessary
+
 /// A struct representing the specifications for various protocols used in CAN communication.
 struct Specs {
     pub j1939: Option<J1939Spec>,
@@ -76,16 +139,89 @@ impl Default for Specs {
 pub struct CANParser {
     /// A regular expression used to match lines in the input file.
     line_regex: Arc<Regex>,
+    /// The built-in format whose hand-written parser splits the lines instead of `line_regex`.
+    log_format: Option<LogFormat>,
     /// An optional reference to a `Specs` struct.
     specs: Option<Arc<Specs>>,
-    /// A string representing the error handling mode.
-    error_handling: String,
+    /// How lines that cannot be parsed are handled.
+    error_handling: ErrorPolicy,
+    /// The format of the timestamps captured by the line regex.
+    timestamp_format: TimestampFormat,
+    /// Overrides whether IDs are extended. If `None`, it is detected from how each ID is written.
+    extended_ids: Option<bool>,
+    /// The specification types used to decode each channel. Channels not listed use every specification.
+    channel_specs: HashMap<String, Vec<String>>,
+    /// The filter dropping messages before they are decoded.
+    filter: Option<MessageFilter>,
+    /// The signals decoded. If `None`, every signal is decoded.
+    signal_selection: Option<SignalSelection>,
+    /// Whether each parse call adds to the results of the previous ones instead of replacing them.
+    append: bool,
+    /// The number of lines given to `parse_lines` so far, so that line numbers continue across calls in append mode.
+    lines_read: u64,
+    /// Whether specification entries that cannot be read are skipped, with a warning, instead of failing the lookup.
+    skip_invalid_spec_entries: bool,
+    /// The callback receiving the progress of each parse.
+    progress_callback: Option<ProgressCallback>,
+    /// The token cancelling the parses of this parser.
+    cancellation_token: CancellationToken,
+    /// The specification entries skipped because they could not be read.
+    pub spec_warnings: Arc<Mutex<Vec<SpecError>>>,
     /// A thread-safe reference to a `FileFlags` struct.
     pub flags: Arc<RwLock<FileFlags>>,
     /// A thread-safe reference to a `FilteredSpec` struct.
     pub filtered_spec: Arc<FilteredSpec>,
+    /// Whether parsed messages are kept in the columnar `store` instead of `messages`.
+    columnar: bool,
     /// A vector of `CANMessage` structs.
     pub messages: Vec<CANMessage>,
+    /// The parsed messages in columnar form, used instead of `messages` when set with `set_columnar`.
+    pub store: MessageStore,
+    /// The problems found by the last call to `parse_file`, `parse_files` or `parse_lines`, or by every call in append mode.
+    /// Shared with the error returned by the call, so the lines are only held once.
+    pub diagnostics: Arc<Diagnostics>,
+}
+
+/// The state of a parse shared between threads: the diagnostics collected and its progress.
+struct ParseState {
+    /// The diagnostics of the lines that could not be parsed.
+    diagnostics: Mutex<Diagnostics>,
+    /// Set once the error policy has stopped the parse.
+    aborted: AtomicBool,
+    /// The progress of the parse and whether it was cancelled.
+    progress: ProgressTracker,
+}
+
+impl ParseState {
+    /// Creates the state of a parse reporting its progress to `progress`.
+    fn new(progress: ProgressTracker) -> Self {
+        Self {
+            diagnostics: Mutex::new(Diagnostics::default()),
+            aborted: AtomicBool::new(false),
+            progress,
+        }
+    }
+
+    /// Returns `true` if the error policy has stopped the parse.
+    fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` if the parse was stopped by the error policy or cancelled.
+    fn is_stopped(&self) -> bool {
+        self.is_aborted() || self.progress.is_cancelled()
+    }
+}
+
+/// A log file to parse with `CANParser::parse_files`.
+#[derive(Clone, Debug, Default)]
+pub struct LogFile {
+    /// The path to the log file.
+    pub path: String,
+    /// The channel to tag the file's messages with, replacing any channel captured from the log.
+    pub channel: Option<String>,
+    /// Nanoseconds added to every timestamp in the file, to align loggers with different clocks.
+    pub time_offset: i64,
 }
 
 /// Represents the flags for different types of protocol that might found during parsing by the CAN parser.
@@ -116,7 +252,7 @@ impl CANParser {
     ///
     /// # Arguments
     ///
-    /// * `error_handling` - A `String` that specifies the error handling method to use. Valid values are "warn", and "ignore".
+    /// * `error_handling` - The `ErrorPolicy` for lines that cannot be parsed.
     /// * `line_regex` - An optional `String` that specifies the regular expression to use for parsing lines. If `None`, its assumed the file is binary.
     /// * `specs_annexes` - An optional `HashMap<String, String>` that specifies the specification annexes to use for parsing messages.
     ///
@@ -124,7 +260,7 @@ impl CANParser {
     ///
     /// A `Result` containing the new `CANParser` instance if successful, or a `CANParserError` if an error occurred.
     pub fn new(
-        error_handling: String,
+        error_handling: ErrorPolicy,
         line_regex: Option<String>,
         specs_annexes: Option<HashMap<String, String>>,
     ) -> Result<Self, CANParserError> {
@@ -134,19 +270,14 @@ impl CANParser {
                 .as_str()
                 .into(),
         );
+        error_handling.validate()?;
+        let log_format = line_regex.as_deref().and_then(LogFormat::from_regex);
         let line_regex = line_regex.map(|s| Regex::new(&s)).transpose()?;
 
-        let known_keys = vec![
-            SPEC_TYPE_J1939,
-            SPEC_TYPE_CAN,
-            SPEC_TYPE_UDS,
-            SPEC_TYPE_TRANSPORT,
-        ]; // ... add other known keys as necessary
-
         let specs = if let Some(annexes) = specs_annexes {
             // Check for any unknown keys
             for key in annexes.keys() {
-                if !known_keys.contains(&key.to_ascii_lowercase().as_str()) {
+                if !KNOWN_SPEC_TYPES.contains(&key.to_ascii_lowercase().as_str()) {
                     return Err(CANParserError::ParserError(format!(
                         "Unknown spec key: {}",
                         key
@@ -166,14 +297,298 @@ impl CANParser {
 
         Ok(Self {
             line_regex: Arc::new(line_regex.unwrap_or_else(|| Regex::new("").unwrap())),
+            log_format,
             specs,
-            error_handling: error_handling.to_ascii_lowercase(),
+            error_handling,
+            timestamp_format: TimestampFormat::default(),
+            extended_ids: None,
+            channel_specs: HashMap::new(),
+            filter: None,
+            signal_selection: None,
+            append: false,
+            lines_read: 0,
+            skip_invalid_spec_entries: false,
+            progress_callback: None,
+            cancellation_token: CancellationToken::new(),
+            spec_warnings: Arc::new(Mutex::new(Vec::new())),
             flags: Arc::new(RwLock::new(FileFlags::default())),
             filtered_spec: Arc::new(FilteredSpec::default()),
+            columnar: false,
             messages: Vec::with_capacity(0),
+            store: MessageStore::new(),
+            diagnostics: Arc::default(),
         })
     }
 
+    /// Sets the format of the timestamps captured by the line regex.
+    ///
+    /// Timestamps are stored as integer nanoseconds. The default, `TimestampFormat::Epoch`, reads
+    /// seconds since the Unix epoch from the `timestamp` group as written by candump.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_format` - The `TimestampFormat` to parse timestamps with.
+    pub fn set_timestamp_format(&mut self, timestamp_format: TimestampFormat) {
+        self.timestamp_format = timestamp_format;
+    }
+
+    /// Selects a built-in log format, replacing the line regex with the format's template.
+    ///
+    /// Lines of the built-in formats are split by a hand-written parser instead of the regex,
+    /// which is several times faster. The parser is also used when `CANParser::new` is given the
+    /// `LogFormat::regex` template of a format. Any other line regex is matched as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_format` - One of `LOG_FORMAT_CANDUMP`, `LOG_FORMAT_ASC` or `LOG_FORMAT_TRC`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the format is unknown.
+    pub fn set_log_format(&mut self, log_format: &str) -> Result<(), CANParserError> {
+        let log_format = LogFormat::from_name(log_format)?;
+        self.line_regex = Arc::new(Regex::new(log_format.regex())?);
+        self.log_format = Some(log_format);
+        Ok(())
+    }
+
+    /// Returns the built-in log format used to split lines, if any.
+    pub fn log_format(&self) -> Option<LogFormat> {
+        self.log_format
+    }
+
+    /// Overrides whether parsed IDs are treated as extended (29-bit) or standard (11-bit).
+    ///
+    /// By default an ID is extended if the log marks it so: it is written with 8 hex digits, has
+    /// the `CAN_EFF_FLAG` set, is too large for 11 bits, or the line regex matched an `ext` group
+    /// (e.g. the `x` suffix of Vector ASC logs). Use this for logs that pad or truncate IDs.
+    ///
+    /// # Arguments
+    ///
+    /// * `extended` - `Some(true)` for all extended IDs, `Some(false)` for all standard IDs, or `None` to detect them.
+    pub fn set_extended_ids(&mut self, extended: Option<bool>) {
+        self.extended_ids = extended;
+    }
+
+    /// Sets the filter restricting the messages that are parsed.
+    ///
+    /// Lines whose message does not match the filter are dropped right after their ID is parsed,
+    /// before any specification lookup or decoding, so large logs can be narrowed cheaply. They
+    /// are not reported as diagnostics. The time window and channel of `parse_files` apply to the
+    /// messages once the time offset and channel of their file are applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter, or `None` to parse every message.
+    pub fn set_filter(&mut self, filter: Option<MessageFilter>) {
+        self.filter = filter;
+    }
+
+    /// Returns the filter restricting the messages that are parsed, if any.
+    pub fn filter(&self) -> Option<&MessageFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Restricts the signals that are decoded and serialized.
+    ///
+    /// The SPNs that are not selected are removed from each PGN as it is looked up in the
+    /// specification, so `parse_j1939_data` only decodes the selected signals and `filtered_spec`
+    /// and the serializers only hold their columns. Changing the selection clears the PGNs already
+    /// looked up, so the next parse looks them up again with the new selection.
+    ///
+    /// # Arguments
+    ///
+    /// * `selection` - The signals to decode, or `None` to decode every signal.
+    pub fn set_signal_selection(&mut self, selection: Option<SignalSelection>) {
+        if selection != self.signal_selection {
+            self.filtered_spec = Arc::new(FilteredSpec::default());
+        }
+        self.signal_selection = selection;
+    }
+
+    /// Sets whether parse calls add to the results of the previous calls instead of replacing them.
+    ///
+    /// In append mode `parse_file`, `parse_files` and `parse_lines` append their messages to
+    /// `messages` and their diagnostics to `diagnostics`, so a log can be fed in chunks or many
+    /// logs parsed in a loop. Line numbers given to `parse_lines` continue from the previous call,
+    /// while lines read from files are numbered within their file. Messages are kept in the order
+    /// of the calls. `flags`, `filtered_spec` and `spec_warnings` always accumulate. Use `reset`
+    /// to start over.
+    ///
+    /// # Arguments
+    ///
+    /// * `append` - Whether to append the results of each parse call.
+    pub fn set_append(&mut self, append: bool) {
+        self.append = append;
+    }
+
+    /// Clears the results of previous parse calls: the messages, diagnostics, flags, filtered
+    /// specification and specification warnings. Line numbering starts again from 1.
+    pub fn reset(&mut self) {
+        self.messages = Vec::with_capacity(0);
+        self.store.clear();
+        self.diagnostics = Arc::default();
+        self.lines_read = 0;
+        self.flags = Arc::new(RwLock::new(FileFlags::default()));
+        self.filtered_spec = Arc::new(FilteredSpec::default());
+        self.spec_warnings = Arc::new(Mutex::new(Vec::new()));
+    }
+
+    /// Sets whether parsed messages are kept in the columnar `store` instead of `messages`.
+    ///
+    /// A `CANMessage` carries a 64 byte payload and its own map of SPN values, so a decoded log
+    /// takes several times its size in memory. The `MessageStore` keeps a table per CAN ID with
+    /// payloads sized to their data length and a column of values per signal, which takes several
+    /// times less memory for large logs. The serializers, `iter_messages` and `message_count`
+    /// read from whichever storage is in use.
+    ///
+    /// # Arguments
+    ///
+    /// * `columnar` - Whether to store the messages in `store`.
+    pub fn set_columnar(&mut self, columnar: bool) {
+        self.columnar = columnar;
+    }
+
+    /// Returns the parsed messages in order, from `store` in columnar mode and from `messages` otherwise.
+    pub fn iter_messages(&self) -> Box<dyn Iterator<Item = Cow<'_, CANMessage>> + '_> {
+        if self.columnar {
+            Box::new(self.store.iter().map(Cow::Owned))
+        } else {
+            Box::new(self.messages.iter().map(Cow::Borrowed))
+        }
+    }
+
+    /// Returns the number of parsed messages, from `store` in columnar mode and from `messages` otherwise.
+    pub fn message_count(&self) -> usize {
+        if self.columnar {
+            self.store.len()
+        } else {
+            self.messages.len()
+        }
+    }
+
+    /// Returns the timestamps and values of a signal across every parsed message carrying it,
+    /// ordered by timestamp. In columnar mode the values are read straight from the signal columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `spn` - The SPN of the signal.
+    pub fn signal(&self, spn: u16) -> (Vec<i64>, Vec<f32>) {
+        if self.columnar {
+            return self.store.signal(spn);
+        }
+        let mut samples: Vec<(i64, f32)> = self
+            .messages
+            .iter()
+            .filter_map(|message| Some((message.ts, *message.data.spns.get(&spn)?)))
+            .collect();
+        samples.sort_by_key(|(ts, _)| *ts);
+        samples.into_iter().unzip()
+    }
+
+    /// Removes the parsed messages from both `messages` and `store`.
+    pub fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.store.clear();
+    }
+
+    /// Sets whether specification entries that cannot be read are skipped instead of failing the lookup.
+    ///
+    /// Specifications are read lazily, the first time a PGN is seen. By default an SPN entry with
+    /// a missing or malformed field fails the lookup, so every line of that PGN is reported as a
+    /// `DiagnosticKind::SpecLookup` diagnostic. When skipping, the PGN is decoded without the bad
+    /// SPN and a `SpecError` naming it is added to `spec_warnings`.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - Whether to skip specification entries that cannot be read.
+    pub fn set_skip_invalid_spec_entries(&mut self, skip: bool) {
+        self.skip_invalid_spec_entries = skip;
+    }
+
+    /// Sets the callback receiving the progress of each parse.
+    ///
+    /// The callback is called every few thousand lines and once more when the parse ends, with the
+    /// bytes and lines read, the messages decoded and, when the size of the input is known, the
+    /// fraction done and the estimated time left. Returning `ControlFlow::Break` cancels the parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The `ProgressCallback`, or `None` to stop reporting progress.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.progress_callback = callback;
+    }
+
+    /// Sets the token cancelling the parses of this parser.
+    ///
+    /// A cancelled parse stops as soon as possible, in both the sequential and parallel paths, keeps
+    /// the messages decoded so far and returns a `CANParserError::ParseCancelled`.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The `CancellationToken` to check while parsing.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = token;
+    }
+
+    /// Returns the token cancelling the parses of this parser, to cancel them from another thread.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
+    /// Creates the state of a parse of an input of the given size.
+    fn parse_state(&self, total_bytes: Option<u64>, total_lines: Option<u64>) -> Arc<ParseState> {
+        Arc::new(ParseState::new(ProgressTracker::new(
+            self.progress_callback.clone(),
+            self.cancellation_token.clone(),
+            total_bytes,
+            total_lines,
+        )))
+    }
+
+    /// Restricts the specifications used to decode messages logged on `channel`.
+    ///
+    /// This lets logs from vehicles with several buses, e.g. a J1939 bus and a proprietary bus,
+    /// decode only the channels the specifications apply to. Channels that are never configured
+    /// are decoded with every loaded specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel or interface name as it appears in the log, e.g. `can0`.
+    /// * `spec_types` - The specification types to decode the channel with. An empty list disables decoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if any of the specification types are unknown.
+    pub fn set_channel_specs(
+        &mut self,
+        channel: String,
+        spec_types: Vec<String>,
+    ) -> Result<(), CANParserError> {
+        let spec_types = spec_types
+            .into_iter()
+            .map(|s| s.to_ascii_lowercase())
+            .collect::<Vec<String>>();
+        for spec_type in &spec_types {
+            if !KNOWN_SPEC_TYPES.contains(&spec_type.as_str()) {
+                return Err(CANParserError::ParserError(format!(
+                    "Unknown spec key: {}",
+                    spec_type
+                )));
+            }
+        }
+        self.channel_specs.insert(channel, spec_types);
+        Ok(())
+    }
+
+    /// Checks whether messages from `channel` should be decoded with the given specification type.
+    fn channel_uses_spec(&self, channel: &Option<String>, spec_type: &str) -> bool {
+        match channel.as_ref().and_then(|c| self.channel_specs.get(c)) {
+            Some(spec_types) => spec_types.iter().any(|s| s == spec_type),
+            None => true,
+        }
+    }
+
     /// Fetches a specification from the given `annexes` hashmap using the specified `key`.
     ///
     /// # Arguments
@@ -197,6 +612,9 @@ impl CANParser {
 
     /// Parses a file containing CAN messages and returns a vector of parsed messages.
     ///
+    /// Gzip, zstd, xz and bzip2 compressed files are detected by their magic bytes and
+    /// decompressed on the fly when the `compression` feature is enabled.
+    ///
     /// # Arguments
     ///
     /// * `self` - A mutable reference to the CANParser instance.
@@ -204,86 +622,286 @@ impl CANParser {
     ///
     /// # Errors
     ///
-    /// Returns a `CANParserError` if the file cannot be opened or if there are parsing errors.
+    /// Returns a `CANParserError` if the file cannot be opened, or a `CANParserError::ParserWarning`
+    /// carrying the `Diagnostics` of the lines that could not be parsed, or a
+    /// `CANParserError::ParseAborted` if the error policy stopped the parse. The diagnostics are
+    /// also kept in `self.diagnostics`.
     ///
     /// # Example
     ///
-    /// ```
-    /// use can_parser::CANParser;
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LOG_FORMAT_CANDUMP};
     ///
-    /// let mut parser = CANParser::new();
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
     /// parser.parse_file("path/to/file.log").unwrap();
     /// ```
     pub fn parse_file(&mut self, file_path: &str) -> Result<(), CANParserError> {
         #[cfg(feature = "debug")]
         let start_time = Self::current_time();
-        let file = File::open(file_path)?;
 
-        let reader = BufReader::new(file);
-        let errors = Arc::new(Mutex::new(vec![]));
+        let result = self.parse_stored(|parser, sink| parser.parse_file_into(file_path, sink));
 
-        let parse_file_line = |line_result: Result<String, std::io::Error>| -> Option<_> {
-            match line_result {
-                Ok(line) => Some(line),
-                Err(e) => {
-                    Self::handle_parsing_error(
-                        &self.error_handling,
-                        &errors,
-                        e.to_string(),
-                        &String::new(),
-                    );
-                    None
-                }
-            }
-        };
+        // Debugging Logic
+        #[cfg(feature = "debug")]
+        Self::debug_log(&self.messages, Self::current_time() - start_time);
 
-        // Function to parse a line
-        let parse_can_message = |line: String| -> Option<_> {
-            match Self::parse_line_inner(&self.specs, &self.line_regex, &line, &self.filtered_spec)
-            {
-                Ok(message) => Some(message),
-                Err(e) => {
-                    Self::handle_parsing_error(&self.error_handling, &errors, e, &line);
-                    None
+        result
+    }
+
+    /// Parses a file containing CAN messages, handing each message to `sink` as it is decoded
+    /// instead of storing it in `self.messages`.
+    ///
+    /// Messages reach the sink in file order. The diagnostics of the lines that could not be
+    /// parsed are handed to `MessageSink::on_warning` once the file has been read, followed by a
+    /// call to `MessageSink::on_finish`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the CANParser instance.
+    /// * `file_path` - A string slice that holds the path to the file to be parsed.
+    /// * `sink` - The `MessageSink` receiving the results.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse_file`, or the first error returned by the sink, which
+    /// stops the parse.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, JsonSink, LOG_FORMAT_CANDUMP};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// let mut sink = JsonSink::create("path/to/output.json").unwrap();
+    /// parser.parse_file_into("path/to/file.log", &mut sink).unwrap();
+    /// ```
+    pub fn parse_file_into(
+        &mut self,
+        file_path: &str,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let state = self.parse_state(log_size(file_path), None);
+        let parsed = self.read_file(file_path, self.filter.as_ref(), &state, sink);
+        self.finish_sink(parsed, state, sink)
+    }
+
+    /// Parses several log files of the same drive into a single timestamp ordered list of messages.
+    ///
+    /// Each file is parsed with the same specifications and `filtered_spec`, in parallel if the
+    /// `parallel` feature is enabled. Every message is tagged with the path of the file it came
+    /// from, and with the file's channel if one is given. The file's time offset is applied before
+    /// the messages are merged, so loggers with unsynchronized clocks can be aligned. Messages
+    /// with equal timestamps keep the order of `files`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the CANParser instance.
+    /// * `files` - The `LogFile`s to parse.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if any file cannot be opened or if there are parsing errors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LogFile, LOG_FORMAT_CANDUMP};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_files(&[
+    ///     LogFile { path: "j1939.log".to_string(), channel: Some("can0".to_string()), time_offset: 0 },
+    ///     LogFile { path: "body.log".to_string(), channel: Some("can1".to_string()), time_offset: -1_500_000 },
+    /// ]).unwrap();
+    /// ```
+    pub fn parse_files(&mut self, files: &[LogFile]) -> Result<(), CANParserError> {
+        #[cfg(feature = "debug")]
+        let start_time = Self::current_time();
+        let total_bytes = files
+            .iter()
+            .map(|file| log_size(&file.path))
+            .sum::<Option<u64>>();
+        let state = self.parse_state(total_bytes, None);
+
+        // Function to parse and tag a file
+        let read_log_file = |file: &LogFile| -> Result<Vec<CANMessage>, CANParserError> {
+            let source: Arc<str> = Arc::from(file.path.as_str());
+            let filter = self.filter.as_ref().map(|filter| filter.for_file(file));
+            let mut messages = Vec::new();
+            self.read_file(&file.path, filter.as_ref(), &state, &mut messages)?;
+            for message in messages.iter_mut() {
+                message.ts = message.ts.checked_add(file.time_offset).ok_or_else(|| {
+                    CANParserError::ParserError(format!(
+                        "Time offset {} of {} moves timestamp {} out of range",
+                        file.time_offset, file.path, message.ts
+                    ))
+                })?;
+                message.source = Some(source.clone());
+                if file.channel.is_some() {
+                    message.channel = file.channel.clone();
                 }
             }
+            Ok(messages)
         };
 
         // Core Logic
-        self.messages = if cfg!(feature = "parallel") {
+        let mut messages: Vec<CANMessage> = if cfg!(feature = "parallel") {
             #[cfg(feature = "parallel")]
             {
                 use rayon::prelude::*;
-                reader
-                    .lines()
-                    .filter_map(parse_file_line)
-                    .par_bridge()
-                    .filter_map(parse_can_message)
-                    .collect()
+                let per_file = files
+                    .par_iter()
+                    .map(read_log_file)
+                    .collect::<Result<Vec<_>, _>>()?;
+                per_file.into_iter().flatten().collect()
             }
             #[cfg(not(feature = "parallel"))]
             {
                 panic!("Parallel feature not enabled");
             }
         } else {
-            reader
-                .lines()
-                .filter_map(parse_file_line)
-                .filter_map(parse_can_message)
-                .collect()
+            let per_file = files
+                .iter()
+                .map(read_log_file)
+                .collect::<Result<Vec<_>, _>>()?;
+            per_file.into_iter().flatten().collect()
         };
 
+        // Stable sorts, so messages with equal timestamps keep their file order.
+        if cfg!(feature = "parallel") {
+            #[cfg(feature = "parallel")]
+            {
+                use rayon::prelude::*;
+                messages.par_sort_by_key(|message| message.ts);
+            }
+        } else {
+            messages.sort_by_key(|message| message.ts);
+        }
+        self.store_messages(messages);
+
         // Debugging Logic
         #[cfg(feature = "debug")]
         Self::debug_log(&self.messages, Self::current_time() - start_time);
 
         // Error Check
-        let errors = errors.lock().unwrap();
-        if errors.is_empty() {
-            Ok(())
+        self.finish_diagnostics(state)
+    }
+
+    /// Reads and parses the lines of a log file into `sink`, collecting any parsing errors and the progress into `state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - A string slice that holds the path to the file to be parsed.
+    /// * `state` - A reference to an Arc wrapped `ParseState` collecting the diagnostics of the lines that could not be parsed and the progress.
+    /// * `sink` - The `MessageSink` receiving the messages in file order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the file cannot be opened or the sink fails.
+    fn read_file(
+        &self,
+        file_path: &str,
+        filter: Option<&MessageFilter>,
+        state: &Arc<ParseState>,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let reader = open_log_reader(file_path)?;
+
+        // Function to number a line and find its byte offset. Lines are split on raw bytes so
+        // that the offsets stay exact and invalid UTF-8 is reported against its own line.
+        let mut next_offset = 0u64;
+        let read_file_line = move |(index, line_result): (usize, std::io::Result<Vec<u8>>)| {
+            let number = index as u64 + 1;
+            let offset = next_offset;
+            let error = match line_result {
+                Ok(mut bytes) => {
+                    next_offset += bytes.len() as u64 + 1;
+                    state.progress.line_read(bytes.len() as u64 + 1);
+                    if bytes.last() == Some(&b'\r') {
+                        bytes.pop();
+                    }
+                    match String::from_utf8(bytes) {
+                        Ok(text) => return Some((number, offset, text)),
+                        Err(e) => (
+                            LineError::new(DiagnosticKind::Io, e.utf8_error().to_string()),
+                            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+                        ),
+                    }
+                }
+                Err(e) => {
+                    state.progress.line_read(0);
+                    (
+                        LineError::new(DiagnosticKind::Io, e.to_string()),
+                        String::new(),
+                    )
+                }
+            };
+            Self::handle_parsing_error(&self.error_handling, state, || {
+                Diagnostic::new(error.0, error.1, number, Some(offset), Some(file_path))
+            });
+            None
+        };
+
+        // Function to parse a line
+        let parse_can_message = |(number, offset, line): (u64, u64, String)| -> Option<_> {
+            if state.is_stopped() {
+                return None;
+            }
+            match self.parse_line_inner(&line, filter) {
+                Ok(Some(message)) => {
+                    state.progress.message_decoded();
+                    Some(message)
+                }
+                Ok(None) => None,
+                Err(e) => {
+                    Self::handle_parsing_error(&self.error_handling, state, || {
+                        Diagnostic::new(e, line, number, Some(offset), Some(file_path))
+                    });
+                    None
+                }
+            }
+        };
+
+        // Core Logic
+        if cfg!(feature = "parallel") {
+            #[cfg(feature = "parallel")]
+            {
+                use rayon::prelude::*;
+                // Lines are read in chunks that are parsed in parallel. Unlike bridging the
+                // reader into rayon, collecting each chunk keeps the messages in file order.
+                let mut lines = reader.split(b'\n').enumerate().filter_map(read_file_line);
+                loop {
+                    let chunk: Vec<_> = lines.by_ref().take(PARALLEL_CHUNK_LINES).collect();
+                    if chunk.is_empty() || state.is_stopped() {
+                        break;
+                    }
+                    let messages: Vec<CANMessage> = chunk
+                        .into_par_iter()
+                        .filter_map(parse_can_message)
+                        .collect();
+                    for message in messages {
+                        sink.on_message(message)?;
+                    }
+                }
+            }
+            #[cfg(not(feature = "parallel"))]
+            {
+                panic!("Parallel feature not enabled");
+            }
         } else {
-            Err(CANParserError::ParserWarning(errors.clone()))
+            let messages = reader
+                .split(b'\n')
+                .enumerate()
+                .take_while(|_| !state.is_stopped())
+                .filter_map(read_file_line)
+                .filter_map(parse_can_message);
+            for message in messages {
+                sink.on_message(message)?;
+            }
         }
+        Ok(())
     }
 
     /// Parses a vector of CAN messages from a vector of strings.
@@ -295,17 +913,20 @@ impl CANParser {
     ///
     /// # Errors
     ///
-    /// Returns a `CANParserError` if there are any errors encountered during parsing.
+    /// Returns a `CANParserError::ParserWarning` carrying the `Diagnostics` of the lines that could
+    /// not be parsed, or a `CANParserError::ParseAborted` if the error policy stopped the parse.
+    /// Line numbers count from 1 at the start of `lines`.
     ///
     /// # Example
     ///
     /// ```
-    /// use can_parser::CANParser;
+    /// use can_parser::{CANParser, ErrorPolicy, LOG_FORMAT_CANDUMP};
     ///
-    /// let mut parser = CANParser::new();
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
     /// let lines = vec![
-    ///     "can0 123#1122334455667788".to_string(),
-    ///     "can0 456#1122334455667788".to_string(),
+    ///     "(1692896400.000000) can0 123#1122334455667788".to_string(),
+    ///     "(1692896400.100000) can0 456#1122334455667788".to_string(),
     /// ];
     /// let result = parser.parse_lines(&lines);
     /// assert!(result.is_ok());
@@ -313,68 +934,115 @@ impl CANParser {
     pub fn parse_lines(&mut self, lines: &Vec<String>) -> Result<(), CANParserError> {
         #[cfg(feature = "debug")]
         let start_time = Self::current_time();
-        let errors = Arc::new(Mutex::new(vec![]));
+
+        let result = self.parse_stored(|parser, sink| parser.parse_lines_into(lines, sink));
+
+        // Debugging Logic
+        #[cfg(feature = "debug")]
+        Self::debug_log(&self.messages, Self::current_time() - start_time);
+
+        result
+    }
+
+    /// Parses a vector of CAN messages from a vector of strings, handing each message to `sink`
+    /// instead of storing it in `self.messages`.
+    ///
+    /// Messages reach the sink in the order of `lines`, followed by the diagnostics of the lines
+    /// that could not be parsed and a call to `MessageSink::on_finish`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the CANParser instance.
+    /// * `lines` - A vector of strings containing the CAN messages to be parsed.
+    /// * `sink` - The `MessageSink` receiving the results.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse_lines`, or the first error returned by the sink, which
+    /// stops the parse.
+    pub fn parse_lines_into(
+        &mut self,
+        lines: &[String],
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let state = self.parse_state(None, Some(lines.len() as u64));
+        let first_line = if self.append { self.lines_read } else { 0 };
 
         // Function to parse a line
-        let parse_can_message = |line: &String| -> Option<_> {
-            match Self::parse_line_inner(&self.specs, &self.line_regex, &line, &self.filtered_spec)
-            {
-                Ok(message) => Some(message),
+        let parse_can_message = |(index, line): (usize, &String)| -> Option<_> {
+            if state.is_stopped() {
+                return None;
+            }
+            match self.parse_line_inner(line, self.filter.as_ref()) {
+                Ok(Some(message)) => {
+                    state.progress.message_decoded();
+                    Some(message)
+                }
+                Ok(None) => None,
                 Err(e) => {
-                    Self::handle_parsing_error(&self.error_handling, &errors, e, &line);
+                    Self::handle_parsing_error(&self.error_handling, &state, || {
+                        Diagnostic::new(e, line.clone(), first_line + index as u64 + 1, None, None)
+                    });
                     None
                 }
             }
         };
 
         // Core Logic
-        self.messages = if cfg!(feature = "parallel") {
+        let parsed = if cfg!(feature = "parallel") {
             #[cfg(feature = "parallel")]
             {
                 use rayon::prelude::*;
-                lines.par_iter().filter_map(parse_can_message).collect()
+                // Lines are parsed in parallel a chunk at a time, so only the messages of one
+                // chunk are held before they reach the sink.
+                lines
+                    .chunks(PARALLEL_CHUNK_LINES)
+                    .enumerate()
+                    .take_while(|_| !state.is_stopped())
+                    .try_for_each(|(chunk_index, chunk)| {
+                        let first_index = chunk_index * PARALLEL_CHUNK_LINES;
+                        let messages: Vec<CANMessage> = chunk
+                            .par_iter()
+                            .enumerate()
+                            .map(|(index, line)| (first_index + index, line))
+                            .filter_map(parse_can_message)
+                            .collect();
+                        let bytes = chunk.iter().map(|line| line.len() as u64 + 1).sum();
+                        state.progress.lines_read(chunk.len() as u64, bytes);
+                        messages
+                            .into_iter()
+                            .try_for_each(|message| sink.on_message(message))
+                    })
             }
             #[cfg(not(feature = "parallel"))]
             {
                 panic!("Parallel feature not enabled");
             }
         } else {
-            lines.iter().filter_map(parse_can_message).collect()
+            lines
+                .iter()
+                .enumerate()
+                .take_while(|_| !state.is_stopped())
+                .inspect(|(_, line)| state.progress.line_read(line.len() as u64 + 1))
+                .filter_map(parse_can_message)
+                .try_for_each(|message| sink.on_message(message))
         };
-        // Debugging Logic
-        #[cfg(feature = "debug")]
-        Self::debug_log(&self.messages, Self::current_time() - start_time);
+        self.lines_read = first_line + lines.len() as u64;
 
         // Error Check
-        let errors = errors.lock().unwrap();
-        if errors.is_empty() {
-            Ok(())
-        } else {
-            Err(CANParserError::ParserWarning(errors.clone()))
-        }
+        self.finish_sink(parsed, state, sink)
     }
 
     /// Returns the current time in milliseconds as a floating-point number.
     ///
-    /// If the `debug` feature is enabled, this function will return the current time
-    /// using either the `web_sys::Performance` API (if the `wasm` feature is also enabled),
-    /// or the `std::time::SystemTime` API (if the `wasm` feature is not enabled).
+    /// The time is read from the `web_sys::Performance` API if the `wasm` feature is enabled,
+    /// or from the `std::time::SystemTime` API otherwise.
     ///
     /// # Examples
     ///
+    /// ```ignore
+    /// let start_time = Self::current_time();
     /// ```
-    /// # #[cfg(feature = "debug")]
-    /// # mod tests {
-    /// #     use super::*;
-    /// #     
-    /// #     #[test]
-    /// #     fn test_current_time() {
-    /// #         let time = current_time();
-    /// #         assert!(time > 0.0);
-    /// #     }
-    /// # }
-    /// ```
-    #[cfg(feature = "debug")]
     fn current_time() -> f64 {
         #[cfg(feature = "wasm")]
         {
@@ -440,6 +1108,68 @@ impl CANParser {
         }
     }
 
+    /// Decodes a `Stream` of lines into a `Stream` of messages.
+    ///
+    /// The lines can be `String`s, `Vec<u8>`s or any other byte buffers, without the trailing
+    /// newline. Byte streams that are not split into lines, such as the chunks of an upload, can
+    /// be read through `stream_reader` once wrapped in an `AsyncRead` (e.g. `tokio_util::io::StreamReader`).
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The parser, shared so that its `filtered_spec` can be read while or after the stream is consumed.
+    /// * `lines` - The lines to decode.
+    ///
+    /// # Returns
+    ///
+    /// A `MessageStream` yielding the decoded messages in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use can_parser::{CANParser, CANParserError, ErrorPolicy, LOG_FORMAT_CANDUMP};
+    /// use futures_util::StreamExt;
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), CANParserError> {
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None)?;
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP)?;
+    /// let parser = Arc::new(parser);
+    /// let lines = futures_util::stream::iter(vec![Ok::<_, std::io::Error>("(1.0) can0 123#11")]);
+    /// let mut messages = parser.clone().stream_lines(lines);
+    /// while let Some(message) = messages.next().await {
+    ///     println!("{:?}", message?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn stream_lines<S, L>(self: Arc<Self>, lines: S) -> MessageStream<S>
+    where
+        S: futures_core::Stream<Item = std::io::Result<L>> + Unpin,
+        L: AsRef<[u8]>,
+    {
+        MessageStream::new(self, lines)
+    }
+
+    /// Decodes the lines read from an `AsyncRead`, such as a socket or a file, into a `Stream` of messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The parser, shared so that its `filtered_spec` can be read while or after the stream is consumed.
+    /// * `reader` - The reader to decode.
+    ///
+    /// # Returns
+    ///
+    /// A `MessageStream` yielding the decoded messages in order.
+    #[cfg(feature = "async")]
+    pub fn stream_reader<R>(self: Arc<Self>, reader: R) -> MessageStream<ReaderLines<R>>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        MessageStream::new(self, ReaderLines::new(reader))
+    }
+
     /// Parses a single line of CAN data and returns a `CANMessage` if successful.
     ///
     /// # Arguments
@@ -449,11 +1179,19 @@ impl CANParser {
     ///
     /// # Returns
     ///
-    /// Returns a `Result` containing a `CANMessage` if parsing was successful, or a `CANParserError` if an error occurred.
+    /// Returns a `Result` containing a `CANMessage` if parsing was successful, a
+    /// `CANParserError::LineSkipped` if the message does not match the filter or the line is a
+    /// header or comment line of the log format, or a `CANParserError` if an error occurred.
     ///
     pub fn parse_line(&self, line: String) -> Result<CANMessage, CANParserError> {
-        match Self::parse_line_inner(&self.specs, &self.line_regex, &line, &self.filtered_spec) {
-            Ok(message) => Ok(message),
+        match self.parse_line_inner(&line, self.filter.as_ref()) {
+            Ok(Some(message)) => Ok(message),
+            Ok(None) => Err(CANParserError::LineSkipped(match self.log_format {
+                Some(log_format) if log_format.is_non_frame(&line) => {
+                    format!("Header or comment line of the {} log", log_format.name())
+                }
+                _ => "Message does not match the filter".to_string(),
+            })),
             Err(e) => Err(CANParserError::ParserError(format!(
                 "Failed to parse line: {}",
                 e
@@ -461,99 +1199,339 @@ impl CANParser {
         }
     }
 
-    /// Handles parsing errors based on the error handling option specified.
+    /// Handles parsing errors based on the error policy specified.
+    ///
+    /// Once more lines have failed than the policy allows, the parse is marked as aborted and
+    /// later failures are no longer recorded. When parsing in parallel, lines are parsed out of
+    /// order, so the failures recorded are not necessarily the first ones in the input.
     ///
     /// # Arguments
     ///
-    /// * `error_handling` - A reference to a string containing the error handling option.
-    /// * `errors` - A reference to an Arc wrapped Mutex containing a vector of error strings.
-    /// * `error` - A string containing the error message.
-    /// * `line` - A reference to a string containing the line where the error occurred.
+    /// * `error_handling` - A reference to the `ErrorPolicy` of the parser.
+    /// * `state` - A reference to an Arc wrapped `ParseState` collecting the diagnostics.
+    /// * `diagnostic` - A function building the diagnostic, only called if it is recorded.
     fn handle_parsing_error(
-        error_handling: &String,
-        errors: &Arc<Mutex<Vec<String>>>,
-        error: String,
-        line: &String,
+        error_handling: &ErrorPolicy,
+        state: &Arc<ParseState>,
+        diagnostic: impl FnOnce() -> Diagnostic,
     ) {
-        match error_handling.as_str() {
-            ERROR_IGNORE => {}
-            ERROR_WARN => {
-                errors.lock().unwrap().push(format!("{}: {}", line, error));
+        if *error_handling == ErrorPolicy::Ignore {
+            return;
+        }
+        let mut diagnostics = state.diagnostics.lock().unwrap();
+        if state.is_aborted() {
+            return;
+        }
+        diagnostics.push(diagnostic());
+        if let Some(limit) = error_handling.limit() {
+            if diagnostics.len() > limit {
+                state.aborted.store(true, Ordering::Relaxed);
             }
-            _ => {
-                panic!("Unknown error handling option: {}", error_handling);
+        }
+    }
+
+    /// Stores the messages of a parse call, appending them to `self.messages` in append mode.
+    fn store_messages(&mut self, messages: Vec<CANMessage>) {
+        if self.columnar {
+            if !self.append {
+                self.store.clear();
             }
+            self.store.extend(messages);
+        } else if self.append {
+            self.messages.extend(messages);
+        } else {
+            self.messages = messages;
+        }
+    }
+
+    /// Runs a parse into a sink and stores its messages. In columnar mode the messages go straight
+    /// into `self.store`, without collecting them first.
+    fn parse_stored(
+        &mut self,
+        parse: impl FnOnce(&mut Self, &mut dyn MessageSink) -> Result<(), CANParserError>,
+    ) -> Result<(), CANParserError> {
+        if self.columnar {
+            let mut store = std::mem::take(&mut self.store);
+            if !self.append {
+                store.clear();
+            }
+            let result = parse(self, &mut store);
+            self.store = store;
+            result
+        } else {
+            let mut messages = Vec::new();
+            let result = parse(self, &mut messages);
+            self.store_messages(messages);
+            result
+        }
+    }
+
+    /// Finishes a parse into a sink: stores the diagnostics, hands them to the sink and tells the
+    /// sink that parsing is done.
+    ///
+    /// The diagnostics are stored even if feeding the sink failed, in which case the sink is not
+    /// called again.
+    ///
+    /// # Arguments
+    ///
+    /// * `parsed` - The result of feeding the messages to the sink.
+    /// * `state` - The `ParseState` of the parse.
+    /// * `sink` - The `MessageSink` receiving the results.
+    ///
+    /// # Returns
+    ///
+    /// The result of `finish_diagnostics`, or the first error returned by the sink.
+    fn finish_sink(
+        &mut self,
+        parsed: Result<(), CANParserError>,
+        state: Arc<ParseState>,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let result = self.finish_diagnostics(state);
+        parsed?;
+        if let Err(CANParserError::ParserWarning(diagnostics))
+        | Err(CANParserError::ParseAborted(diagnostics))
+        | Err(CANParserError::ParseCancelled(diagnostics)) = &result
+        {
+            for diagnostic in &diagnostics.entries {
+                sink.on_warning(diagnostic)?;
+            }
+        }
+        sink.on_finish(&self.filtered_spec)?;
+        result
+    }
+
+    /// Reports the final progress, then orders the diagnostics collected while parsing and stores
+    /// them in `self.diagnostics`, adding them to the diagnostics of previous calls in append mode.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every line was parsed, a `CANParserError::ParseCancelled` if the parse was
+    /// cancelled, a `CANParserError::ParseAborted` if the error policy stopped the parse,
+    /// otherwise a `CANParserError::ParserWarning` carrying the diagnostics of this call.
+    fn finish_diagnostics(&mut self, state: Arc<ParseState>) -> Result<(), CANParserError> {
+        if !state.is_stopped() {
+            state.progress.report();
+        }
+        let mut diagnostics = std::mem::take(&mut *state.diagnostics.lock().unwrap());
+        diagnostics.sort();
+        let diagnostics = if self.append {
+            Arc::make_mut(&mut self.diagnostics).extend(diagnostics.clone());
+            Arc::new(diagnostics)
+        } else {
+            self.diagnostics = Arc::new(diagnostics);
+            self.diagnostics.clone()
+        };
+        if state.progress.is_cancelled() {
+            Err(CANParserError::ParseCancelled(diagnostics))
+        } else if state.is_aborted() {
+            Err(CANParserError::ParseAborted(diagnostics))
+        } else if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(CANParserError::ParserWarning(diagnostics))
         }
     }
 
     /// Parses a single line of CAN data and returns a `CANMessage` struct containing the parsed data.
     ///
+    /// The line is split by the parser's built-in log format, or matched with its line regex,
+    /// decoded with its specifications, and any protocols seen in the line are recorded in its
+    /// `flags`. Messages not matching `filter` are dropped as soon as their ID is parsed, and the
+    /// header, comment and trailer lines of the log format are skipped.
+    ///
     /// # Arguments
     ///
-    /// * `annex` - An optional `Arc` reference to a `Specs` struct containing additional specifications.
-    /// * `line_regex` - An `Arc` reference to a `Regex` struct used to match the line of data.
     /// * `line` - The line of data to be parsed.
-    /// * `spec` - An `Arc` reference to a `FilteredSpec` struct containing the specifications for the parsed data.
+    /// * `filter` - The filter of the parse, usually `self.filter`.
     ///
     /// # Returns
     ///
-    /// Returns a `Result` containing a `CANMessage` struct if the parsing was successful, or a `String` error message if parsing failed.
+    /// Returns a `Result` containing the `CANMessage` if the parsing was successful or `None` if
+    /// it was filtered out or skipped, or a `LineError` describing why parsing failed.
     fn parse_line_inner(
-        annex: &Option<Arc<Specs>>,
-        line_regex: &Arc<Regex>,
+        &self,
         line: &str,
-        spec: &Arc<FilteredSpec>,
-    ) -> Result<CANMessage, String> {
-        let captures = line_regex
-            .captures(line)
-            .ok_or_else(|| "No captures found".to_string())?;
+        filter: Option<&MessageFilter>,
+    ) -> Result<Option<CANMessage>, LineError> {
+        use DiagnosticKind::*;
+
+        let fields = match self.log_format {
+            Some(log_format) => match log_format.split(line) {
+                Some(fields) => fields,
+                None if log_format.is_non_frame(line) => return Ok(None),
+                None => {
+                    return Err(LineError::new(
+                        RegexMiss,
+                        format!("Not a {} frame", log_format.name()),
+                    ))
+                }
+            },
+            None => LineFields::from_captures(
+                &self
+                    .line_regex
+                    .captures(line)
+                    .ok_or_else(|| LineError::new(RegexMiss, "No captures found"))?,
+            ),
+        };
 
         let mut msg = CANMessage::default();
 
-        if let Some(timestamp) = captures.name("timestamp") {
-            msg.ts = timestamp
-                .as_str()
-                .parse::<f64>()
-                .map_err(|_| "Failed to parse timestamp".to_string())?;
+        if let Some(ts) = self
+            .timestamp_format
+            .parse_fields(&fields)
+            .map_err(LineError::of(BadTimestamp))?
+        {
+            msg.ts = ts;
+        }
+
+        if let Some(id) = fields.id {
+            let extended = self.extended_ids.or_else(|| fields.ext.then_some(true));
+            parse_id(id, &mut msg.id, extended).map_err(LineError::of(BadId))?;
+        }
+        if fields.rtr {
+            msg.id.flags.rtr = true;
         }
 
-        if let Some(data) = captures.name("data") {
-            let data = data.as_str();
-            let length = data.len().min(64);
-            for i in (0..length).step_by(2) {
-                msg.data.data[i / 2] = u8::from_str_radix(&data[i..i + 2], 16)
-                    .map_err(|_| "Failed to parse data".to_string())?;
+        if let Some(filter) = filter {
+            if !filter.matches_fields(&msg.id, msg.ts, fields.channel) {
+                return Ok(None);
             }
-            msg.data.len = (length / 2) as u8;
-        }
-        if let Some(id) = captures.name("id") {
-            parse_id(id, &mut msg.id);
-            if let Some(ref a) = annex {
-                if msg.id.flags.ext {
-                    let mut hit = false;
-                    if let Some(cache_result) = spec.j1939.read().unwrap().get(&msg.id.pgn) {
-                        parse_j1939_data(&mut msg.data, &cache_result.spns);
-                        hit = true;
-                    }
-                    if !hit {
-                        if let Some(ref j1939) = a.j1939 {
-                            match j1939.get_id_metadata(&msg.id).map_err(|e| {
-                                format!("Failed to get metadata for PGN {}: {}", msg.id.pgn, e)
-                            })? {
-                                Metadata::J1939(aux) => {
-                                    // Insert aux_info using a write lock.
-                                    spec.j1939.write().unwrap().insert(msg.id.pgn, aux.clone());
-                                    parse_j1939_data(&mut msg.data, &aux.spns);
-                                }
-                                _ => {}
-                            }
+        }
+
+        if let Some(channel) = fields.channel {
+            msg.channel = Some(channel.to_string());
+        }
+
+        if let Some(direction) = fields.direction {
+            msg.direction = Some(
+                direction
+                    .parse::<Direction>()
+                    .map_err(LineError::of(BadField))?,
+            );
+        }
+
+        if let Some(fd_flags) = fields.fd_flags {
+            let fd_flags = u8::from_str_radix(fd_flags, 16)
+                .map_err(|_| LineError::new(BadField, "Failed to parse CAN FD flags"))?;
+            msg.id.flags.fd = true;
+            msg.id.flags.brs = (fd_flags & CANFD_BRS) == CANFD_BRS;
+            msg.id.flags.esi = (fd_flags & CANFD_ESI) == CANFD_ESI;
+            if !self.flags.read().unwrap().canfd {
+                self.flags.write().unwrap().canfd = true;
+            }
+        }
+
+        if let Some(data) = fields.data {
+            let mut buffer = [0u8; MAX_HEX_DIGITS];
+            let data = compact_hex(data.as_bytes(), &mut buffer)
+                .ok_or_else(|| LineError::new(BadLength, "Data is longer than a CAN FD frame"))?;
+            if data.len() % 2 != 0 {
+                return Err(LineError::new(
+                    BadHex,
+                    "Data has an odd number of hex digits",
+                ));
+            }
+            let length = data.len() / 2;
+            if len_to_dlc(length, msg.id.flags.fd).is_none() {
+                return Err(LineError::new(
+                    BadLength,
+                    format!(
+                        "Invalid data length {} for a {} frame (max {} bytes)",
+                        length,
+                        if msg.id.flags.fd { "CAN FD" } else { "CAN" },
+                        if msg.id.flags.fd {
+                            CANFD_MAX_DLEN
+                        } else {
+                            CAN_MAX_DLEN
                         }
-                    }
+                    ),
+                ));
+            }
+            if !decode_hex(data, &mut msg.data.data[..length]) {
+                return Err(LineError::new(BadHex, "Failed to parse data"));
+            }
+            msg.data.len = length as u8;
+        }
+        if let (true, Some(dlc)) = (fields.rtr, fields.rtr_dlc) {
+            let dlc = u8::from_str_radix(dlc, 16)
+                .map_err(|_| LineError::new(BadField, "Failed to parse remote frame DLC"))?;
+            msg.data.len = dlc_to_len(dlc, false) as u8;
+        }
+
+        if msg.id.flags.err {
+            // Error frames carry controller state rather than signals.
+            msg.error = Some(CANErrorFrame::from_frame(
+                msg.id.id,
+                &msg.data.data[..msg.data.len as usize],
+            ));
+        } else if msg.id.flags.ext
+            && !msg.id.flags.rtr
+            && self.channel_uses_spec(&msg.channel, SPEC_TYPE_J1939)
+        {
+            let mut hit = false;
+            if let Some(cache_result) = self.filtered_spec.j1939.read().unwrap().get(&msg.id.pgn) {
+                parse_j1939_data(&mut msg.data, &cache_result.spns);
+                hit = true;
+            }
+            if !hit {
+                if let Some(aux) = self.load_j1939_pgn(&msg.id).map_err(|e| {
+                    LineError::new(
+                        SpecLookup,
+                        format!("Failed to get metadata for PGN {}: {}", msg.id.pgn, e),
+                    )
+                })? {
+                    parse_j1939_data(&mut msg.data, &aux.spns);
                 }
             }
         }
 
-        Ok(msg)
+        Ok(Some(msg))
+    }
+
+    /// Reads the specification of the PGN of `id` from the J1939 specification and caches it in
+    /// `filtered_spec`. Callers check `filtered_spec` first.
+    ///
+    /// # Returns
+    ///
+    /// Returns the specification of the PGN, or `None` if no J1939 specification is loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SpecError` if the PGN cannot be read from the specification.
+    fn load_j1939_pgn(&self, id: &CANID) -> Result<Option<SpecPGN>, SpecError> {
+        let j1939 = match self.specs.as_ref().and_then(|specs| specs.j1939.as_ref()) {
+            Some(j1939) => j1939,
+            None => return Ok(None),
+        };
+        let metadata = if self.skip_invalid_spec_entries {
+            j1939.get_id_metadata_skipping_invalid(id)
+        } else {
+            j1939
+                .get_id_metadata(id)
+                .map(|metadata| (metadata, Vec::new()))
+        };
+        match metadata? {
+            (Metadata::J1939(mut aux), skipped) => {
+                if let Some(selection) = &self.signal_selection {
+                    selection.select(&mut aux);
+                }
+                // Insert aux_info using a write lock. Only the first lookup of a PGN reports the
+                // entries it skipped.
+                let first_lookup = self
+                    .filtered_spec
+                    .j1939
+                    .write()
+                    .unwrap()
+                    .insert(id.pgn, aux.clone())
+                    .is_none();
+                if first_lookup && !skipped.is_empty() {
+                    self.spec_warnings.lock().unwrap().extend(skipped);
+                }
+                Ok(Some(aux))
+            }
+            _ => Ok(None),
+        }
     }
 
     /// Converts the CANParser object to a JSON string.
@@ -574,7 +1552,7 @@ impl CANParser {
     /// Returns a CANParserError if there is an error serializing the CANParser object to JSON or writing
     /// the JSON string to a file.
     pub fn to_json(&self, output_path: Option<String>) -> Result<Option<String>, CANParserError> {
-        to_json(output_path, &self.filtered_spec, &self.messages)
+        to_json(output_path, &self.filtered_spec, self.iter_messages())
     }
 
     /// Converts the filtered CAN specification and messages to a CSV format.
@@ -589,7 +1567,84 @@ impl CANParser {
     /// * `Ok(Some(csv_string))` - If the `output_path` is not provided and the CSV data is successfully combined into a single string.
     /// * `Err(CANParserError)` - If there is an error during the serialization or saving process.
     pub fn to_csv(&self, output_path: Option<String>) -> Result<Option<String>, CANParserError> {
-        to_csv(output_path, &self.filtered_spec, &self.messages)
+        to_csv(output_path, &self.filtered_spec, self.iter_messages())
+    }
+
+    /// Converts the decoded signals to a wide, time-aligned CSV with a column per signal, for
+    /// spreadsheets and dataframes.
+    ///
+    /// Rows are `interval` apart and hold the values of each interval resampled by `resampling`.
+    /// Columns are named `label [units]` after the specification.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - An optional string representing the path to save the CSV file.
+    /// * `interval` - The interval between rows in nanoseconds.
+    /// * `resampling` - How the values of each interval are resampled.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(None)` - If the `output_path` is provided and the CSV file is successfully saved.
+    /// * `Ok(Some(csv_string))` - If the `output_path` is not provided.
+    /// * `Err(CANParserError)` - If the interval is not positive or the CSV cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, Resampling, LOG_FORMAT_CANDUMP, NANOS_PER_SEC};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_file("path/to/can/data.log").unwrap();
+    /// parser
+    ///     .to_wide_csv(Some("signals.csv".to_string()), NANOS_PER_SEC / 10, Resampling::ForwardFill)
+    ///     .unwrap();
+    /// ```
+    pub fn to_wide_csv(
+        &self,
+        output_path: Option<String>,
+        interval: i64,
+        resampling: Resampling,
+    ) -> Result<Option<String>, CANParserError> {
+        to_wide_csv(
+            output_path,
+            &self.filtered_spec,
+            self.iter_messages(),
+            interval,
+            resampling,
+        )
+    }
+
+    /// Converts the decoded signals to a long, tidy CSV with a row per signal value.
+    ///
+    /// Every row has the `LONG_CSV_COLUMNS`: the timestamp, ID, source address, PGN, SPN, label,
+    /// raw value, value and units. Fields are quoted as needed, so labels and units may hold commas.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - An optional string representing the path to save the CSV file.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(None)` - If the `output_path` is provided and the CSV file is successfully saved.
+    /// * `Ok(Some(csv_string))` - If the `output_path` is not provided.
+    /// * `Err(CANParserError)` - If the CSV cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LOG_FORMAT_CANDUMP};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_file("path/to/can/data.log").unwrap();
+    /// parser.to_long_csv(Some("signals.csv".to_string())).unwrap();
+    /// ```
+    pub fn to_long_csv(
+        &self,
+        output_path: Option<String>,
+    ) -> Result<Option<String>, CANParserError> {
+        to_long_csv(output_path, &self.filtered_spec, self.iter_messages())
     }
 
     /// Writes the parsed CAN data to an SQLite database at the specified output path.
@@ -604,15 +1659,61 @@ impl CANParser {
     ///
     /// # Examples
     ///
-    /// ```
-    /// use can_parser::CANParser;
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LOG_FORMAT_CANDUMP};
     ///
-    /// let parser = CANParser::new();
-    /// parser.parse("path/to/can/data.log").unwrap();
-    /// parser.to_sqlite("path/to/output.db").unwrap();
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_file("path/to/can/data.log").unwrap();
+    /// parser.to_sqlite("path/to/output.db".to_string()).unwrap();
     /// ```
     #[cfg(feature = "sqlite")]
     pub fn to_sqlite(&self, output_path: String) -> Result<(), CANParserError> {
-        to_sqlite(output_path, &self.filtered_spec, &self.messages)
+        to_sqlite(output_path, &self.filtered_spec, self.iter_messages())
+    }
+
+    /// Writes the selected parsed messages back out as a log, losslessly, so it can be replayed
+    /// with can-utils or loaded into other tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - An optional path to save the log to.
+    /// * `format` - The format of the log.
+    /// * `selection` - The messages to write, see `MessageFilter`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(None)` - If the `output_path` is provided and the log is successfully saved.
+    /// * `Ok(Some(log))` - If the `output_path` is not provided, with the bytes of the log.
+    /// * `Err(CANParserError)` - If there is an error writing the log.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LogWriterFormat, MessageFilter, LOG_FORMAT_CANDUMP};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_file("path/to/can/data.log").unwrap();
+    /// let selection = MessageFilter {
+    ///     pgns: Some(vec![61444]),
+    ///     ..Default::default()
+    /// };
+    /// parser
+    ///     .write_log(Some("path/to/eec1.log".to_string()), LogWriterFormat::Candump, &selection)
+    ///     .unwrap();
+    /// ```
+    pub fn write_log(
+        &self,
+        output_path: Option<String>,
+        format: LogWriterFormat,
+        selection: &MessageFilter,
+    ) -> Result<Option<Vec<u8>>, CANParserError> {
+        write_log(
+            output_path,
+            format,
+            self.iter_messages()
+                .filter(|message| selection.matches(message)),
+        )
     }
 }
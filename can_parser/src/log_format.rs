@@ -0,0 +1,478 @@
+use crate::can_message::CANFD_MAX_DLEN;
+use crate::error::CANParserError;
+use regex::Captures;
+
+pub const LOG_FORMAT_CANDUMP: &str = "candump";
+pub const LOG_FORMAT_ASC: &str = "asc";
+pub const LOG_FORMAT_TRC: &str = "trc";
+
+/// The line regex of candump logs, e.g. `(1692896400.123456) can0 18FEF100#0011223344556677`.
+const CANDUMP_REGEX: &str = r"^\((?P<timestamp>[0-9]+\.[0-9]+)\)[ \t]*(?:(?P<channel>[A-Za-z0-9_-]+)[ \t]+)?.*?(?P<id>[0-9A-F]{3,8})#(?:#(?P<fd_flags>[0-9A-F]))?(?:(?P<rtr>R)(?P<rtr_dlc>[0-9A-F])?|(?P<data>[0-9A-F]*))(?:[ \t]+(?P<direction>[RT])(?-u:\b))?";
+/// The line regex of the classic CAN frames of Vector ASC logs, e.g.
+/// `0.001000 1  18FEF100x       Rx   d 8 00 11 22 33 44 55 66 77`.
+const ASC_REGEX: &str = r"^[ \t]*(?P<timestamp>[0-9]+\.[0-9]+)[ \t]+(?P<channel>[0-9]+)[ \t]+(?P<id>[0-9A-Fa-f]+)(?P<ext>x)?[ \t]+(?P<direction>Rx|Tx)[ \t]+(?:[dD][ \t]+[0-9A-Fa-f](?-u:\b)(?P<data>(?:[ \t]+[0-9A-Fa-f]{2}(?-u:\b))*)|(?P<rtr>[rR])(?:[ \t]+(?P<rtr_dlc>[0-9A-Fa-f])(?-u:\b))?)";
+/// The line regex of the classic CAN data frames of PEAK TRC 2.x logs, e.g.
+/// `1  1059.900 DT 1 0300 Rx - 8  00 11 22 33 44 55 66 77`.
+const TRC_REGEX: &str = r"^[ \t]*[0-9]+[ \t]+(?P<timestamp_ms>[0-9]+\.[0-9]+)[ \t]+DT[ \t]+(?:(?P<channel>[0-9]+)[ \t]+)?(?P<id>[0-9A-F]{4,8})[ \t]+(?P<direction>Rx|Tx)[ \t]+(?:-[ \t]+)?[0-9]+(?P<data>(?:[ \t]+[0-9A-F]{2}(?-u:\b))*)";
+
+/// The `fd_flags` field of CAN FD frames, indexed by their BRS and ESI bits.
+const FD_FLAGS: [&str; 4] = ["0", "1", "2", "3"];
+
+/// The number of hex digits of the largest CAN FD payload.
+pub(crate) const MAX_HEX_DIGITS: usize = 2 * CANFD_MAX_DLEN;
+
+/// The text log formats with a built-in template.
+///
+/// Lines of these formats are split by hand-written parsers instead of the line regex, which is
+/// several times faster. The parser uses them when its line regex is the `regex` of a format, or
+/// when one is selected with `CANParser::set_log_format`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// The output of can-utils `candump -L` or `candump -l`, with optional direction.
+    Candump,
+    /// Vector ASC logs with hexadecimal IDs, including CAN FD frames.
+    Asc,
+    /// PEAK TRC logs, versions 1.1 to 2.1, including CAN FD frames. Timestamps are the offset from the start of the log.
+    Trc,
+}
+
+impl LogFormat {
+    /// Creates a `LogFormat` from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of "candump", "asc" or "trc".
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the name is unknown.
+    pub fn from_name(name: &str) -> Result<Self, CANParserError> {
+        match name.to_ascii_lowercase().as_str() {
+            LOG_FORMAT_CANDUMP => Ok(LogFormat::Candump),
+            LOG_FORMAT_ASC => Ok(LogFormat::Asc),
+            LOG_FORMAT_TRC => Ok(LogFormat::Trc),
+            _ => Err(CANParserError::ParserError(format!(
+                "Unknown log format: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the format whose template is `regex`, if any.
+    pub fn from_regex(regex: &str) -> Option<Self> {
+        [LogFormat::Candump, LogFormat::Asc, LogFormat::Trc]
+            .into_iter()
+            .find(|format| format.regex() == regex)
+    }
+
+    /// Returns the name of the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogFormat::Candump => LOG_FORMAT_CANDUMP,
+            LogFormat::Asc => LOG_FORMAT_ASC,
+            LogFormat::Trc => LOG_FORMAT_TRC,
+        }
+    }
+
+    /// Returns the line regex template of the format.
+    ///
+    /// The hand-written parsers read every line the template matches. The ASC and TRC templates
+    /// only match classic CAN frames of the most common layout, while their parsers also read
+    /// CAN FD frames and the other TRC versions.
+    pub fn regex(&self) -> &'static str {
+        match self {
+            LogFormat::Candump => CANDUMP_REGEX,
+            LogFormat::Asc => ASC_REGEX,
+            LogFormat::Trc => TRC_REGEX,
+        }
+    }
+
+    /// Splits a line into its fields.
+    ///
+    /// # Returns
+    ///
+    /// The fields of the line, or `None` if it is not a frame of the format, e.g. a header,
+    /// comment or status line.
+    pub(crate) fn split<'a>(&self, line: &'a str) -> Option<LineFields<'a>> {
+        match self {
+            LogFormat::Candump => split_candump(line),
+            LogFormat::Asc => split_asc(line),
+            LogFormat::Trc => split_trc(line),
+        }
+    }
+
+    /// Returns `true` if `line` is a known line of the format that holds no frame, such as a
+    /// header, comment or trailer line, so that it is skipped instead of reported.
+    pub(crate) fn is_non_frame(&self, line: &str) -> bool {
+        match self {
+            LogFormat::Candump => false,
+            LogFormat::Asc => is_asc_non_frame(line),
+            // TRC headers and comments start with `;`
+            LogFormat::Trc => {
+                let line = line.trim_start();
+                line.is_empty() || line.starts_with(';')
+            }
+        }
+    }
+}
+
+/// The fields of a log line, as captured by the line regex or split by a `LogFormat`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LineFields<'a> {
+    /// The `timestamp` group, in seconds, or the `timestamp_ms` group, in milliseconds.
+    pub timestamp: Option<&'a str>,
+    /// Whether `timestamp` is in milliseconds.
+    pub timestamp_ms: bool,
+    /// The `date` group.
+    pub date: Option<&'a str>,
+    /// The `time` group.
+    pub time: Option<&'a str>,
+    /// The `channel` group.
+    pub channel: Option<&'a str>,
+    /// The `direction` group.
+    pub direction: Option<&'a str>,
+    /// The `id` group, in hex.
+    pub id: Option<&'a str>,
+    /// Whether the line marks the ID as extended, with an `ext` group.
+    pub ext: bool,
+    /// The `fd_flags` group, in hex, present on CAN FD frames.
+    pub fd_flags: Option<&'a str>,
+    /// The `data` group, in hex. Whitespace between the bytes is allowed.
+    pub data: Option<&'a str>,
+    /// Whether the frame is a remote frame, with an `rtr` group.
+    pub rtr: bool,
+    /// The `rtr_dlc` group, in hex.
+    pub rtr_dlc: Option<&'a str>,
+}
+
+impl<'a> LineFields<'a> {
+    /// Collects the named groups captured by the line regex.
+    pub(crate) fn from_captures(captures: &Captures<'a>) -> Self {
+        let group = |name: &str| captures.name(name).map(|m| m.as_str());
+        let (timestamp, timestamp_ms) = match group("timestamp") {
+            Some(timestamp) => (Some(timestamp), false),
+            None => (group("timestamp_ms"), true),
+        };
+        Self {
+            timestamp,
+            timestamp_ms,
+            date: group("date"),
+            time: group("time"),
+            channel: group("channel"),
+            direction: group("direction"),
+            id: group("id"),
+            ext: captures.name("ext").is_some(),
+            fd_flags: group("fd_flags"),
+            data: group("data"),
+            rtr: captures.name("rtr").is_some(),
+            rtr_dlc: group("rtr_dlc"),
+        }
+    }
+}
+
+/// Converts an ASCII hex digit to its value. It has no branches so that loops over it vectorize,
+/// and is only meaningful for valid digits.
+#[inline(always)]
+fn nibble(digit: u8) -> u8 {
+    (digit & 0x0F) + 9 * (digit >> 6)
+}
+
+/// Decodes hex digits into bytes.
+///
+/// The digits are validated and converted in two branch-free passes, which the compiler turns
+/// into SIMD instructions, instead of parsing them pair by pair.
+///
+/// # Arguments
+///
+/// * `hex` - The hex digits, without separators. Must hold twice as many digits as `out` has bytes.
+/// * `out` - The bytes to decode into.
+///
+/// # Returns
+///
+/// `false` if `hex` holds anything other than hex digits.
+pub(crate) fn decode_hex(hex: &[u8], out: &mut [u8]) -> bool {
+    debug_assert_eq!(hex.len(), 2 * out.len());
+    let valid = hex
+        .iter()
+        .fold(true, |valid, digit| valid & digit.is_ascii_hexdigit());
+    for (byte, pair) in out.iter_mut().zip(hex.chunks_exact(2)) {
+        *byte = (nibble(pair[0]) << 4) | nibble(pair[1]);
+    }
+    valid
+}
+
+/// Removes the whitespace between the bytes of a data field, e.g. `00 11 22`.
+///
+/// # Returns
+///
+/// `hex` itself if it holds no whitespace, the digits copied into `buffer` otherwise, or `None` if
+/// there are more digits than fit in `buffer`.
+pub(crate) fn compact_hex<'b>(
+    hex: &'b [u8],
+    buffer: &'b mut [u8; MAX_HEX_DIGITS],
+) -> Option<&'b [u8]> {
+    if !hex.iter().any(u8::is_ascii_whitespace) {
+        return Some(hex);
+    }
+    let mut length = 0;
+    for &digit in hex.iter().filter(|digit| !digit.is_ascii_whitespace()) {
+        *buffer.get_mut(length)? = digit;
+        length += 1;
+    }
+    Some(&buffer[..length])
+}
+
+/// Returns the part of `line` from the start of `first` to the end of `last`, two tokens of it.
+fn span<'a>(line: &'a str, first: &str, last: &str) -> &'a str {
+    let start = first.as_ptr() as usize - line.as_ptr() as usize;
+    let end = last.as_ptr() as usize + last.len() - line.as_ptr() as usize;
+    &line[start..end]
+}
+
+/// Returns the part of `line` spanned by `tokens`, or an empty string if there are none.
+fn span_all<'a>(line: &'a str, tokens: &[&'a str]) -> &'a str {
+    match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => span(line, first, last),
+        _ => "",
+    }
+}
+
+/// Returns `true` if `token` is a direction written as `Rx` or `Tx`.
+fn is_direction(token: &str) -> bool {
+    token.eq_ignore_ascii_case("rx") || token.eq_ignore_ascii_case("tx")
+}
+
+/// Returns `true` if `token` is a `0` or `1` flag.
+fn is_flag(token: &str) -> bool {
+    token == "0" || token == "1"
+}
+
+/// Splits a candump line, `(timestamp) channel id#data [direction]`.
+///
+/// The frame may be `id#data`, `id##<fd_flags>data` for CAN FD frames or `id#R[dlc]` for remote
+/// frames.
+fn split_candump(line: &str) -> Option<LineFields<'_>> {
+    let (timestamp, rest) = line.strip_prefix('(')?.split_once(')')?;
+    let mut tokens = rest.split_ascii_whitespace();
+    let mut channel = None;
+    let frame = loop {
+        let token = tokens.next()?;
+        if token.contains('#') {
+            break token;
+        }
+        channel = channel.or(Some(token));
+    };
+    let (id, payload) = frame.split_once('#')?;
+    if !(3..=8).contains(&id.len()) {
+        return None;
+    }
+    let mut fields = LineFields {
+        timestamp: Some(timestamp),
+        channel,
+        id: Some(id),
+        ..LineFields::default()
+    };
+    if let Some(payload) = payload.strip_prefix('#') {
+        fields.fd_flags = Some(payload.get(..1)?);
+        fields.data = Some(&payload[1..]);
+    } else if let Some(dlc) = payload.strip_prefix('R') {
+        fields.rtr = true;
+        fields.rtr_dlc = dlc.get(..1);
+    } else {
+        fields.data = Some(payload);
+    }
+    fields.direction = tokens.next().filter(|token| *token == "R" || *token == "T");
+    Some(fields)
+}
+
+/// Splits an ASC ID into the ID and whether it has the `x` suffix of extended IDs.
+fn split_asc_id(token: &str) -> (&str, bool) {
+    match token.strip_suffix(|c| c == 'x' || c == 'X') {
+        Some(id) => (id, true),
+        None => (token, false),
+    }
+}
+
+/// Splits a Vector ASC frame line. Classic frames are written
+/// `timestamp channel id[x] direction d dlc data... [extra]` or
+/// `timestamp channel id[x] direction r [dlc]`, and CAN FD frames
+/// `timestamp CANFD channel direction id[x] [name] brs esi dlc length data... [extra]`.
+fn split_asc(line: &str) -> Option<LineFields<'_>> {
+    let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+    let (&timestamp, rest) = tokens.split_first()?;
+    let mut fields = LineFields {
+        timestamp: Some(timestamp),
+        ..LineFields::default()
+    };
+    if rest.first()?.eq_ignore_ascii_case("canfd") {
+        let &[_, channel, direction, id, ref rest @ ..] = rest else {
+            return None;
+        };
+        if !is_direction(direction) {
+            return None;
+        }
+        // A symbolic message name may follow the ID.
+        let rest = match rest {
+            [brs, esi, ..] if is_flag(brs) && is_flag(esi) => rest,
+            [_, rest @ ..] => rest,
+            [] => return None,
+        };
+        let &[brs, esi, _dlc, length, ref rest @ ..] = rest else {
+            return None;
+        };
+        let length: usize = length.parse().ok()?;
+        let (id, ext) = split_asc_id(id);
+        fields.channel = Some(channel);
+        fields.direction = Some(direction);
+        fields.id = Some(id);
+        fields.ext = ext;
+        fields.fd_flags = Some(FD_FLAGS[(brs == "1") as usize + 2 * (esi == "1") as usize]);
+        fields.data = Some(span_all(line, rest.get(..length)?));
+    } else {
+        let &[channel, id, direction, kind, ref rest @ ..] = rest else {
+            return None;
+        };
+        if !channel.bytes().all(|b| b.is_ascii_digit()) || !is_direction(direction) {
+            return None;
+        }
+        let (id, ext) = split_asc_id(id);
+        fields.channel = Some(channel);
+        fields.direction = Some(direction);
+        fields.id = Some(id);
+        fields.ext = ext;
+        if kind.eq_ignore_ascii_case("d") {
+            let (dlc, rest) = rest.split_first()?;
+            let length = u8::from_str_radix(dlc, 16).ok()?.min(8) as usize;
+            fields.data = Some(span_all(line, rest.get(..length)?));
+        } else if kind.eq_ignore_ascii_case("r") {
+            fields.rtr = true;
+            fields.rtr_dlc = rest.first().copied().filter(|dlc| dlc.len() == 1);
+        } else {
+            return None;
+        }
+    }
+    Some(fields)
+}
+
+/// The lines of the header and trailer of ASC logs, in lowercase, e.g. `date Thu Aug 24 ...`,
+/// `base hex  timestamps absolute` or `End TriggerBlock`.
+const ASC_NON_FRAME_PREFIXES: [&str; 6] = [
+    "date ",
+    "base ",
+    "internal events logged",
+    "no internal events logged",
+    "begin triggerblock",
+    "end triggerblock",
+];
+
+/// Returns `true` if `line` is a blank, comment, header or trailer line of an ASC log, or its
+/// `Start of measurement` event.
+fn is_asc_non_frame(line: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with("//") {
+        return true;
+    }
+    let starts_with = |prefix: &str| {
+        line.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+    if ASC_NON_FRAME_PREFIXES
+        .iter()
+        .any(|prefix| starts_with(prefix))
+    {
+        return true;
+    }
+    line.split_once(|c: char| c.is_ascii_whitespace())
+        .is_some_and(|(_, event)| {
+            event
+                .trim_start()
+                .eq_ignore_ascii_case("start of measurement")
+        })
+}
+
+/// Splits a PEAK TRC frame line.
+///
+/// Version 1.x lines are written `number) offset [bus] [direction] id [-] dlc data...` or
+/// `number) offset [bus] [direction] id [-] dlc RTR`, and version 2.x lines
+/// `number offset type [bus] id direction [-] length data...`, where `type` is `DT` for classic
+/// frames, `FD`, `FB`, `FE` or `BI` for CAN FD frames and `RR` for remote frames. Offsets are in
+/// milliseconds.
+fn split_trc(line: &str) -> Option<LineFields<'_>> {
+    let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+    let &[number, timestamp, ref rest @ ..] = tokens.as_slice() else {
+        return None;
+    };
+    let mut fields = LineFields {
+        timestamp: Some(timestamp),
+        timestamp_ms: true,
+        ..LineFields::default()
+    };
+    let data = if number.ends_with(')') {
+        let rest = match *rest {
+            [direction, ref rest @ ..] if is_direction(direction) => {
+                fields.direction = Some(direction);
+                rest
+            }
+            [bus, direction, ref rest @ ..] if is_direction(direction) => {
+                fields.channel = Some(bus);
+                fields.direction = Some(direction);
+                rest
+            }
+            _ => rest,
+        };
+        let (&id, rest) = rest.split_first()?;
+        let rest = match rest {
+            ["-", rest @ ..] => rest,
+            _ => rest,
+        };
+        let (&dlc, data) = rest.split_first()?;
+        fields.id = Some(id);
+        if data
+            .first()
+            .is_some_and(|token| token.eq_ignore_ascii_case("rtr"))
+        {
+            fields.rtr = true;
+            fields.rtr_dlc = Some(dlc);
+            return Some(fields);
+        }
+        data
+    } else {
+        let (&kind, rest) = rest.split_first()?;
+        let rest = match *rest {
+            [id, direction, ref rest @ ..] if is_direction(direction) => {
+                fields.id = Some(id);
+                fields.direction = Some(direction);
+                rest
+            }
+            [bus, id, direction, ref rest @ ..] if is_direction(direction) => {
+                fields.channel = Some(bus);
+                fields.id = Some(id);
+                fields.direction = Some(direction);
+                rest
+            }
+            _ => return None,
+        };
+        let rest = match rest {
+            ["-", rest @ ..] => rest,
+            _ => rest,
+        };
+        let (&length, data) = rest.split_first()?;
+        match kind {
+            "DT" => {}
+            "FD" => fields.fd_flags = Some(FD_FLAGS[0]),
+            "FB" => fields.fd_flags = Some(FD_FLAGS[1]),
+            "FE" => fields.fd_flags = Some(FD_FLAGS[2]),
+            "BI" => fields.fd_flags = Some(FD_FLAGS[3]),
+            "RR" => {
+                fields.rtr = true;
+                fields.rtr_dlc = Some(length);
+                return Some(fields);
+            }
+            // Status, error and event lines
+            _ => return None,
+        }
+        data
+    };
+    fields.data = Some(span_all(line, data));
+    Some(fields)
+}
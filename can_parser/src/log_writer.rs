@@ -0,0 +1,578 @@
+use crate::can_message::{
+    len_to_dlc, CANMessage, Direction, CANFD_BRS, CANFD_ESI, CANID, CAN_EFF_FLAG, CAN_ERR_FLAG,
+};
+use crate::error::CANParserError;
+use crate::log_format::{LOG_FORMAT_ASC, LOG_FORMAT_CANDUMP};
+use crate::sink::MessageSink;
+use crate::specification::FilteredSpec;
+use crate::timestamp::{format_seconds, CivilTime};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Seek, SeekFrom, Write};
+
+pub const LOG_FORMAT_BLF: &str = "blf";
+
+/// The channel of messages without one in candump logs.
+const CANDUMP_DEFAULT_CHANNEL: &str = "can0";
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The ASC flags of CAN FD frames.
+const ASC_FD_EDL: u32 = 0x1000;
+const ASC_FD_BRS: u32 = 0x2000;
+const ASC_FD_ESI: u32 = 0x4000;
+
+const BLF_FILE_HEADER_SIZE: usize = 144;
+const BLF_OBJECT_HEADER_SIZE: usize = 32;
+const BLF_CONTAINER_HEADER_SIZE: usize = 32;
+/// The uncompressed size at which the buffered objects are written out as a log container.
+const BLF_MAX_CONTAINER_SIZE: usize = 128 * 1024;
+/// BLF object types.
+const BLF_CAN_MESSAGE: u32 = 1;
+const BLF_LOG_CONTAINER: u32 = 10;
+const BLF_CAN_ERROR_EXT: u32 = 73;
+const BLF_CAN_FD_MESSAGE: u32 = 100;
+/// Object timestamps are in nanoseconds.
+const BLF_TIME_ONE_NANS: u32 = 2;
+/// Message flags.
+const BLF_DIR_TX: u8 = 0x01;
+const BLF_REMOTE_FLAG: u8 = 0x80;
+/// CAN FD flags.
+const BLF_FD_EDL: u8 = 0x01;
+const BLF_FD_BRS: u8 = 0x02;
+const BLF_FD_ESI: u8 = 0x04;
+
+/// The formats of the logs `CANParser` writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogWriterFormat {
+    /// The `candump -L` format of can-utils, replayable with `canplayer`.
+    Candump,
+    /// Vector ASC with hexadecimal IDs and absolute timestamps.
+    Asc,
+    /// Vector BLF, the binary logging format of CANoe and CANalyzer.
+    Blf,
+}
+
+impl LogWriterFormat {
+    /// Creates a `LogWriterFormat` from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of "candump", "asc" or "blf".
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the name is unknown.
+    pub fn from_name(name: &str) -> Result<Self, CANParserError> {
+        match name.to_ascii_lowercase().as_str() {
+            LOG_FORMAT_CANDUMP => Ok(LogWriterFormat::Candump),
+            LOG_FORMAT_ASC => Ok(LogWriterFormat::Asc),
+            LOG_FORMAT_BLF => Ok(LogWriterFormat::Blf),
+            _ => Err(CANParserError::ParserError(format!(
+                "Unknown log writer format: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the name of the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogWriterFormat::Candump => LOG_FORMAT_CANDUMP,
+            LogWriterFormat::Asc => LOG_FORMAT_ASC,
+            LogWriterFormat::Blf => LOG_FORMAT_BLF,
+        }
+    }
+}
+
+/// Writes messages to a log in one of the `LogWriterFormat`s, so they can be replayed with
+/// can-utils or loaded into other tools.
+///
+/// Frames are written losslessly: their timestamps to the nanosecond, IDs, payloads, channels,
+/// directions and remote, error and CAN FD flags. ASC and BLF number their channels, so channels
+/// such as `can0` are written as their 1-based number there. ASC and BLF logs start with a header
+/// holding the time of the first message, and BLF logs are completed by `finish`, so a sink holds a
+/// single log and is used for a single parse. BLF logs time their frames from the first message, so
+/// their messages must not be older than the first one.
+pub struct LogSink<W: Write + Seek> {
+    format: LogWriterFormat,
+    writer: W,
+    /// The time the ASC header was written for, once the first message is written.
+    asc_start: Option<i64>,
+    blf: BlfState,
+}
+
+impl LogSink<BufWriter<File>> {
+    /// Creates a `LogSink` writing to the file at `output_path`, replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::IOError` if the file cannot be created.
+    pub fn create(output_path: &str, format: LogWriterFormat) -> Result<Self, CANParserError> {
+        Self::new(BufWriter::new(File::create(output_path)?), format)
+    }
+}
+
+impl<W: Write + Seek> LogSink<W> {
+    /// Creates a `LogSink` writing to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::IOError` if the header of a BLF log cannot be reserved.
+    pub fn new(mut writer: W, format: LogWriterFormat) -> Result<Self, CANParserError> {
+        if format == LogWriterFormat::Blf {
+            // Reserve the header, which is written once the log is complete
+            writer.write_all(&[0; BLF_FILE_HEADER_SIZE])?;
+        }
+        Ok(Self {
+            format,
+            writer,
+            asc_start: None,
+            blf: BlfState::default(),
+        })
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes a message.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::IOError` if the message cannot be written, or a
+    /// `CANParserError::ParserError` if a BLF message is older than the first message.
+    pub fn write(&mut self, message: &CANMessage) -> Result<(), CANParserError> {
+        match self.format {
+            LogWriterFormat::Candump => writeln!(self.writer, "{}", candump_line(message))?,
+            LogWriterFormat::Asc => {
+                let start = match self.asc_start {
+                    Some(start) => start,
+                    None => {
+                        // The header holds the start time to the millisecond
+                        let start = message.ts.div_euclid(1_000_000) * 1_000_000;
+                        write!(self.writer, "{}", asc_header(start))?;
+                        self.asc_start = Some(start);
+                        start
+                    }
+                };
+                writeln!(self.writer, "{}", asc_line(message, start))?;
+            }
+            LogWriterFormat::Blf => {
+                self.blf.add(message)?;
+                if self.blf.buffer.len() >= BLF_MAX_CONTAINER_SIZE {
+                    self.blf.flush(&mut self.writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Completes the log and flushes the writer. Nothing may be written afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError::IOError` if the log cannot be written.
+    pub fn finish(&mut self) -> Result<(), CANParserError> {
+        match self.format {
+            LogWriterFormat::Candump => {}
+            LogWriterFormat::Asc => {
+                if self.asc_start.is_none() {
+                    write!(self.writer, "{}", asc_header(0))?;
+                }
+                writeln!(self.writer, "End TriggerBlock")?;
+            }
+            LogWriterFormat::Blf => {
+                self.blf.flush(&mut self.writer)?;
+                let file_size = self.writer.stream_position()?;
+                self.writer.seek(SeekFrom::Start(0))?;
+                self.writer.write_all(&self.blf.file_header(file_size))?;
+                self.writer.seek(SeekFrom::End(0))?;
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> MessageSink for LogSink<W> {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        self.write(&message)
+    }
+
+    fn on_finish(&mut self, _filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        self.finish()
+    }
+}
+
+/// Writes messages to a log.
+///
+/// # Arguments
+///
+/// * `output_path` - The path of the log file, or `None` to return the log.
+/// * `format` - The format of the log.
+/// * `messages` - The messages to write, in order.
+///
+/// # Returns
+///
+/// The log if `output_path` is `None`, otherwise `None`.
+///
+/// # Errors
+///
+/// Returns a `CANParserError::IOError` if the log cannot be written, or a
+/// `CANParserError::ParserError` if a BLF message is older than the first message.
+pub fn write_log<M: Borrow<CANMessage>>(
+    output_path: Option<String>,
+    format: LogWriterFormat,
+    messages: impl IntoIterator<Item = M>,
+) -> Result<Option<Vec<u8>>, CANParserError> {
+    match output_path {
+        Some(output_path) => {
+            let mut sink = LogSink::create(&output_path, format)?;
+            for message in messages {
+                sink.write(message.borrow())?;
+            }
+            sink.finish()?;
+            Ok(None)
+        }
+        None => {
+            let mut sink = LogSink::new(Cursor::new(Vec::new()), format)?;
+            for message in messages {
+                sink.write(message.borrow())?;
+            }
+            sink.finish()?;
+            Ok(Some(sink.into_inner().into_inner()))
+        }
+    }
+}
+
+/// Formats a payload as uppercase hex digits, with `separator` between the bytes.
+fn hex_bytes(data: &[u8], separator: &str) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Returns the 1-based channel number of a message for the ASC and BLF formats, which number their
+/// channels. Numeric channels, such as those read from ASC logs, are kept, and interfaces such as
+/// `can0` are numbered from their 0-based index. Other channels are written as channel 1.
+fn channel_number(channel: &Option<String>) -> u16 {
+    let channel = match channel {
+        Some(channel) => channel,
+        None => return 1,
+    };
+    if let Ok(number) = channel.parse::<u16>() {
+        return number;
+    }
+    let name = channel.trim_end_matches(|c: char| c.is_ascii_digit());
+    channel[name.len()..]
+        .parse::<u16>()
+        .map(|index| index.saturating_add(1))
+        .unwrap_or(1)
+}
+
+/// Formats a CAN ID as candump does, with 8 digits for extended IDs and error frames and 3 for
+/// standard IDs. Error frames carry `CAN_ERR_FLAG`.
+pub(crate) fn candump_id(id: &CANID) -> String {
+    if id.flags.err {
+        format!("{:08X}", id.id | CAN_ERR_FLAG)
+    } else if id.flags.ext {
+        format!("{:08X}", id.id)
+    } else {
+        format!("{:03X}", id.id)
+    }
+}
+
+/// Formats a message as a `candump -L` line, `(timestamp) channel id#data`, followed by the
+/// direction when it is known.
+fn candump_line(message: &CANMessage) -> String {
+    let data = &message.data.data[..message.data.len as usize];
+    let mut line = format!(
+        "({}) {} {}#",
+        format_seconds(message.ts),
+        message
+            .channel
+            .as_deref()
+            .unwrap_or(CANDUMP_DEFAULT_CHANNEL),
+        candump_id(&message.id)
+    );
+    if message.id.flags.fd {
+        let mut fd_flags = 0;
+        if message.id.flags.brs {
+            fd_flags |= CANFD_BRS;
+        }
+        if message.id.flags.esi {
+            fd_flags |= CANFD_ESI;
+        }
+        line.push_str(&format!("#{:X}", fd_flags));
+        line.push_str(&hex_bytes(data, ""));
+    } else if message.id.flags.rtr {
+        line.push('R');
+        if message.data.len > 0 {
+            line.push_str(&format!("{:X}", message.data.len));
+        }
+    } else {
+        line.push_str(&hex_bytes(data, ""));
+    }
+    match message.direction {
+        Some(Direction::Rx) => line.push_str(" R"),
+        Some(Direction::Tx) => line.push_str(" T"),
+        None => {}
+    }
+    line
+}
+
+/// Formats the header of an ASC log starting at `start` nanoseconds since the Unix epoch.
+fn asc_header(start: i64) -> String {
+    let time = CivilTime::from_nanos(start);
+    let date = format!(
+        "{} {} {:02} {:02}:{:02}:{:02}.{:03} {} {}",
+        WEEKDAYS[time.weekday as usize],
+        MONTHS[time.month as usize - 1],
+        time.day,
+        (time.hour + 11) % 12 + 1,
+        time.minute,
+        time.second,
+        time.millisecond,
+        if time.hour < 12 { "am" } else { "pm" },
+        time.year
+    );
+    format!(
+        "date {}\nbase hex  timestamps absolute\ninternal events logged\n// version 9.0.0\nBegin Triggerblock {}\n{:>11} Start of measurement\n",
+        date,
+        date,
+        format_seconds(0)
+    )
+}
+
+/// Formats a message as an ASC line with its timestamp relative to `start`.
+fn asc_line(message: &CANMessage, start: i64) -> String {
+    let timestamp = format_seconds(message.ts - start);
+    let channel = channel_number(&message.channel);
+    if message.id.flags.err {
+        return format!("{:>11} {}  ErrorFrame", timestamp, channel);
+    }
+    let id = if message.id.flags.ext {
+        format!("{:X}x", message.id.id)
+    } else {
+        format!("{:X}", message.id.id)
+    };
+    let direction = message.direction.unwrap_or(Direction::Rx).to_string();
+    let data = &message.data.data[..message.data.len as usize];
+    if message.id.flags.fd {
+        let dlc = len_to_dlc(data.len(), true).unwrap_or_default();
+        let mut flags = ASC_FD_EDL;
+        if message.id.flags.brs {
+            flags |= ASC_FD_BRS;
+        }
+        if message.id.flags.esi {
+            flags |= ASC_FD_ESI;
+        }
+        format!(
+            "{:>11} CANFD {:>3} {:<4} {:>9} {} {} {:x} {:>2} {} {:>8} {:>4} {:>8X} {:>8} {:>8} {:>8} {:>8} {:>8}",
+            timestamp,
+            channel,
+            direction,
+            id,
+            message.id.flags.brs as u8,
+            message.id.flags.esi as u8,
+            dlc,
+            data.len(),
+            hex_bytes(data, " "),
+            0,
+            0,
+            flags,
+            0,
+            0,
+            0,
+            0,
+            0
+        )
+    } else if message.id.flags.rtr {
+        format!(
+            "{:>11} {:<2} {:<15} {:<4} r {:X}",
+            timestamp, channel, id, direction, message.data.len
+        )
+    } else {
+        format!(
+            "{:>11} {:<2} {:<15} {:<4} d {:X} {}",
+            timestamp,
+            channel,
+            id,
+            direction,
+            data.len(),
+            hex_bytes(data, " ")
+        )
+    }
+}
+
+/// The objects of a BLF log that are not written out yet, and what the file header needs.
+#[derive(Default)]
+struct BlfState {
+    /// The serialized objects of the next log container.
+    buffer: Vec<u8>,
+    /// The timestamps of the first and last message.
+    start: Option<i64>,
+    stop: i64,
+    object_count: u32,
+    /// The size of the log with its containers uncompressed.
+    uncompressed_size: u64,
+}
+
+impl BlfState {
+    /// Serializes a message as a BLF object into the buffer.
+    ///
+    /// Objects hold their time as an unsigned offset from the first message, so a message older
+    /// than the first one cannot be written.
+    fn add(&mut self, message: &CANMessage) -> Result<(), CANParserError> {
+        let start = *self.start.get_or_insert(message.ts);
+        if message.ts < start {
+            return Err(CANParserError::ParserError(format!(
+                "BLF message at {} ns precedes the first message at {} ns; sort the messages by time",
+                message.ts, start
+            )));
+        }
+        self.stop = message.ts;
+        let data = &message.data.data[..message.data.len as usize];
+        let channel = channel_number(&message.channel);
+        let mut arbitration_id = message.id.id;
+        if message.id.flags.ext {
+            arbitration_id |= CAN_EFF_FLAG;
+        }
+        let mut flags = 0;
+        if message.direction == Some(Direction::Tx) {
+            flags |= BLF_DIR_TX;
+        }
+        if message.id.flags.rtr {
+            flags |= BLF_REMOTE_FLAG;
+        }
+
+        let mut object = Vec::with_capacity(84);
+        let object_type = if message.id.flags.err {
+            object.extend_from_slice(&channel.to_le_bytes());
+            // Length, flags, ECC and position
+            object.extend_from_slice(&[0; 8]);
+            object.push(data.len().min(8) as u8);
+            object.push(0);
+            // Frame length
+            object.extend_from_slice(&[0; 4]);
+            object.extend_from_slice(&(message.id.id | CAN_ERR_FLAG).to_le_bytes());
+            // Extended flags and padding
+            object.extend_from_slice(&[0; 4]);
+            object.extend_from_slice(&padded::<8>(data));
+            BLF_CAN_ERROR_EXT
+        } else if message.id.flags.fd {
+            let mut fd_flags = BLF_FD_EDL;
+            if message.id.flags.brs {
+                fd_flags |= BLF_FD_BRS;
+            }
+            if message.id.flags.esi {
+                fd_flags |= BLF_FD_ESI;
+            }
+            object.extend_from_slice(&channel.to_le_bytes());
+            object.push(flags);
+            object.push(len_to_dlc(data.len(), true).unwrap_or_default());
+            object.extend_from_slice(&arbitration_id.to_le_bytes());
+            // Frame length and bit count
+            object.extend_from_slice(&[0; 5]);
+            object.push(fd_flags);
+            object.push(data.len() as u8);
+            object.extend_from_slice(&[0; 5]);
+            object.extend_from_slice(&padded::<64>(data));
+            BLF_CAN_FD_MESSAGE
+        } else {
+            object.extend_from_slice(&channel.to_le_bytes());
+            object.push(flags);
+            object.push(message.data.len);
+            object.extend_from_slice(&arbitration_id.to_le_bytes());
+            object.extend_from_slice(&padded::<8>(data));
+            BLF_CAN_MESSAGE
+        };
+
+        let size = BLF_OBJECT_HEADER_SIZE + object.len();
+        self.buffer.extend_from_slice(b"LOBJ");
+        self.buffer
+            .extend_from_slice(&(BLF_OBJECT_HEADER_SIZE as u16).to_le_bytes());
+        self.buffer.extend_from_slice(&1u16.to_le_bytes());
+        self.buffer.extend_from_slice(&(size as u32).to_le_bytes());
+        self.buffer.extend_from_slice(&object_type.to_le_bytes());
+        self.buffer
+            .extend_from_slice(&BLF_TIME_ONE_NANS.to_le_bytes());
+        // Client index and object version
+        self.buffer.extend_from_slice(&[0; 4]);
+        self.buffer
+            .extend_from_slice(&((message.ts - start) as u64).to_le_bytes());
+        self.buffer.extend_from_slice(&object);
+        self.buffer.resize(self.buffer.len() + object.len() % 4, 0);
+        self.object_count += 1;
+        Ok(())
+    }
+
+    /// Writes the buffered objects as an uncompressed log container.
+    fn flush(&mut self, writer: &mut impl Write) -> Result<(), CANParserError> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let size = BLF_CONTAINER_HEADER_SIZE + self.buffer.len();
+        writer.write_all(b"LOBJ")?;
+        writer.write_all(&16u16.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&(size as u32).to_le_bytes())?;
+        writer.write_all(&BLF_LOG_CONTAINER.to_le_bytes())?;
+        // No compression, then the uncompressed size
+        writer.write_all(&[0; 8])?;
+        writer.write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        writer.write_all(&[0; 4])?;
+        writer.write_all(&self.buffer)?;
+        writer.write_all(&vec![0; size % 4])?;
+        self.uncompressed_size += size as u64;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Serializes the file header of a log of `file_size` bytes.
+    fn file_header(&self, file_size: u64) -> Vec<u8> {
+        let mut header = Vec::with_capacity(BLF_FILE_HEADER_SIZE);
+        header.extend_from_slice(b"LOGG");
+        header.extend_from_slice(&(BLF_FILE_HEADER_SIZE as u32).to_le_bytes());
+        // Application ID and version, then the version of the BLF format
+        header.extend_from_slice(&[0, 0, 0, 0, 2, 6, 8, 1]);
+        header.extend_from_slice(&file_size.to_le_bytes());
+        header.extend_from_slice(
+            &(BLF_FILE_HEADER_SIZE as u64 + self.uncompressed_size).to_le_bytes(),
+        );
+        header.extend_from_slice(&self.object_count.to_le_bytes());
+        // Objects read
+        header.extend_from_slice(&[0; 4]);
+        for ts in [self.start.unwrap_or_default(), self.stop] {
+            let time = CivilTime::from_nanos(ts);
+            for field in [
+                time.year,
+                time.month,
+                time.weekday,
+                time.day,
+                time.hour,
+                time.minute,
+                time.second,
+                time.millisecond,
+            ] {
+                header.extend_from_slice(&(field as u16).to_le_bytes());
+            }
+        }
+        header.resize(BLF_FILE_HEADER_SIZE, 0);
+        header
+    }
+}
+
+/// Copies `data` into an array of `N` bytes padded with zeros.
+fn padded<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut out = [0; N];
+    let length = data.len().min(N);
+    out[..length].copy_from_slice(&data[..length]);
+    out
+}
@@ -0,0 +1,280 @@
+use crate::can_message::{CANData, CANMessage, Direction, CANID};
+use crate::error::CANParserError;
+use crate::error_frame::CANErrorFrame;
+use crate::sink::MessageSink;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// The frames logged with a single CAN ID, stored column by column.
+///
+/// Row `i` of every column belongs to the `i`th frame of the table. Signal columns hold `NaN`
+/// for the frames that did not carry the signal.
+#[derive(Clone, Debug, Serialize)]
+pub struct FrameTable {
+    /// The ID shared by the frames, with its J1939 fields and flags.
+    pub id: CANID,
+    /// The timestamps of the frames in nanoseconds.
+    pub timestamps: Vec<i64>,
+    /// The payloads of the frames back to back, each sized to its data length.
+    pub payloads: Vec<u8>,
+    /// The offset of each payload in `payloads`, followed by the total length.
+    pub offsets: Vec<u32>,
+    /// The index of the channel of each frame in `MessageStore::channels()`.
+    pub channels: Vec<Option<u32>>,
+    /// The direction of each frame.
+    pub directions: Vec<Option<Direction>>,
+    /// The index of the source file of each frame in `MessageStore::sources()`.
+    pub sources: Vec<Option<u32>>,
+    /// The decoded error classes of the frames that are error frames, by row.
+    pub errors: BTreeMap<u32, CANErrorFrame>,
+    /// The decoded value of each signal, by SPN.
+    pub signals: BTreeMap<u16, Vec<f32>>,
+}
+
+impl FrameTable {
+    /// Creates an empty table for frames with the given ID.
+    fn new(id: CANID) -> Self {
+        Self {
+            id,
+            timestamps: Vec::new(),
+            payloads: Vec::new(),
+            offsets: vec![0],
+            channels: Vec::new(),
+            directions: Vec::new(),
+            sources: Vec::new(),
+            errors: BTreeMap::new(),
+            signals: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of frames in the table.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Returns `true` if the table holds no frames.
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Returns the payload of the frame at `row`.
+    pub fn payload(&self, row: usize) -> &[u8] {
+        &self.payloads[self.offsets[row] as usize..self.offsets[row + 1] as usize]
+    }
+
+    /// Returns the values of a signal, one per frame, or `None` if no frame carried it.
+    pub fn signal(&self, spn: u16) -> Option<&[f32]> {
+        self.signals.get(&spn).map(Vec::as_slice)
+    }
+}
+
+/// A columnar store of CAN messages, an alternative to `Vec<CANMessage>` that takes several
+/// times less memory for large logs.
+///
+/// Messages are grouped into a `FrameTable` per CAN ID, so the ID is stored once per table,
+/// payloads take only their data length and signal values are stored as plain columns instead
+/// of a map per message. The order in which the messages were added is kept, and `iter`
+/// rebuilds the `CANMessage`s in that order.
+#[derive(Clone, Debug, Default)]
+pub struct MessageStore {
+    /// The tables of frames, one per CAN ID, in the order the IDs were first seen.
+    tables: Vec<FrameTable>,
+    /// The table and row of each message, in the order the messages were added.
+    order: Vec<(u32, u32)>,
+    /// The channels the messages were logged on.
+    channels: Vec<String>,
+    /// The log files the messages were read from.
+    sources: Vec<Arc<str>>,
+    /// The position of the table of each CAN ID in `tables`.
+    table_index: HashMap<CANID, u32>,
+    /// The position of each channel in `channels`.
+    channel_index: HashMap<String, u32>,
+}
+
+impl MessageStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of messages in the store.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Returns `true` if the store holds no messages.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Removes every message.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the tables of frames, one per CAN ID, in the order the IDs were first seen.
+    pub fn tables(&self) -> &[FrameTable] {
+        &self.tables
+    }
+
+    /// Returns the channels the messages were logged on, indexed by `FrameTable::channels`.
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// Returns the log files the messages were read from, indexed by `FrameTable::sources`.
+    pub fn sources(&self) -> &[Arc<str>] {
+        &self.sources
+    }
+
+    /// Returns the table of the frames with the given ID.
+    pub fn table(&self, id: &CANID) -> Option<&FrameTable> {
+        self.table_index
+            .get(id)
+            .map(|&index| &self.tables[index as usize])
+    }
+
+    /// Adds a message to the table of its ID.
+    pub fn push(&mut self, message: CANMessage) {
+        let table_number = match self.table_index.get(&message.id) {
+            Some(&index) => index,
+            None => {
+                let index = self.tables.len() as u32;
+                self.table_index.insert(message.id.clone(), index);
+                self.tables.push(FrameTable::new(message.id.clone()));
+                index
+            }
+        };
+        let channel = message.channel.map(|channel| self.channel_number(channel));
+        let source = message.source.map(|source| self.source_number(source));
+        let table = &mut self.tables[table_number as usize];
+        let row = table.len();
+
+        table.timestamps.push(message.ts);
+        table
+            .payloads
+            .extend_from_slice(&message.data.data[..message.data.len as usize]);
+        table.offsets.push(table.payloads.len() as u32);
+        table.channels.push(channel);
+        table.directions.push(message.direction);
+        table.sources.push(source);
+        if let Some(error) = message.error {
+            table.errors.insert(row as u32, error);
+        }
+        for (spn, value) in message.data.spns {
+            let column = table.signals.entry(spn).or_default();
+            column.resize(row, f32::NAN);
+            column.push(value);
+        }
+        // Keep every signal column as long as the table
+        for column in table.signals.values_mut() {
+            column.resize(row + 1, f32::NAN);
+        }
+        self.order.push((table_number, row as u32));
+    }
+
+    /// Rebuilds the message at position `index` in the order the messages were added.
+    pub fn get(&self, index: usize) -> Option<CANMessage> {
+        let &(table, row) = self.order.get(index)?;
+        Some(self.message(&self.tables[table as usize], row as usize))
+    }
+
+    /// Returns an iterator rebuilding the messages in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = CANMessage> + '_ {
+        self.order
+            .iter()
+            .map(|&(table, row)| self.message(&self.tables[table as usize], row as usize))
+    }
+
+    /// Returns the timestamps and values of a signal across every table carrying it, ordered by
+    /// timestamp. Frames that did not carry the signal are left out.
+    pub fn signal(&self, spn: u16) -> (Vec<i64>, Vec<f32>) {
+        let mut samples: Vec<(i64, f32)> = self
+            .tables
+            .iter()
+            .filter_map(|table| Some((table, table.signal(spn)?)))
+            .flat_map(|(table, values)| {
+                table.timestamps.iter().copied().zip(values.iter().copied())
+            })
+            .filter(|(_, value)| !value.is_nan())
+            .collect();
+        samples.sort_by_key(|(ts, _)| *ts);
+        samples.into_iter().unzip()
+    }
+
+    /// Rebuilds the message at `row` of `table`.
+    fn message(&self, table: &FrameTable, row: usize) -> CANMessage {
+        let payload = table.payload(row);
+        let mut data = [0u8; 64];
+        data[..payload.len()].copy_from_slice(payload);
+        let spns = table
+            .signals
+            .iter()
+            .filter(|(_, values)| !values[row].is_nan())
+            .map(|(spn, values)| (*spn, values[row]))
+            .collect();
+        CANMessage {
+            ts: table.timestamps[row],
+            channel: table.channels[row].map(|channel| self.channels[channel as usize].clone()),
+            direction: table.directions[row],
+            source: table.sources[row].map(|source| self.sources[source as usize].clone()),
+            id: table.id.clone(),
+            data: CANData {
+                len: payload.len() as u8,
+                data,
+                spns,
+            },
+            error: table.errors.get(&(row as u32)).cloned(),
+        }
+    }
+
+    /// Returns the number of a channel, adding it if it is new.
+    fn channel_number(&mut self, channel: String) -> u32 {
+        if let Some(&number) = self.channel_index.get(&channel) {
+            return number;
+        }
+        let number = self.channels.len() as u32;
+        self.channel_index.insert(channel.clone(), number);
+        self.channels.push(channel);
+        number
+    }
+
+    /// Returns the number of a source file, adding it if it is new. Sources are few, so they are
+    /// looked up by pointer first and by name otherwise.
+    fn source_number(&mut self, source: Arc<str>) -> u32 {
+        if let Some(number) = self
+            .sources
+            .iter()
+            .rposition(|known| Arc::ptr_eq(known, &source) || *known == source)
+        {
+            return number as u32;
+        }
+        self.sources.push(source);
+        (self.sources.len() - 1) as u32
+    }
+}
+
+impl Extend<CANMessage> for MessageStore {
+    fn extend<T: IntoIterator<Item = CANMessage>>(&mut self, messages: T) {
+        for message in messages {
+            self.push(message);
+        }
+    }
+}
+
+impl FromIterator<CANMessage> for MessageStore {
+    fn from_iter<T: IntoIterator<Item = CANMessage>>(messages: T) -> Self {
+        let mut store = Self::new();
+        store.extend(messages);
+        store
+    }
+}
+
+/// Stores the messages, as `CANParser::parse_file` does in columnar mode.
+impl MessageSink for MessageStore {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        self.push(message);
+        Ok(())
+    }
+}
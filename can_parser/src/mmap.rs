@@ -0,0 +1,240 @@
+use crate::can_message::CANMessage;
+use crate::compression::Compression;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, LineError};
+use crate::error::CANParserError;
+use crate::error_policy::ErrorPolicy;
+use crate::sink::MessageSink;
+use crate::{CANParser, ParseState};
+use memmap2::Mmap;
+use std::fs::File;
+use std::sync::Arc;
+
+/// The approximate size of the newline aligned chunks a mapped file is split into.
+const MMAP_CHUNK_BYTES: usize = 1 << 22;
+
+/// The number of chunks parsed per worker thread before their messages are handed to the sink.
+#[cfg(feature = "parallel")]
+const MMAP_CHUNKS_PER_THREAD: usize = 4;
+
+/// The result of parsing a chunk of a mapped file.
+struct ChunkResult {
+    /// The messages decoded from the chunk, in file order.
+    messages: Vec<CANMessage>,
+    /// The number of lines in the chunk.
+    lines: u64,
+    /// The lines that could not be parsed, with their line number relative to the chunk and
+    /// their byte offset in the file.
+    errors: Vec<(u64, u64, LineError, String)>,
+}
+
+/// Splits `bytes` into chunks of about `MMAP_CHUNK_BYTES` that each end just after a newline, or
+/// at the end of the file.
+///
+/// # Returns
+///
+/// The byte offset of the start of each chunk, followed by the length of the file.
+fn chunk_bounds(bytes: &[u8]) -> Vec<usize> {
+    let mut bounds = vec![0];
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = (start + MMAP_CHUNK_BYTES).min(bytes.len());
+        start = match bytes[end..].iter().position(|&b| b == b'\n') {
+            Some(newline) => end + newline + 1,
+            None => bytes.len(),
+        };
+        bounds.push(start);
+    }
+    bounds
+}
+
+impl CANParser {
+    /// Parses a file containing CAN messages by memory mapping it, storing the messages as
+    /// `parse_file` does.
+    ///
+    /// The file is split into newline aligned chunks that are parsed in place, as `&str` slices of
+    /// the mapping, without reading the lines into owned strings. With the `parallel` feature the
+    /// chunks are spread across the rayon worker threads. This is considerably faster than
+    /// `parse_file` for large uncompressed logs. Compressed files cannot be mapped and are parsed
+    /// as by `parse_file`.
+    ///
+    /// The file must not be modified while it is parsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the CANParser instance.
+    /// * `file_path` - A string slice that holds the path to the file to be parsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse_file`, or a `CANParserError::IOError` if the file cannot
+    /// be mapped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use can_parser::{CANParser, ErrorPolicy, LOG_FORMAT_CANDUMP};
+    ///
+    /// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    /// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    /// parser.parse_file_mmap("path/to/file.log").unwrap();
+    /// ```
+    pub fn parse_file_mmap(&mut self, file_path: &str) -> Result<(), CANParserError> {
+        #[cfg(feature = "debug")]
+        let start_time = Self::current_time();
+
+        let result = self.parse_stored(|parser, sink| parser.parse_file_mmap_into(file_path, sink));
+
+        // Debugging Logic
+        #[cfg(feature = "debug")]
+        Self::debug_log(&self.messages, Self::current_time() - start_time);
+
+        result
+    }
+
+    /// Parses a file containing CAN messages by memory mapping it, handing each message to `sink`
+    /// as by `parse_file_into`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the CANParser instance.
+    /// * `file_path` - A string slice that holds the path to the file to be parsed.
+    /// * `sink` - The `MessageSink` receiving the results.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse_file_into`, or a `CANParserError::IOError` if the file
+    /// cannot be mapped.
+    pub fn parse_file_mmap_into(
+        &mut self,
+        file_path: &str,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let file = File::open(file_path)?;
+        let length = file.metadata()?.len();
+        let state = self.parse_state(Some(length), None);
+        let parsed = if length > 0 {
+            // SAFETY: the mapping is only read, and the caller is told not to modify the file
+            // while it is parsed.
+            let mmap = unsafe { Mmap::map(&file)? };
+            let header = &mmap[..mmap.len().min(6)];
+            if Compression::from_magic(header) == Compression::None {
+                self.read_mmap(&mmap, file_path, &state, sink)
+            } else {
+                drop(mmap);
+                self.read_file(file_path, self.filter.as_ref(), &state, sink)
+            }
+        } else {
+            Ok(())
+        };
+        self.finish_sink(parsed, state, sink)
+    }
+
+    /// Parses the chunks of a mapped file into `sink`, collecting any parsing errors and the
+    /// progress into `state`.
+    ///
+    /// Chunks are parsed in batches, in parallel if the `parallel` feature is enabled. Once a
+    /// batch is parsed its messages are handed to the sink and its errors are recorded in file
+    /// order, which is also when the line numbers of the chunks become known.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error returned by the sink.
+    fn read_mmap(
+        &self,
+        bytes: &[u8],
+        file_path: &str,
+        state: &Arc<ParseState>,
+        sink: &mut dyn MessageSink,
+    ) -> Result<(), CANParserError> {
+        let bounds = chunk_bounds(bytes);
+        let chunks: Vec<(usize, &[u8])> = bounds
+            .windows(2)
+            .map(|bound| (bound[0], &bytes[bound[0]..bound[1]]))
+            .collect();
+
+        // Function to parse a chunk
+        let parse_chunk =
+            |&(offset, chunk): &(usize, &[u8])| self.parse_chunk(offset, chunk, state);
+
+        // Core Logic
+        #[cfg(feature = "parallel")]
+        let batch_size = rayon::current_num_threads() * MMAP_CHUNKS_PER_THREAD;
+        #[cfg(not(feature = "parallel"))]
+        let batch_size = 1;
+        let mut line_number = 0;
+        for batch in chunks.chunks(batch_size) {
+            if state.is_stopped() {
+                break;
+            }
+            let results: Vec<ChunkResult> = if cfg!(feature = "parallel") {
+                #[cfg(feature = "parallel")]
+                {
+                    use rayon::prelude::*;
+                    batch.par_iter().map(parse_chunk).collect()
+                }
+                #[cfg(not(feature = "parallel"))]
+                {
+                    panic!("Parallel feature not enabled");
+                }
+            } else {
+                batch.iter().map(parse_chunk).collect()
+            };
+            for result in results {
+                for (number, offset, error, line) in result.errors {
+                    let number = line_number + number;
+                    Self::handle_parsing_error(&self.error_handling, state, || {
+                        Diagnostic::new(error, line, number, Some(offset), Some(file_path))
+                    });
+                }
+                line_number += result.lines;
+                for message in result.messages {
+                    sink.on_message(message)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the lines of a chunk of a mapped file starting at byte `offset`.
+    ///
+    /// Lines are parsed as slices of the chunk. Only the lines that could not be parsed are
+    /// copied, to build their diagnostics.
+    fn parse_chunk(&self, offset: usize, chunk: &[u8], state: &ParseState) -> ChunkResult {
+        let mut result = ChunkResult {
+            messages: Vec::new(),
+            lines: 0,
+            errors: Vec::new(),
+        };
+        let keep_errors = self.error_handling != ErrorPolicy::Ignore;
+        let body = chunk.strip_suffix(b"\n").unwrap_or(chunk);
+        let mut line_offset = offset as u64;
+        for bytes in body.split(|&b| b == b'\n') {
+            if state.is_stopped() {
+                break;
+            }
+            result.lines += 1;
+            let number = result.lines;
+            let start = line_offset;
+            line_offset += bytes.len() as u64 + 1;
+            let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+            let error = match std::str::from_utf8(bytes) {
+                Ok(line) => match self.parse_line_inner(line, self.filter.as_ref()) {
+                    Ok(Some(message)) => {
+                        state.progress.message_decoded();
+                        result.messages.push(message);
+                        continue;
+                    }
+                    Ok(None) => continue,
+                    Err(e) => e,
+                },
+                Err(e) => LineError::new(DiagnosticKind::Io, e.to_string()),
+            };
+            if keep_errors {
+                let line = String::from_utf8_lossy(bytes).into_owned();
+                result.errors.push((number, start, error, line));
+            }
+        }
+        state.progress.lines_read(result.lines, chunk.len() as u64);
+        result
+    }
+}
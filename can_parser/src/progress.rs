@@ -0,0 +1,157 @@
+use crate::CANParser;
+use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The number of lines between two progress reports.
+const PROGRESS_INTERVAL_LINES: u64 = 1 << 14;
+
+/// A callback receiving the progress of a parse. Returning `ControlFlow::Break` cancels the parse.
+///
+/// When parsing in parallel the callback may be called from the worker threads.
+pub type ProgressCallback = Arc<dyn Fn(&Progress) -> ControlFlow<()> + Send + Sync>;
+
+/// A snapshot of the progress of a parse.
+#[cfg_attr(feature = "python", pyo3::prelude::pyclass(get_all))]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// The number of bytes read so far, if the input is a file.
+    pub bytes_read: u64,
+    /// The size of the input in bytes, if it is known. Compressed files have no known size.
+    pub total_bytes: Option<u64>,
+    /// The number of lines read so far.
+    pub lines_read: u64,
+    /// The number of lines of the input, if it is known.
+    pub total_lines: Option<u64>,
+    /// The number of messages decoded so far.
+    pub messages: u64,
+    /// The time spent parsing so far, in seconds.
+    pub elapsed: f64,
+    /// The fraction of the input processed so far, between 0 and 1, if the size of the input is known.
+    pub fraction: Option<f64>,
+    /// The estimated time left, in seconds, if the size of the input is known.
+    pub eta: Option<f64>,
+}
+
+/// A token used to cancel a parse, e.g. from another thread.
+///
+/// Once cancelled a token stays cancelled, so a new token has to be given to the parser before it
+/// can parse again.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the parses using this token. They stop as soon as possible and return a
+    /// `CANParserError::ParseCancelled`.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counts the progress of a single parse, reports it to the progress callback and tracks whether
+/// the parse was cancelled. Shared between the threads of a parse.
+pub(crate) struct ProgressTracker {
+    callback: Option<ProgressCallback>,
+    token: CancellationToken,
+    /// Set when the callback cancels this parse, without cancelling the parser's token.
+    stopped: AtomicBool,
+    start_time: f64,
+    total_bytes: Option<u64>,
+    total_lines: Option<u64>,
+    bytes_read: AtomicU64,
+    lines_read: AtomicU64,
+    messages: AtomicU64,
+}
+
+impl ProgressTracker {
+    /// Creates a tracker for a parse of an input of the given size.
+    pub(crate) fn new(
+        callback: Option<ProgressCallback>,
+        token: CancellationToken,
+        total_bytes: Option<u64>,
+        total_lines: Option<u64>,
+    ) -> Self {
+        Self {
+            callback,
+            token,
+            stopped: AtomicBool::new(false),
+            start_time: CANParser::current_time(),
+            total_bytes,
+            total_lines,
+            bytes_read: AtomicU64::new(0),
+            lines_read: AtomicU64::new(0),
+            messages: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `true` if the parse was cancelled by the token or the callback.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed) || self.token.is_cancelled()
+    }
+
+    /// Counts a line of `bytes` bytes, including its newline, and reports the progress every
+    /// `PROGRESS_INTERVAL_LINES` lines.
+    pub(crate) fn line_read(&self, bytes: u64) {
+        self.lines_read(1, bytes);
+    }
+
+    /// Counts `lines` lines of `bytes` bytes in total, read at once, and reports the progress if
+    /// they cross a multiple of `PROGRESS_INTERVAL_LINES` lines.
+    pub(crate) fn lines_read(&self, lines: u64, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        let before = self.lines_read.fetch_add(lines, Ordering::Relaxed);
+        if (before + lines) / PROGRESS_INTERVAL_LINES > before / PROGRESS_INTERVAL_LINES {
+            self.report();
+        }
+    }
+
+    /// Counts a decoded message.
+    pub(crate) fn message_decoded(&self) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reports the progress to the callback, if there is one.
+    pub(crate) fn report(&self) {
+        if let Some(callback) = &self.callback {
+            if callback(&self.snapshot()).is_break() {
+                self.stopped.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns the current progress.
+    fn snapshot(&self) -> Progress {
+        let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+        let lines_read = self.lines_read.load(Ordering::Relaxed);
+        let elapsed = (CANParser::current_time() - self.start_time) / 1000.0;
+        let fraction = match (self.total_bytes, self.total_lines) {
+            (Some(total), _) if total > 0 => Some(bytes_read as f64 / total as f64),
+            (_, Some(total)) if total > 0 => Some(lines_read as f64 / total as f64),
+            _ => None,
+        }
+        .map(|fraction| fraction.min(1.0));
+        Progress {
+            bytes_read,
+            total_bytes: self.total_bytes,
+            lines_read,
+            total_lines: self.total_lines,
+            messages: self.messages.load(Ordering::Relaxed),
+            elapsed,
+            fraction,
+            eta: fraction
+                .filter(|fraction| *fraction > 0.0)
+                .map(|fraction| elapsed * (1.0 - fraction) / fraction),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use crate::can_message::{read_bits_lsb, CANMessage};
+use crate::error::CANParserError;
+use crate::log_writer::candump_id;
+use crate::specification::{fixed_str, FilteredSpec, SpecSPN};
+use crate::timestamp::format_seconds;
+use csv::Writer;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+
+pub const RESAMPLING_FFILL: &str = "ffill";
+pub const RESAMPLING_MEAN: &str = "mean";
+
+/// The columns of a long CSV, one row per decoded signal value.
+pub const LONG_CSV_COLUMNS: [&str; 9] = [
+    "timestamp",
+    "id",
+    "sa",
+    "pgn",
+    "spn",
+    "label",
+    "raw",
+    "value",
+    "units",
+];
+
+/// The most rows a wide CSV may have, so that a small interval or logs far apart in time fail
+/// instead of writing billions of rows.
+pub const MAX_WIDE_CSV_ROWS: i64 = 10_000_000;
+
+/// How the signals of a wide CSV are resampled onto its time base.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resampling {
+    /// The last value of each interval, carried forward through the intervals without a value.
+    ForwardFill,
+    /// The mean of the values of each interval, left empty for intervals without a value.
+    Mean,
+}
+
+impl Resampling {
+    /// Creates a `Resampling` from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of "ffill" or "mean".
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the name is unknown.
+    pub fn from_name(name: &str) -> Result<Self, CANParserError> {
+        match name.to_ascii_lowercase().as_str() {
+            RESAMPLING_FFILL => Ok(Resampling::ForwardFill),
+            RESAMPLING_MEAN => Ok(Resampling::Mean),
+            _ => Err(CANParserError::ParserError(format!(
+                "Unknown resampling: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the name of the resampling.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resampling::ForwardFill => RESAMPLING_FFILL,
+            Resampling::Mean => RESAMPLING_MEAN,
+        }
+    }
+}
+
+/// A column of a wide CSV: an SPN as sent by one source address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct SignalKey {
+    spn: u16,
+    sa: u8,
+}
+
+/// Converts the decoded signals of the messages to a wide CSV, with a row per interval and a
+/// column per signal.
+///
+/// Rows start at the first timestamp rounded down to a multiple of `interval`, and each holds the
+/// values of the signals in `[timestamp, timestamp + interval)` resampled by `resampling`. Columns
+/// are named `label [units]` after the specification and ordered by SPN. Labels shared by several
+/// SPNs are followed by the SPN, and SPNs sent by several source addresses get a column per source
+/// address, followed by the address.
+///
+/// # Arguments
+///
+/// * `output_path` - An optional path to save the CSV file to.
+/// * `filtered_spec` - The specification of the messages, naming the columns.
+/// * `messages` - The messages, owned or borrowed, in any order.
+/// * `interval` - The interval between rows in nanoseconds.
+/// * `resampling` - How the values of each interval are resampled.
+///
+/// # Returns
+///
+/// * `Ok(None)` - If the `output_path` is provided and the CSV file is successfully saved.
+/// * `Ok(Some(csv_string))` - If the `output_path` is not provided.
+/// * `Err(CANParserError)` - If the interval is not positive, the CSV would have more than
+///   `MAX_WIDE_CSV_ROWS` rows or it cannot be written.
+pub fn to_wide_csv<M: Borrow<CANMessage>>(
+    output_path: Option<String>,
+    filtered_spec: &FilteredSpec,
+    messages: impl IntoIterator<Item = M>,
+    interval: i64,
+    resampling: Resampling,
+) -> Result<Option<String>, CANParserError> {
+    if interval <= 0 {
+        return Err(CANParserError::ParserError(
+            "The interval of a wide CSV must be positive".to_string(),
+        ));
+    }
+
+    // The PGN of each signal names its column
+    let mut pgns: BTreeMap<SignalKey, u16> = BTreeMap::new();
+    let mut samples: Vec<(i64, SignalKey, f32)> = Vec::new();
+    for message in messages {
+        let message = message.borrow();
+        for (&spn, &value) in &message.data.spns {
+            let key = SignalKey {
+                spn,
+                sa: message.id.sa,
+            };
+            pgns.entry(key).or_insert(message.id.pgn);
+            samples.push((message.ts, key, value));
+        }
+    }
+    samples.sort_by_key(|&(ts, _, _)| ts);
+    let columns: Vec<SignalKey> = pgns.keys().copied().collect();
+    let header = column_names(&columns, &pgns, filtered_spec);
+
+    let csv = write_wide_csv(&header, &columns, &samples, interval, resampling)?;
+    match output_path {
+        Some(output_path) => {
+            std::fs::write(output_path, csv)?;
+            Ok(None)
+        }
+        None => String::from_utf8(csv).map(Some).map_err(|e| {
+            CANParserError::ParserError(format!("Failed to convert to string: {}", e))
+        }),
+    }
+}
+
+/// Writes the header and rows of a wide CSV from samples sorted by timestamp.
+fn write_wide_csv(
+    header: &[String],
+    columns: &[SignalKey],
+    samples: &[(i64, SignalKey, f32)],
+    interval: i64,
+    resampling: Resampling,
+) -> Result<Vec<u8>, CANParserError> {
+    let mut wtr = Writer::from_writer(vec![]);
+    wtr.write_record(std::iter::once("timestamp").chain(header.iter().map(String::as_str)))?;
+    let index: HashMap<SignalKey, usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, key)| (*key, i))
+        .collect();
+
+    if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+        let mut last_values: Vec<Option<f32>> = vec![None; columns.len()];
+        let mut sums: Vec<(f64, u32)> = vec![(0.0, 0); columns.len()];
+        let mut samples = samples.iter().peekable();
+        let mut start = first.0.div_euclid(interval) * interval;
+        let rows = (last.0.saturating_sub(start) / interval).saturating_add(1);
+        if rows > MAX_WIDE_CSV_ROWS {
+            return Err(CANParserError::ParserError(format!(
+                "A wide CSV with an interval of {} ns from {} to {} would have {} rows, more than \
+                 the {} allowed. Use a larger interval or a shorter time range",
+                interval,
+                format_seconds(first.0),
+                format_seconds(last.0),
+                rows,
+                MAX_WIDE_CSV_ROWS
+            )));
+        }
+        while start <= last.0 {
+            let end = start + interval;
+            sums.fill((0.0, 0));
+            while let Some(&(_, key, value)) = samples.next_if(|sample| sample.0 < end) {
+                let column = index[&key];
+                last_values[column] = Some(value);
+                sums[column].0 += value as f64;
+                sums[column].1 += 1;
+            }
+            let values = (0..columns.len()).map(|column| match resampling {
+                Resampling::ForwardFill => last_values[column]
+                    .map(|value| value.to_string())
+                    .unwrap_or_default(),
+                Resampling::Mean => match sums[column] {
+                    (_, 0) => String::new(),
+                    (sum, count) => (sum / count as f64).to_string(),
+                },
+            });
+            wtr.write_record(std::iter::once(format_seconds(start)).chain(values))?;
+            start = end;
+        }
+    }
+    Ok(wtr.into_inner()?)
+}
+
+/// Names the columns of a wide CSV `label [units]`, qualifying the labels shared by several SPNs
+/// with the SPN and the SPNs sent by several source addresses with the address.
+fn column_names(
+    columns: &[SignalKey],
+    pgns: &BTreeMap<SignalKey, u16>,
+    filtered_spec: &FilteredSpec,
+) -> Vec<String> {
+    let spec = filtered_spec.j1939.read().unwrap();
+    let specs: Vec<Option<&SpecSPN>> = columns
+        .iter()
+        .map(|key| spec.get(&pgns[key]).and_then(|pgn| pgn.spns.get(&key.spn)))
+        .collect();
+    let labels: Vec<String> = columns
+        .iter()
+        .zip(&specs)
+        .map(|(key, spn)| match spn.map(|spn| fixed_str(&spn.label)) {
+            Some(label) if !label.is_empty() => label,
+            _ => format!("SPN {}", key.spn),
+        })
+        .collect();
+
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let mut name = labels[i].clone();
+            let shared_label = columns
+                .iter()
+                .zip(&labels)
+                .any(|(other, label)| other.spn != key.spn && *label == labels[i]);
+            if shared_label {
+                name.push_str(&format!(" (SPN {})", key.spn));
+            }
+            if columns
+                .iter()
+                .any(|other| other.spn == key.spn && other.sa != key.sa)
+            {
+                name.push_str(&format!(" (SA {})", key.sa));
+            }
+            let units = specs[i]
+                .map(|spn| fixed_str(&spn.units))
+                .unwrap_or_default();
+            if !units.is_empty() {
+                name.push_str(&format!(" [{}]", units));
+            }
+            name
+        })
+        .collect()
+}
+
+/// Converts the decoded signals of the messages to a long CSV, with a row per signal value.
+///
+/// The columns are always `LONG_CSV_COLUMNS`, even without messages. Rows follow the messages,
+/// and the SPNs of a message are ordered by number. IDs are hexadecimal as in candump logs, and
+/// `raw` is the value of the bits of the SPN in the payload, before its resolution and offset were
+/// applied. The label, raw value and units are left empty for SPNs missing from the specification.
+///
+/// # Arguments
+///
+/// * `output_path` - An optional path to save the CSV file to.
+/// * `filtered_spec` - The specification of the messages, labelling the signals.
+/// * `messages` - The messages, owned or borrowed.
+///
+/// # Returns
+///
+/// * `Ok(None)` - If the `output_path` is provided and the CSV file is successfully saved.
+/// * `Ok(Some(csv_string))` - If the `output_path` is not provided.
+/// * `Err(CANParserError)` - If the CSV cannot be written.
+pub fn to_long_csv<M: Borrow<CANMessage>>(
+    output_path: Option<String>,
+    filtered_spec: &FilteredSpec,
+    messages: impl IntoIterator<Item = M>,
+) -> Result<Option<String>, CANParserError> {
+    let spec = filtered_spec.j1939.read().unwrap();
+    let mut wtr = Writer::from_writer(vec![]);
+    wtr.write_record(LONG_CSV_COLUMNS)?;
+    for message in messages {
+        let message = message.borrow();
+        let mut spns: Vec<(&u16, &f32)> = message.data.spns.iter().collect();
+        spns.sort_by_key(|&(spn, _)| *spn);
+        for (&spn, &value) in spns {
+            let spec_spn = spec.get(&message.id.pgn).and_then(|pgn| pgn.spns.get(&spn));
+            wtr.write_record([
+                format_seconds(message.ts),
+                candump_id(&message.id),
+                message.id.sa.to_string(),
+                message.id.pgn.to_string(),
+                spn.to_string(),
+                spec_spn
+                    .map(|spec_spn| fixed_str(&spec_spn.label))
+                    .unwrap_or_default(),
+                spec_spn
+                    .and_then(|spec_spn| raw_value(spec_spn, message))
+                    .unwrap_or_default(),
+                value.to_string(),
+                spec_spn
+                    .map(|spec_spn| fixed_str(&spec_spn.units))
+                    .unwrap_or_default(),
+            ])?;
+        }
+    }
+    let csv = wtr.into_inner()?;
+
+    match output_path {
+        Some(output_path) => {
+            std::fs::write(output_path, csv)?;
+            Ok(None)
+        }
+        None => String::from_utf8(csv).map(Some).map_err(|e| {
+            CANParserError::ParserError(format!("Failed to convert to string: {}", e))
+        }),
+    }
+}
+
+/// Returns the raw bits of an SPN in the payload of a message, or `None` if the SPN has no bits or
+/// does not fit in the payload.
+fn raw_value(spec: &SpecSPN, message: &CANMessage) -> Option<String> {
+    if spec.length == 0 {
+        return None;
+    }
+    let payload = message.data.data.get(..message.data.len as usize)?;
+    read_bits_lsb(payload, spec.start_bit as usize, spec.length as usize).map(|raw| raw.to_string())
+}
@@ -0,0 +1,111 @@
+use crate::specification::{SpecPGN, SpecSPN};
+
+/// Selects the signals that are decoded, by SPN number or label.
+///
+/// Each pattern is matched against the SPN number and against its label, case-insensitively and
+/// ignoring spaces, as labels are stored without them. Labels too long for the specification are
+/// stored without their vowels, so patterns also match those labels once their vowels are dropped
+/// too, as long as a letter or digit remains.
+/// `*` matches any run of characters and `?` any single character, so `190`, `engine speed`,
+/// `wheel*speed` and `59?` are all patterns.
+///
+/// # Example
+///
+/// ```
+/// use can_parser::{CANParser, ErrorPolicy, SignalSelection};
+///
+/// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+/// parser.set_signal_selection(Some(SignalSelection::new(vec![
+///     "190".to_string(),
+///     "*brake switch*".to_string(),
+/// ])));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SignalSelection {
+    /// The patterns, normalized as the labels they are matched against, and without their vowels
+    /// if they have any and something other than wildcards remains.
+    patterns: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl SignalSelection {
+    /// Creates a `SignalSelection` selecting the signals matching any of `patterns`.
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .map(|pattern| {
+                    let pattern = normalize(pattern.as_bytes());
+                    let abbreviated: Vec<u8> =
+                        pattern.iter().copied().filter(|b| !is_vowel(*b)).collect();
+                    let abbreviated = (abbreviated.len() < pattern.len()
+                        && abbreviated.iter().any(|b| !matches!(b, b'*' | b'?')))
+                    .then_some(abbreviated);
+                    (pattern, abbreviated)
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns `true` if the SPN is selected.
+    pub fn matches(&self, spn: u16, spec: &SpecSPN) -> bool {
+        let number = spn.to_string();
+        let label = normalize(&spec.label);
+        // Labels are only stored without vowels when they were too long
+        let is_abbreviated = !label.iter().copied().any(is_vowel);
+        self.patterns.iter().any(|(pattern, abbreviated)| {
+            glob_match(pattern, number.as_bytes())
+                || glob_match(pattern, &label)
+                || abbreviated
+                    .as_ref()
+                    .is_some_and(|abbreviated| is_abbreviated && glob_match(abbreviated, &label))
+        })
+    }
+
+    /// Removes the SPNs that are not selected from a PGN.
+    pub fn select(&self, pgn: &mut SpecPGN) {
+        pgn.spns.retain(|spn, spec| self.matches(*spn, spec));
+    }
+}
+
+/// Lowercases text and drops the spaces, quotes and padding that labels are stored without.
+fn normalize(text: &[u8]) -> Vec<u8> {
+    text.iter()
+        .filter(|&&b| !matches!(b, 0 | b' ' | b'"'))
+        .map(|b| b.to_ascii_lowercase())
+        .collect()
+}
+
+/// Returns `true` if `b` is a lowercase vowel, the letters dropped from abbreviated labels.
+fn is_vowel(b: u8) -> bool {
+    matches!(b, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+/// Returns `true` if `text` matches `pattern`, where `*` matches any run of bytes and `?` any
+/// single byte.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` and of the text it was tried against
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more byte
+                Some((star, start)) => {
+                    p = star + 1;
+                    t = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
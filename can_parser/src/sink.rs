@@ -0,0 +1,61 @@
+use crate::can_message::CANMessage;
+use crate::diagnostics::Diagnostic;
+use crate::error::CANParserError;
+use crate::specification::FilteredSpec;
+
+/// Receives the results of a parse as they are decoded, instead of collecting them in
+/// `CANParser::messages`.
+///
+/// A sink is driven by `CANParser::parse_file_into` and `CANParser::parse_lines_into`. Messages
+/// are handed over in input order, followed by the diagnostics of the lines that could not be
+/// parsed and a single call to `on_finish`. Returning an error from any method stops the parse
+/// and is returned to the caller.
+///
+/// # Example
+///
+/// ```
+/// use can_parser::{
+///     CANMessage, CANParser, CANParserError, ErrorPolicy, MessageSink, LOG_FORMAT_CANDUMP,
+/// };
+///
+/// struct Counter(usize);
+///
+/// impl MessageSink for Counter {
+///     fn on_message(&mut self, _message: CANMessage) -> Result<(), CANParserError> {
+///         self.0 += 1;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+/// parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+/// let lines = vec![
+///     "(1692896400.000000) can0 123#1122".to_string(),
+///     "(1692896400.100000) can0 456#3344".to_string(),
+/// ];
+/// let mut counter = Counter(0);
+/// parser.parse_lines_into(&lines, &mut counter).unwrap();
+/// assert_eq!(counter.0, 2);
+/// ```
+pub trait MessageSink {
+    /// Called for each message that was parsed.
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError>;
+
+    /// Called for each line that could not be parsed, once all messages have been handed over.
+    fn on_warning(&mut self, _diagnostic: &Diagnostic) -> Result<(), CANParserError> {
+        Ok(())
+    }
+
+    /// Called once parsing is done, with the specification entries used by the parsed messages.
+    fn on_finish(&mut self, _filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        Ok(())
+    }
+}
+
+/// Collects the messages, as `CANParser::parse_file` does.
+impl MessageSink for Vec<CANMessage> {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        self.push(message);
+        Ok(())
+    }
+}
@@ -36,6 +36,13 @@ where
     serializer.serialize_str(str.trim().replace("\"", "").as_str())
 }
 
+/// Returns the text of a fixed-size field such as `SpecSPN::label`, as it is serialized.
+pub(crate) fn fixed_str(array: &[u8]) -> String {
+    String::from_utf8_lossy(array)
+        .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+        .replace('"', "")
+}
+
 // Helpers for deserialization
 /// Deserialize a string into a fixed-size array of u8 values.
 ///
@@ -190,12 +197,86 @@ impl Default for Metadata {
     }
 }
 
-#[derive(Debug)]
-pub struct SpecError(pub String);
+/// An error found while loading or reading a specification.
+///
+/// Besides the message, the error names as much as is known of where the problem is: the file,
+/// the PGN and SPN, the location within the file and the offending field.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpecError {
+    /// A description of the problem.
+    pub message: String,
+    /// The specification file, if the specification was read from a file.
+    pub file: Option<String>,
+    /// The PGN being read.
+    pub pgn: Option<u32>,
+    /// The SPN being read.
+    pub spn: Option<u32>,
+    /// Where in the file the problem is: a JSON path such as `J1939SPNdb.190.Resolution`, or a
+    /// spreadsheet cell such as `row 12, column T`.
+    pub location: Option<String>,
+    /// The name of the offending field.
+    pub field: Option<String>,
+}
+
+impl SpecError {
+    /// Creates a new `SpecError` with the given message and no context.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the specification file the error was found in.
+    pub fn with_file(mut self, file: Option<&str>) -> Self {
+        self.file = file.map(str::to_string);
+        self
+    }
+
+    /// Sets the PGN being read when the error was found.
+    pub fn with_pgn(mut self, pgn: u32) -> Self {
+        self.pgn = Some(pgn);
+        self
+    }
+
+    /// Sets the SPN being read when the error was found.
+    pub fn with_spn(mut self, spn: u32) -> Self {
+        self.spn = Some(spn);
+        self
+    }
+
+    /// Sets where in the file the error was found.
+    pub fn at(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Sets the name of the offending field.
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+}
 
 impl std::fmt::Display for SpecError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "SpecError: {}", self.0)
+        write!(f, "SpecError: {}", self.message)?;
+        let context: Vec<String> = [
+            self.file.as_ref().map(|file| format!("file {}", file)),
+            self.pgn.map(|pgn| format!("PGN {}", pgn)),
+            self.spn.map(|spn| format!("SPN {}", spn)),
+            self.location
+                .as_ref()
+                .map(|location| format!("at {}", location)),
+            self.field.as_ref().map(|field| format!("field {}", field)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !context.is_empty() {
+            write!(f, " ({})", context.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -235,6 +316,25 @@ pub trait Specification {
     /// not found in the specification.
     fn get_id_metadata(&self, id: &CANID) -> Result<Metadata, SpecError>;
 
+    /// Gets the metadata for a given CAN ID, skipping entries of the specification that cannot be
+    /// read instead of failing.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A reference to the CAN ID.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the metadata for the given CAN ID along with a `SpecError` for each
+    /// skipped entry, or a `SpecError` if the ID itself cannot be read from the specification.
+    fn get_id_metadata_skipping_invalid(
+        &self,
+        id: &CANID,
+    ) -> Result<(Metadata, Vec<SpecError>), SpecError> {
+        self.get_id_metadata(id)
+            .map(|metadata| (metadata, Vec::new()))
+    }
+
     // fn get_session_metadata(&self, session: &Vec<CANMessage>) -> Result<Metadata, SpecError>;
 }
 
@@ -258,7 +358,7 @@ pub trait Specification {
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// use can_parser::specification::{determine_path_and_file_type, FileType};
 ///
 /// let file_path = "/home/user/data.json";
@@ -276,7 +376,7 @@ pub fn determine_path_and_file_type(s: &str) -> Result<(bool, FileType), SpecErr
         let file_path_regex = Regex::new(
             r"^(?-u:(?-u:[\w \.-]|[\\/]){0,2}:?[\\/]/?)(?-u:[\w \.-]+[\\/])*(?-u:[\w \.-])*$",
         )
-        .map_err(|e| SpecError(e.to_string()))?;
+        .map_err(|e| SpecError::new(e.to_string()))?;
         is_path = file_path_regex.is_match(s);
     }
     if is_path {
@@ -288,18 +388,18 @@ pub fn determine_path_and_file_type(s: &str) -> Result<(bool, FileType), SpecErr
                 Some("xlsx") => Ok((true, FileType::Xlsx)),
                 Some("dbc") => Ok((true, FileType::Dbc)),
                 None => {
-                    let mut file = File::open(s).map_err(|e| SpecError(e.to_string()))?;
+                    let mut file = File::open(s).map_err(|e| SpecError::new(e.to_string()))?;
                     let mut buffer = [0; 5];
                     file.read(&mut buffer)
-                        .map_err(|e| SpecError(e.to_string()))?;
+                        .map_err(|e| SpecError::new(e.to_string()).with_file(Some(s)))?;
                     let contents = String::from_utf8_lossy(&buffer);
                     let file_type = determine_file_type_from_contents(&contents)?;
                     Ok((true, file_type))
                 }
-                _ => Err(SpecError("Unsupported specification file type".to_string())),
+                _ => Err(SpecError::new("Unsupported specification file type").with_file(Some(s))),
             }
         } else {
-            Err(SpecError("Specification file does not exist".to_string()))
+            Err(SpecError::new("Specification file does not exist").with_file(Some(s)))
         }
     } else {
         let file_type = determine_file_type_from_contents(s)?;
@@ -319,7 +419,7 @@ pub fn determine_path_and_file_type(s: &str) -> Result<(bool, FileType), SpecErr
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// use can_parser::specification::{determine_file_type_from_contents, FileType};
 ///
 /// let contents = "{ \"name\": \"example\", \"version\": 1 }";
@@ -334,7 +434,7 @@ pub fn determine_file_type_from_contents(contents: &str) -> Result<FileType, Spe
     } else if contents.starts_with("VERSION") {
         Ok(FileType::Dbc)
     } else {
-        Err(SpecError("Unsupported specification file type".to_string()))
+        Err(SpecError::new("Unsupported specification file type"))
     }
 }
 
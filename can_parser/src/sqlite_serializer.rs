@@ -1,7 +1,9 @@
 use crate::can_message::{CANMessage, CANID};
 use crate::error::CANParserError;
+use crate::sink::MessageSink;
 use crate::specification::{SpecPGN, SpecSPN, FilteredSpec};
-use rusqlite::{params, Connection, DatabaseName};
+use rusqlite::{params, Connection};
+use std::borrow::Borrow;
 use std::path::Path;
 
 /// Writes the parsed CAN data to an SQLite database at the specified output path.
@@ -16,32 +18,88 @@ use std::path::Path;
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// use can_parser::CANParser;
 ///
 /// let parser = CANParser::new();
 /// parser.parse("path/to/can/data.log").unwrap();
 /// parser.to_sqlite("path/to/output.db").unwrap();
 /// ```
-pub fn to_sqlite(
+pub fn to_sqlite<M: Borrow<CANMessage>>(
     output_path: String,
     filtered_spec: &FilteredSpec,
-    messages: &Vec<CANMessage>,
+    messages: impl IntoIterator<Item = M>,
 ) -> Result<(), CANParserError> {
-    let conn = Connection::open_in_memory()?;
-    create_sqlite_tables(&conn)?;
-    for (pgn, pgn_data) in filtered_spec.j1939.read().unwrap().clone().into_iter() {
-        insert_spec_pgn(&conn, pgn, &pgn_data)?;
-        for (spn, spn_data) in pgn_data.spns {
-            insert_spec_spn(&conn, pgn, spn, &spn_data)?;
+    let mut sink = SqliteSink::create(output_path)?;
+    for message in messages {
+        sink.insert(message.borrow())?;
+    }
+    sink.on_finish(filtered_spec)
+}
+
+/// The number of messages a `SqliteSink` inserts in each transaction.
+const SQLITE_BATCH_MESSAGES: usize = 1 << 14;
+
+/// A `MessageSink` writing the messages to an SQLite database while they are parsed.
+///
+/// The messages are written straight to the database file, in transactions of
+/// `SQLITE_BATCH_MESSAGES` messages, so memory use does not grow with the log. The specification
+/// tables are written when parsing finishes, as by `CANParser::to_sqlite`.
+pub struct SqliteSink {
+    conn: Connection,
+    /// The number of messages inserted in the open transaction.
+    pending: usize,
+}
+
+impl SqliteSink {
+    /// Creates a `SqliteSink` writing the database to `output_path`, replacing any existing file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the existing file cannot be removed or the database cannot
+    /// be created.
+    pub fn create(output_path: String) -> Result<Self, CANParserError> {
+        if Path::new(&output_path).exists() {
+            std::fs::remove_file(&output_path)?;
         }
+        let conn = Connection::open(&output_path)?;
+        // Messages are inserted before the specification they reference, and the PGNs of
+        // messages without a specification are referenced by none
+        conn.pragma_update(None, "foreign_keys", false)?;
+        create_sqlite_tables(&conn)?;
+        conn.execute_batch("BEGIN")?;
+        Ok(Self { conn, pending: 0 })
     }
-    for message in messages.clone() {
-        insert_canid(&conn, &message.id)?;
-        insert_message(&conn, &message)?;
+
+    /// Inserts a message and its CAN ID, committing the open transaction once it holds
+    /// `SQLITE_BATCH_MESSAGES` messages.
+    fn insert(&mut self, message: &CANMessage) -> Result<(), CANParserError> {
+        insert_canid(&self.conn, &message.id)?;
+        insert_message(&self.conn, message)?;
+        self.pending += 1;
+        if self.pending == SQLITE_BATCH_MESSAGES {
+            self.conn.execute_batch("COMMIT; BEGIN")?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+}
+
+impl MessageSink for SqliteSink {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        self.insert(&message)
+    }
+
+    fn on_finish(&mut self, filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        for (pgn, pgn_data) in filtered_spec.j1939.read().unwrap().iter() {
+            insert_spec_pgn(&self.conn, *pgn, pgn_data)?;
+            for (spn, spn_data) in &pgn_data.spns {
+                insert_spec_spn(&self.conn, *pgn, *spn, spn_data)?;
+            }
+        }
+        self.conn.execute_batch("COMMIT")?;
+        Ok(())
     }
-    conn.backup(DatabaseName::Main, Path::new(&output_path), None)?;
-    Ok(())
 }
 
 /// Creates SQLite tables for storing CAN messages, PGNs, SPNs, and CAN IDs.
@@ -56,7 +114,7 @@ pub fn to_sqlite(
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use can_parser::CANParser;
 ///
 /// let parser = CANParser::new();
@@ -114,10 +172,17 @@ pub fn create_sqlite_tables(conn: &Connection) -> Result<(), CANParserError> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS messages (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
-                timestamp REAL,
+                timestamp INTEGER,
+                channel TEXT,
+                direction TEXT,
+                source TEXT,
                 can_id REFERENCES CANIDs(id),
                 length INTEGER,
                 data BLOB,
+                fd INTEGER,
+                brs INTEGER,
+                esi INTEGER,
+                error_frame TEXT,
                 spn_values TEXT
             )",
         [],
@@ -180,7 +245,7 @@ pub fn insert_spec_pgn(conn: &Connection, pgn: u16, pgn_data: &SpecPGN) -> Resul
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use can_parser::{CANParserError, SpecSPN};
 /// use rusqlite::Connection;
 ///
@@ -251,7 +316,7 @@ pub fn insert_spec_spn(
 ///
 /// # Example
 ///
-/// ```
+/// ```ignore
 /// use can_parser::CANID;
 /// use rusqlite::Connection;
 ///
@@ -299,7 +364,7 @@ pub fn insert_canid(conn: &Connection, id: &CANID) -> Result<(), CANParserError>
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// use can_parser::{CANMessage, CANParserError};
 /// use rusqlite::Connection;
 ///
@@ -310,20 +375,39 @@ pub fn insert_canid(conn: &Connection, id: &CANID) -> Result<(), CANParserError>
 /// ```
 pub fn insert_message(conn: &Connection, message: &CANMessage) -> Result<(), CANParserError> {
     let spn_values = serde_json::to_string(&message.data.spns)?;
+    let error_frame = message
+        .error
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
     conn.execute(
         "INSERT OR IGNORE INTO messages (
                 timestamp,
+                channel,
+                direction,
+                source,
                 can_id,
                 length,
                 data,
+                fd,
+                brs,
+                esi,
+                error_frame,
                 spn_values
             )
-            VALUES (?1, ?2, ?3, ?4, ?5)",
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         params![
             message.ts,
+            message.channel,
+            message.direction.map(|d| d.to_string()),
+            message.source.as_deref(),
             message.id.id,
             message.data.len,
-            message.data.data,
+            &message.data.data[..message.data.len as usize],
+            message.id.flags.fd,
+            message.id.flags.brs,
+            message.id.flags.esi,
+            error_frame,
             spn_values
         ],
     )?;
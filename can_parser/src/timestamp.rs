@@ -0,0 +1,353 @@
+use crate::error::CANParserError;
+use crate::log_format::LineFields;
+use regex::Captures;
+
+pub const NANOS_PER_SEC: i64 = 1_000_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+pub const TIMESTAMP_EPOCH: &str = "epoch";
+pub const TIMESTAMP_RELATIVE: &str = "relative";
+pub const TIMESTAMP_ISO8601: &str = "iso8601";
+pub const TIMESTAMP_DATETIME: &str = "datetime";
+
+/// The format of the timestamps captured by the line regex.
+///
+/// Every format is converted to integer nanoseconds so that epoch timestamps keep their full
+/// sub-microsecond precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch in the `timestamp` group, e.g. `1692896400.123456`, or
+    /// milliseconds in the `timestamp_ms` group.
+    #[default]
+    Epoch,
+    /// Seconds since the start of the log in the `timestamp` group, or milliseconds in the
+    /// `timestamp_ms` group, added to `start` (nanoseconds since the Unix epoch).
+    Relative { start: i64 },
+    /// An ISO-8601 date and time in the `timestamp` group, e.g. `2023-08-24T17:00:00.123+02:00`.
+    /// Times without a time zone are taken to be `utc_offset` seconds ahead of UTC.
+    Iso8601 { utc_offset: i32 },
+    /// A `date` group (`YYYY-MM-DD` or `YYYY/MM/DD`) and a `time` group (`HH:MM:SS.ffff`). Without
+    /// a `date` group the time is the time of day, read from `time` or `timestamp`.
+    /// Times are taken to be `utc_offset` seconds ahead of UTC.
+    DateTime { utc_offset: i32 },
+}
+
+impl TimestampFormat {
+    /// Creates a `TimestampFormat` from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - One of "epoch", "relative", "iso8601" or "datetime".
+    /// * `utc_offset` - An optional UTC offset such as "+02:00" or "Z" used by "iso8601" and "datetime". Defaults to UTC.
+    /// * `start` - The start of the log in nanoseconds since the Unix epoch, used by "relative". Defaults to 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `CANParserError` if the name or the UTC offset is invalid.
+    pub fn from_name(
+        name: &str,
+        utc_offset: Option<&str>,
+        start: Option<i64>,
+    ) -> Result<Self, CANParserError> {
+        let utc_offset = utc_offset
+            .map(parse_utc_offset)
+            .transpose()
+            .map_err(CANParserError::ParserError)?
+            .unwrap_or_default();
+        match name.to_ascii_lowercase().as_str() {
+            TIMESTAMP_EPOCH => Ok(TimestampFormat::Epoch),
+            TIMESTAMP_RELATIVE => Ok(TimestampFormat::Relative {
+                start: start.unwrap_or_default(),
+            }),
+            TIMESTAMP_ISO8601 => Ok(TimestampFormat::Iso8601 { utc_offset }),
+            TIMESTAMP_DATETIME => Ok(TimestampFormat::DateTime { utc_offset }),
+            _ => Err(CANParserError::ParserError(format!(
+                "Unknown timestamp format: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Parses the timestamp captured from a line.
+    ///
+    /// # Arguments
+    ///
+    /// * `captures` - The captures of the line regex.
+    ///
+    /// # Returns
+    ///
+    /// The timestamp in nanoseconds since the Unix epoch (or since the start of the log), or `None`
+    /// if the line regex has no timestamp groups. An error message is returned if the timestamp
+    /// could not be parsed.
+    pub fn parse(&self, captures: &Captures) -> Result<Option<i64>, String> {
+        self.parse_fields(&LineFields::from_captures(captures))
+    }
+
+    /// Parses the timestamp of a line from its fields, as `parse` does from the captures. Epoch
+    /// and relative timestamps are read in milliseconds from a `timestamp_ms` field.
+    pub(crate) fn parse_fields(&self, fields: &LineFields) -> Result<Option<i64>, String> {
+        let timestamp = fields.timestamp;
+        let seconds = |ts: &str| match fields.timestamp_ms {
+            true => parse_millis(ts),
+            false => parse_seconds(ts),
+        };
+        match self {
+            TimestampFormat::Epoch => timestamp.map(seconds).transpose(),
+            TimestampFormat::Relative { start } => timestamp
+                .map(|ts| {
+                    seconds(ts)?
+                        .checked_add(*start)
+                        .ok_or_else(|| format!("Timestamp out of range: {}", ts))
+                })
+                .transpose(),
+            TimestampFormat::Iso8601 { utc_offset } => timestamp
+                .map(|ts| parse_iso8601(ts, *utc_offset))
+                .transpose(),
+            TimestampFormat::DateTime { utc_offset } => {
+                let time = match fields.time.or(timestamp) {
+                    Some(time) => parse_time_of_day(time)?,
+                    None => return Ok(None),
+                };
+                let days = match fields.date {
+                    Some(date) => parse_date(date)?,
+                    None => 0,
+                };
+                epoch_nanos(days, time, *utc_offset)
+                    .map(Some)
+                    .ok_or_else(|| format!("Timestamp out of range: {}", fields.date.unwrap_or("")))
+            }
+        }
+    }
+}
+
+/// Converts a timestamp in nanoseconds to floating point seconds.
+pub fn nanos_to_secs(ns: i64) -> f64 {
+    (ns / NANOS_PER_SEC) as f64 + (ns % NANOS_PER_SEC) as f64 / NANOS_PER_SEC as f64
+}
+
+/// Formats a timestamp in nanoseconds as decimal seconds, with microseconds unless the timestamp
+/// has a finer resolution.
+pub(crate) fn format_seconds(ns: i64) -> String {
+    let sign = if ns < 0 { "-" } else { "" };
+    let ns = ns.unsigned_abs();
+    let secs = ns / NANOS_PER_SEC as u64;
+    let nanos = ns % NANOS_PER_SEC as u64;
+    if nanos.is_multiple_of(1000) {
+        format!("{}{}.{:06}", sign, secs, nanos / 1000)
+    } else {
+        format!("{}{}.{:09}", sign, secs, nanos)
+    }
+}
+
+/// Parses decimal seconds such as `1692896400.123456789` into nanoseconds without going through a float.
+///
+/// Digits beyond nanosecond precision are truncated.
+pub fn parse_seconds(s: &str) -> Result<i64, String> {
+    parse_decimal(s, 9)
+}
+
+/// Parses decimal milliseconds such as `1692896400123.456` into nanoseconds, as `parse_seconds`
+/// does seconds. Epoch milliseconds would overflow if they were read as seconds first.
+fn parse_millis(s: &str) -> Result<i64, String> {
+    parse_decimal(s, 6)
+}
+
+/// Parses a decimal number of units into nanoseconds, where a unit has `digits` decimal digits
+/// of nanoseconds.
+fn parse_decimal(s: &str, digits: u32) -> Result<i64, String> {
+    let err = || format!("Failed to parse timestamp {}", s);
+    let (negative, number) = match s.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, s),
+    };
+    let (units, frac) = number.split_once('.').unwrap_or((number, ""));
+    if units.is_empty() && frac.is_empty() {
+        return Err(err());
+    }
+    if !units.bytes().all(|b| b.is_ascii_digit()) || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(err());
+    }
+    let units: i64 = if units.is_empty() {
+        0
+    } else {
+        units.parse().map_err(|_| err())?
+    };
+    let mut nanos: i64 = 0;
+    for (i, b) in frac.bytes().take(digits as usize).enumerate() {
+        nanos += (b - b'0') as i64 * 10i64.pow(digits - 1 - i as u32);
+    }
+    let ns = units
+        .checked_mul(10i64.pow(digits))
+        .and_then(|ns| ns.checked_add(nanos))
+        .ok_or_else(err)?;
+    Ok(if negative { -ns } else { ns })
+}
+
+/// Parses a UTC offset such as `Z`, `+02:00`, `-0530` or `+01` into seconds ahead of UTC.
+pub fn parse_utc_offset(s: &str) -> Result<i32, String> {
+    let err = || format!("Failed to parse UTC offset {}", s);
+    if s.eq_ignore_ascii_case("z") || s.eq_ignore_ascii_case("utc") {
+        return Ok(0);
+    }
+    let sign = match s.as_bytes().first() {
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        _ => return Err(err()),
+    };
+    let digits = s[1..].replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(err());
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (&digits[..2], "0"),
+        4 => (&digits[..2], &digits[2..]),
+        _ => return Err(err()),
+    };
+    let hours: i32 = hours.parse().map_err(|_| err())?;
+    let minutes: i32 = minutes.parse().map_err(|_| err())?;
+    if hours > 23 || minutes > 59 {
+        return Err(err());
+    }
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parses an ISO-8601 date and time into nanoseconds since the Unix epoch.
+///
+/// The date and time may be separated by `T` or a space. An explicit time zone takes precedence
+/// over `utc_offset`.
+fn parse_iso8601(s: &str, utc_offset: i32) -> Result<i64, String> {
+    let err = || format!("Failed to parse ISO-8601 timestamp {}", s);
+    let (date, time) = s.split_once(['T', 't', ' ']).ok_or_else(err)?;
+    let days = parse_date(date)?;
+    let (time, utc_offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => (&time[..i], parse_utc_offset(&time[i..])?),
+        None => (time, utc_offset),
+    };
+    let time = parse_time_of_day(time)?;
+    epoch_nanos(days, time, utc_offset).ok_or_else(err)
+}
+
+/// Combines days since the Unix epoch, nanoseconds since midnight and a UTC offset into
+/// nanoseconds since the Unix epoch, or `None` if the result does not fit in an `i64`.
+fn epoch_nanos(days: i64, time: i64, utc_offset: i32) -> Option<i64> {
+    days.checked_mul(SECS_PER_DAY * NANOS_PER_SEC)?
+        .checked_add(time)?
+        .checked_sub(utc_offset as i64 * NANOS_PER_SEC)
+}
+
+/// Parses a `YYYY-MM-DD` or `YYYY/MM/DD` date into days since the Unix epoch.
+fn parse_date(s: &str) -> Result<i64, String> {
+    let err = || format!("Failed to parse date {}", s);
+    let mut parts = s.split(['-', '/']);
+    let mut next = || -> Result<i64, String> {
+        parts
+            .next()
+            .filter(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|p| p.parse::<i64>().ok())
+            .ok_or_else(err)
+    };
+    let (year, month, day) = (next()?, next()?, next()?);
+    if parts.next().is_some()
+        || year > 9999
+        || !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+    {
+        return Err(err());
+    }
+    Ok(days_from_civil(year, month, day))
+}
+
+/// Returns the number of days in a month of the proleptic Gregorian calendar.
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Parses a `HH:MM:SS.ffff` or `HH:MM` time of day into nanoseconds since midnight.
+fn parse_time_of_day(s: &str) -> Result<i64, String> {
+    let err = || format!("Failed to parse time {}", s);
+    let mut parts = s.trim().splitn(3, ':');
+    let hours: i64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(err)?;
+    let minutes: i64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(err)?;
+    let seconds = match parts.next() {
+        Some(seconds) => parse_seconds(seconds)?,
+        None => 0,
+    };
+    if !(0..24).contains(&hours)
+        || !(0..60).contains(&minutes)
+        || !(0..60 * NANOS_PER_SEC).contains(&seconds)
+    {
+        return Err(err());
+    }
+    Ok((hours * 3600 + minutes * 60) * NANOS_PER_SEC + seconds)
+}
+
+/// Returns the number of days between the Unix epoch and the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Returns the proleptic Gregorian `(year, month, day)` of a number of days since the Unix epoch,
+/// the inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// A UTC date and time of day, as written in log file headers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CivilTime {
+    pub year: i64,
+    /// The month, from 1 (January) to 12.
+    pub month: i64,
+    pub day: i64,
+    /// The day of the week, from 0 (Sunday) to 6.
+    pub weekday: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    pub millisecond: i64,
+}
+
+impl CivilTime {
+    /// Splits a timestamp in nanoseconds since the Unix epoch into its UTC date and time of day.
+    pub(crate) fn from_nanos(ns: i64) -> Self {
+        let millis = ns.div_euclid(1_000_000);
+        let days = millis.div_euclid(SECS_PER_DAY * 1000);
+        let millis_of_day = millis.rem_euclid(SECS_PER_DAY * 1000);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            // The Unix epoch was a Thursday
+            weekday: (days + 4).rem_euclid(7),
+            hour: millis_of_day / 3_600_000,
+            minute: millis_of_day / 60_000 % 60,
+            second: millis_of_day / 1000 % 60,
+            millisecond: millis_of_day % 1000,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use can_parser::{
+    CANParser, CANParserError, Diagnostic, DiagnosticKind, Diagnostics, ErrorPolicy, LogFormat,
+};
+
+/// Feeding a log in chunks in append mode must accumulate the messages and diagnostics of every
+/// chunk, with line numbers continuing across chunks.
+#[test]
+fn parse_lines_appends_across_calls() {
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    parser.set_append(true);
+
+    let first = vec![
+        "(1692896400.000000) can0 123#1122".to_string(),
+        "(1692896400.000100) can0 456#3344".to_string(),
+    ];
+    let second = vec![
+        "(1692896400.000200) can0 789#5566".to_string(),
+        "not a candump line".to_string(),
+    ];
+    parser.parse_lines(&first).unwrap();
+    match parser.parse_lines(&second) {
+        Err(CANParserError::ParserWarning(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics.entries[0].line_number, 4);
+        }
+        other => panic!("expected a parser warning, got {:?}", other.err()),
+    }
+    assert_eq!(parser.messages.len(), 3);
+    assert_eq!(parser.diagnostics.len(), 1);
+
+    parser.reset();
+    assert!(parser.messages.is_empty());
+    assert!(parser.diagnostics.is_empty());
+    let _ = parser.parse_lines(&second);
+    assert_eq!(parser.diagnostics.entries[0].line_number, 2);
+}
+
+/// Returns diagnostics of the given kind at the given sources and line numbers, in that order.
+fn diagnostics(kind: DiagnosticKind, lines: &[(Option<&str>, u64)]) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+    for (source, line_number) in lines {
+        diagnostics.push(Diagnostic {
+            source: source.map(str::to_string),
+            line_number: *line_number,
+            byte_offset: None,
+            line: String::new(),
+            kind,
+            message: String::new(),
+        });
+    }
+    diagnostics
+}
+
+/// Extending diagnostics must merge the incoming ones, in any order, into the ordered entries and
+/// add up the counts.
+#[test]
+fn diagnostics_extend_merges_in_order() {
+    let mut merged = diagnostics(
+        DiagnosticKind::RegexMiss,
+        &[(None, 2), (None, 5), (Some("a.log"), 1), (Some("b.log"), 3)],
+    );
+    merged.extend(diagnostics(
+        DiagnosticKind::BadHex,
+        &[
+            (Some("b.log"), 1),
+            (None, 9),
+            (Some("a.log"), 4),
+            (None, 1),
+            (None, 5),
+        ],
+    ));
+
+    let order: Vec<(Option<&str>, u64, DiagnosticKind)> = merged
+        .entries
+        .iter()
+        .map(|d| (d.source.as_deref(), d.line_number, d.kind))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (None, 1, DiagnosticKind::BadHex),
+            (None, 2, DiagnosticKind::RegexMiss),
+            (None, 5, DiagnosticKind::RegexMiss),
+            (None, 5, DiagnosticKind::BadHex),
+            (None, 9, DiagnosticKind::BadHex),
+            (Some("a.log"), 1, DiagnosticKind::RegexMiss),
+            (Some("a.log"), 4, DiagnosticKind::BadHex),
+            (Some("b.log"), 1, DiagnosticKind::BadHex),
+            (Some("b.log"), 3, DiagnosticKind::RegexMiss),
+        ]
+    );
+    assert_eq!(merged.count(DiagnosticKind::RegexMiss), 4);
+    assert_eq!(merged.count(DiagnosticKind::BadHex), 5);
+}
@@ -0,0 +1,86 @@
+use can_parser::{dlc_to_len, len_to_dlc, CANParserError, DiagnosticKind, ErrorPolicy};
+
+mod common;
+use common::candump_parsers;
+
+/// CAN FD frames must keep their payload and their BRS and ESI flags.
+#[test]
+fn canfd_frames_keep_flags() {
+    let payload = "00112233445566778899AABB";
+    for mut parser in candump_parsers(ErrorPolicy::Warn) {
+        let lines: Vec<String> = vec![
+            format!("(1692896400.000000) can0 123##0{}", payload),
+            format!("(1692896400.000100) can0 123##1{}", payload),
+            format!(
+                "(1692896400.000200) can0 18FEF100##3{}{}",
+                payload,
+                "CC".repeat(52)
+            ),
+            "(1692896400.000300) can0 123#1122".to_string(),
+        ];
+        parser.parse_lines(&lines).unwrap();
+        assert!(parser.diagnostics.is_empty());
+        assert!(parser.flags.read().unwrap().canfd);
+
+        let flags: Vec<(bool, bool, bool)> = parser
+            .messages
+            .iter()
+            .map(|m| (m.id.flags.fd, m.id.flags.brs, m.id.flags.esi))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                (true, false, false),
+                (true, true, false),
+                (true, true, true),
+                (false, false, false)
+            ]
+        );
+        let lengths: Vec<u8> = parser.messages.iter().map(|m| m.data.len).collect();
+        assert_eq!(lengths, vec![12, 12, 64, 2]);
+        assert_eq!(parser.messages[0].data.data[11], 0xBB);
+    }
+}
+
+/// Payloads of a length without a DLC must be rejected as bad lengths.
+#[test]
+fn invalid_lengths_are_rejected() {
+    for mut parser in candump_parsers(ErrorPolicy::Warn) {
+        let lines: Vec<String> = vec![
+            // 9 bytes is not a CAN FD length
+            "(1692896400.000000) can0 123##0112233445566778899".to_string(),
+            // 12 bytes is too long for classic CAN
+            "(1692896400.000100) can0 123#00112233445566778899AABB".to_string(),
+            // Longer than any CAN FD frame
+            format!("(1692896400.000200) can0 123##0{}", "00".repeat(65)),
+            // An odd number of digits
+            "(1692896400.000300) can0 123#112".to_string(),
+        ];
+        match parser.parse_lines(&lines) {
+            Err(CANParserError::ParserWarning(diagnostics)) => {
+                assert_eq!(diagnostics.count(DiagnosticKind::BadLength), 3);
+                assert_eq!(diagnostics.count(DiagnosticKind::BadHex), 1);
+            }
+            other => panic!("Expected a warning, got {:?}", other),
+        }
+        assert!(parser.messages.is_empty());
+    }
+}
+
+/// Data length codes must map to the CAN FD lengths, and classic CAN must cap them at 8 bytes.
+#[test]
+fn dlc_conversions() {
+    let lengths: Vec<usize> = (0..16).map(|dlc| dlc_to_len(dlc, true)).collect();
+    assert_eq!(
+        lengths,
+        vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64]
+    );
+    assert_eq!(dlc_to_len(15, false), 8);
+    for (dlc, len) in lengths.iter().enumerate() {
+        assert_eq!(len_to_dlc(*len, true), Some(dlc as u8));
+    }
+    assert_eq!(len_to_dlc(8, false), Some(8));
+    assert_eq!(len_to_dlc(12, false), None);
+    assert_eq!(len_to_dlc(9, true), None);
+    assert_eq!(len_to_dlc(65, true), None);
+}
@@ -0,0 +1,60 @@
+use can_parser::{CANParser, Direction, ErrorPolicy, LogFormat, SPEC_TYPE_J1939};
+
+mod common;
+use common::{candump_parsers, eec1_annexes};
+
+/// The channel and direction of each line must be captured by the candump template, whether
+/// the line is split by the hand-written parser or matched by the regex itself.
+#[test]
+fn candump_channel_and_direction() {
+    for parser in candump_parsers(ErrorPolicy::Strict) {
+        let message = parser
+            .parse_line("(1692896400.000000) vcan-1 123#1122 T".to_string())
+            .unwrap();
+        assert_eq!(message.channel.as_deref(), Some("vcan-1"));
+        assert_eq!(message.direction, Some(Direction::Tx));
+
+        let message = parser
+            .parse_line("(1692896400.000000) can0 18FEF100#0011223344556677 R".to_string())
+            .unwrap();
+        assert_eq!(message.channel.as_deref(), Some("can0"));
+        assert_eq!(message.direction, Some(Direction::Rx));
+        assert_eq!(message.data.len, 8);
+
+        let message = parser
+            .parse_line("(1692896400.000000) can0 123#11".to_string())
+            .unwrap();
+        assert_eq!(message.direction, None);
+    }
+}
+
+/// Channels restricted to no specification must not be decoded, while the others still are.
+#[test]
+fn channel_specs_restrict_decoding() {
+    let line_regex = LogFormat::Candump.regex().to_string();
+    let mut parser =
+        CANParser::new(ErrorPolicy::Strict, Some(line_regex), Some(eec1_annexes())).unwrap();
+    parser
+        .set_channel_specs("can1".to_string(), Vec::new())
+        .unwrap();
+    assert!(parser
+        .set_channel_specs("can2".to_string(), vec!["unknown".to_string()])
+        .is_err());
+
+    let decoded = parser
+        .parse_line("(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF".to_string())
+        .unwrap();
+    assert_eq!(decoded.data.spns.get(&190), Some(&1500.0));
+    let skipped = parser
+        .parse_line("(1692896400.000000) can1 18F00400#FF64FFE02EFFFFFF".to_string())
+        .unwrap();
+    assert!(skipped.data.spns.is_empty());
+
+    parser
+        .set_channel_specs("can1".to_string(), vec![SPEC_TYPE_J1939.to_string()])
+        .unwrap();
+    let decoded = parser
+        .parse_line("(1692896400.000000) can1 18F00400#FF64FFE02EFFFFFF".to_string())
+        .unwrap();
+    assert_eq!(decoded.data.spns.get(&190), Some(&1500.0));
+}
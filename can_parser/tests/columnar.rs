@@ -0,0 +1,38 @@
+use can_parser::{CANParser, ErrorPolicy, LogFormat};
+
+/// The columnar store must give back the same messages, in the same order, as the message Vec.
+#[test]
+fn columnar_store_matches_messages() {
+    let lines = vec![
+        "(1692896400.000000) can0 123#1122".to_string(),
+        "(1692896400.000100) can1 18FEF100#0011223344556677 R".to_string(),
+        "(1692896400.000200) can0 123#33".to_string(),
+        "(1692896400.000300) can0 456##100112233445566778899AABB".to_string(),
+    ];
+    let mut rows = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    rows.parse_lines(&lines).unwrap();
+    let mut columns = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    columns.set_columnar(true);
+    columns.parse_lines(&lines).unwrap();
+
+    assert!(columns.messages.is_empty());
+    assert_eq!(columns.store.len(), 4);
+    assert_eq!(columns.store.tables().len(), 3);
+    let table = &columns.store.tables()[0];
+    assert_eq!(table.payload(1), &[0x33]);
+    assert_eq!(columns.store.tables()[2].payload(0).len(), 12);
+    assert_eq!(
+        serde_json::to_value(&rows.messages).unwrap(),
+        serde_json::to_value(columns.iter_messages().collect::<Vec<_>>()).unwrap()
+    );
+}
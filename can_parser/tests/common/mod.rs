@@ -0,0 +1,61 @@
+//! Helpers shared by the integration tests. Each test file only uses some of them.
+#![allow(dead_code)]
+
+use can_parser::{CANParser, ErrorPolicy, LogFormat, SPEC_TYPE_J1939};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// A J1939 annex with the engine speed and driver's demand torque of EEC1.
+pub const EEC1_ANNEX: &str = r#"{
+    "J1939PGNdb": {
+        "61444": {
+            "Name": "Electronic Engine Controller 1",
+            "Label": "EEC1",
+            "PGNLength": "8",
+            "Rate": "engine speed dependent (20 ms)",
+            "SPNs": [512, 190],
+            "SPNStartBits": [[8], [24]]
+        }
+    },
+    "J1939SPNdb": {
+        "512": {
+            "Name": "Driver's Demand Engine - Percent Torque",
+            "Units": "%",
+            "SPNLength": 8,
+            "Resolution": 1,
+            "Offset": -125,
+            "OperationalHigh": 125
+        },
+        "190": {
+            "Name": "Engine Speed",
+            "Units": "rpm",
+            "SPNLength": 16,
+            "Resolution": 0.125,
+            "Offset": 0,
+            "OperationalHigh": 8031.875
+        }
+    }
+}"#;
+
+/// Returns the annexes of a parser decoding with `EEC1_ANNEX`, passed as JSON rather than a path.
+pub fn eec1_annexes() -> HashMap<String, String> {
+    HashMap::from([(SPEC_TYPE_J1939.to_string(), EEC1_ANNEX.to_string())])
+}
+
+/// Returns a path in the temporary directory unique to this test process, so that parallel runs
+/// do not collide.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("can_parser_{}_{}", std::process::id(), name))
+}
+
+/// Returns parsers splitting candump lines by hand and with the template regex.
+pub fn candump_parsers(error_handling: ErrorPolicy) -> Vec<CANParser> {
+    // Wrapping the template keeps the parser from recognizing it, so the regex is matched
+    [
+        LogFormat::Candump.regex().to_string(),
+        format!("(?:{})", LogFormat::Candump.regex()),
+    ]
+    .into_iter()
+    .map(|line_regex| CANParser::new(error_handling, Some(line_regex), None).unwrap())
+    .collect()
+}
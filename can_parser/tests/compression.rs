@@ -0,0 +1,109 @@
+use can_parser::{
+    log_size, open_log_reader, CANParser, Compression, ErrorPolicy, LOG_FORMAT_CANDUMP,
+};
+use std::io::{BufRead, Write};
+
+mod common;
+use common::temp_path;
+
+const LOG: &str =
+    "(1692896400.000000) can0 123#1122\n(1692896400.000100) can0 18FEF100#0011223344556677\n";
+
+/// Returns the path of a temporary log file named after `name`, holding `bytes`.
+fn write_log(name: &str, bytes: &[u8]) -> String {
+    let path = temp_path(&format!("compression_{}.log", name));
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(bytes)
+        .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Returns the IDs parsed from the log at `path`.
+fn parsed_ids(path: &str) -> Result<Vec<u32>, can_parser::CANParserError> {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None)?;
+    parser.set_log_format(LOG_FORMAT_CANDUMP)?;
+    parser.parse_file(path)?;
+    Ok(parser
+        .messages
+        .iter()
+        .map(|message| message.id.id)
+        .collect())
+}
+
+/// Compression must be detected from the magic bytes, whatever the file is named.
+#[test]
+fn compression_is_detected_from_magic_bytes() {
+    assert_eq!(
+        Compression::from_magic(&[0x1F, 0x8B, 0x08]),
+        Compression::Gzip
+    );
+    assert_eq!(
+        Compression::from_magic(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+        Compression::Zstd
+    );
+    assert_eq!(
+        Compression::from_magic(&[0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00]),
+        Compression::Xz
+    );
+    assert_eq!(Compression::from_magic(b"BZh91AY"), Compression::Bzip2);
+    assert_eq!(Compression::from_magic(LOG.as_bytes()), Compression::None);
+    assert_eq!(Compression::from_magic(&[0x1F]), Compression::None);
+    assert_eq!(Compression::from_magic(&[]), Compression::None);
+
+    let path = write_log("plain", LOG.as_bytes());
+    assert_eq!(log_size(&path), Some(LOG.len() as u64));
+    let lines: Vec<String> = open_log_reader(&path)
+        .unwrap()
+        .lines()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(lines, LOG.lines().collect::<Vec<_>>());
+    assert_eq!(parsed_ids(&path).unwrap(), vec![0x123, 0x18FEF100]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Compressed logs must be decompressed on the fly, including gzip files of several members.
+#[cfg(feature = "compression")]
+#[test]
+fn compressed_logs_are_decompressed() {
+    use std::io::Read;
+
+    let mut gzip = Vec::new();
+    // Two gzip members, as written by appending to a gzip log
+    for line in LOG.lines() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        writeln!(encoder, "{}", line).unwrap();
+        gzip.extend(encoder.finish().unwrap());
+    }
+    let zstd = zstd::stream::encode_all(LOG.as_bytes(), 0).unwrap();
+    let mut xz = Vec::new();
+    xz2::read::XzEncoder::new(LOG.as_bytes(), 6)
+        .read_to_end(&mut xz)
+        .unwrap();
+    let mut bzip2 = Vec::new();
+    bzip2::read::BzEncoder::new(LOG.as_bytes(), bzip2::Compression::default())
+        .read_to_end(&mut bzip2)
+        .unwrap();
+
+    for (name, bytes) in [("gzip", gzip), ("zstd", zstd), ("xz", xz), ("bzip2", bzip2)] {
+        let path = write_log(name, &bytes);
+        assert_eq!(log_size(&path), None, "{}", name);
+        assert_eq!(
+            parsed_ids(&path).unwrap(),
+            vec![0x123, 0x18FEF100],
+            "{}",
+            name
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+/// Without the `compression` feature, compressed logs must be rejected instead of parsed as text.
+#[cfg(not(feature = "compression"))]
+#[test]
+fn compressed_logs_need_the_feature() {
+    let path = write_log("gzip", &[0x1F, 0x8B, 0x08, 0x00]);
+    assert!(parsed_ids(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,149 @@
+use can_parser::{
+    CANParser, CANParserError, DiagnosticKind, Diagnostics, ErrorPolicy, LogFormat,
+    MAX_DIAGNOSTICS_PER_KIND,
+};
+use std::sync::Arc;
+
+mod common;
+use common::temp_path;
+
+const LINES: [&str; 5] = [
+    "(1692896400.000000) can0 123#1122",
+    "not a frame",
+    "(1692896400.000200) can0 123#112",
+    "(1692896400.000300) can0 123#1122334455667788AA",
+    "(1692896400.000400) can0 456#33",
+];
+
+/// Returns the diagnostics carried by a parse warning.
+fn warning(result: Result<(), CANParserError>) -> Arc<Diagnostics> {
+    match result {
+        Err(CANParserError::ParserWarning(diagnostics)) => diagnostics,
+        other => panic!("Expected a warning, got {:?}", other),
+    }
+}
+
+/// Returns a parser splitting candump lines.
+fn parser() -> CANParser {
+    CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap()
+}
+
+/// Lines that cannot be parsed must be reported with their line number, raw text and kind, and
+/// counted per kind.
+#[test]
+fn parse_lines_reports_diagnostics() {
+    let mut parser = parser();
+    let lines: Vec<String> = LINES.iter().map(|line| line.to_string()).collect();
+    let diagnostics = warning(parser.parse_lines(&lines));
+    assert_eq!(parser.messages.len(), 2);
+
+    assert_eq!(diagnostics.len(), 3);
+    let positions: Vec<(u64, &str, DiagnosticKind)> = diagnostics
+        .entries
+        .iter()
+        .map(|d| (d.line_number, d.line.as_str(), d.kind))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            (2, LINES[1], DiagnosticKind::RegexMiss),
+            (3, LINES[2], DiagnosticKind::BadHex),
+            (4, LINES[3], DiagnosticKind::BadLength),
+        ]
+    );
+    assert!(diagnostics.entries.iter().all(|d| d.source.is_none()));
+    assert!(diagnostics.entries.iter().all(|d| d.byte_offset.is_none()));
+    assert_eq!(diagnostics.count(DiagnosticKind::RegexMiss), 1);
+    assert_eq!(diagnostics.count(DiagnosticKind::BadHex), 1);
+    assert_eq!(diagnostics.count(DiagnosticKind::BadLength), 1);
+    assert_eq!(diagnostics.count(DiagnosticKind::SpecLookup), 0);
+    assert_eq!(parser.diagnostics.len(), 3);
+}
+
+/// Lines read from a file must also carry the file and the byte offset of the line.
+#[test]
+fn parse_file_reports_sources_and_offsets() {
+    let path = temp_path("diagnostics.log");
+    std::fs::write(&path, LINES.join("\n") + "\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let mut parser = parser();
+    let diagnostics = warning(parser.parse_file(path));
+    std::fs::remove_file(path).unwrap();
+    let offsets: Vec<(u64, Option<u64>)> = diagnostics
+        .entries
+        .iter()
+        .map(|d| (d.line_number, d.byte_offset))
+        .collect();
+    let offset = |line: usize| Some(LINES[..line].iter().map(|l| l.len() as u64 + 1).sum());
+    assert_eq!(
+        offsets,
+        vec![(2, offset(1)), (3, offset(2)), (4, offset(3))]
+    );
+    assert!(diagnostics
+        .entries
+        .iter()
+        .all(|d| d.source.as_deref() == Some(path)));
+}
+
+/// The summary must list the counts per kind and the diagnostics with their positions, and the
+/// kinds must serialize by name.
+#[test]
+fn diagnostics_display_and_serialize() {
+    let mut parser = parser();
+    let lines: Vec<String> = (0..12).map(|n| format!("bad line {}", n)).collect();
+    let diagnostics = warning(parser.parse_lines(&lines));
+
+    let summary = diagnostics.to_string();
+    assert!(summary.starts_with("12 line(s) could not be parsed:\n  regex_miss: 12\n"));
+    assert!(summary.contains("  line 1: regex_miss: "));
+    assert!(summary.contains(": bad line 9\n"));
+    assert!(!summary.contains(": bad line 10\n"));
+    assert!(summary.ends_with("  ... and 2 more\n"));
+
+    let json = serde_json::to_value(&diagnostics).unwrap();
+    assert_eq!(json["counts"]["regex_miss"], 12);
+    assert_eq!(json["entries"][0]["kind"], "regex_miss");
+    assert_eq!(json["entries"][0]["line_number"], 1);
+    let parsed: Diagnostics = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.count(DiagnosticKind::RegexMiss), 12);
+}
+
+/// The error and the parser must share the diagnostics, and only the first lines of each kind
+/// must be kept while every one is counted.
+#[test]
+fn diagnostics_are_shared_and_capped() {
+    let mut parser = parser();
+    let mut lines = vec!["not a frame".to_string(); MAX_DIAGNOSTICS_PER_KIND + 5];
+    lines.push(LINES[2].to_string());
+    let diagnostics = warning(parser.parse_lines(&lines));
+    assert!(Arc::ptr_eq(&diagnostics, &parser.diagnostics));
+
+    assert_eq!(diagnostics.len(), MAX_DIAGNOSTICS_PER_KIND + 6);
+    assert_eq!(
+        diagnostics.count(DiagnosticKind::RegexMiss),
+        MAX_DIAGNOSTICS_PER_KIND + 5
+    );
+    assert_eq!(diagnostics.entries.len(), MAX_DIAGNOSTICS_PER_KIND + 1);
+    assert_eq!(
+        diagnostics.entries.last().unwrap().kind,
+        DiagnosticKind::BadHex
+    );
+    assert!(diagnostics
+        .to_string()
+        .contains(&format!("... and {} more", MAX_DIAGNOSTICS_PER_KIND - 4)));
+
+    // Appended parses keep the cap across calls
+    parser.set_append(true);
+    warning(parser.parse_lines(&lines));
+    assert_eq!(parser.diagnostics.len(), 2 * (MAX_DIAGNOSTICS_PER_KIND + 6));
+    assert_eq!(
+        parser.diagnostics.entries.len(),
+        MAX_DIAGNOSTICS_PER_KIND + 2
+    );
+}
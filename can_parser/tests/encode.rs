@@ -0,0 +1,131 @@
+use can_parser::{
+    CANParser, ErrorPolicy, LogWriterFormat, TimestampFormat, TrafficConfig, LOG_FORMAT_ASC,
+    LOG_FORMAT_CANDUMP,
+};
+use std::collections::HashMap;
+
+mod common;
+use common::{eec1_annexes, temp_path};
+
+/// Returns a parser decoding with the EEC1 annex.
+fn parser_with_annex(error_handling: ErrorPolicy) -> CANParser {
+    CANParser::new(error_handling, None, Some(eec1_annexes())).unwrap()
+}
+
+/// Encoded frames must decode back to the encoded values, with the other bits not available.
+#[test]
+fn encode_j1939_round_trips() {
+    let mut parser = parser_with_annex(ErrorPolicy::Strict);
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+
+    let message = parser
+        .encode_j1939(61444, &HashMap::from([(190, 1500.0)]), 0x00, None, None)
+        .unwrap();
+    assert_eq!(message.id.id, 0x18F00400);
+    assert_eq!(message.id.sa, 0x00);
+    assert_eq!(message.data.len, 8);
+    assert_eq!(
+        &message.data.data[..8],
+        &[0xFF, 0xFF, 0xFF, 0xE0, 0x2E, 0xFF, 0xFF, 0xFF]
+    );
+    assert_eq!(message.data.spns[&190], 1500.0);
+
+    let parsed = parser
+        .parse_line("(1692896400.000000) can0 18F00400#FFFFFFE02EFFFFFF".to_string())
+        .unwrap();
+    assert_eq!(parsed.data.spns[&190], 1500.0);
+
+    let message = parser
+        .encode_j1939(61444, &HashMap::from([(512, -25.0)]), 0x21, None, Some(3))
+        .unwrap();
+    assert_eq!(message.id.id, 0x0CF00421);
+    assert_eq!(message.data.data[1], 100);
+
+    assert!(parser
+        .encode_j1939(61444, &HashMap::from([(190, -1.0)]), 0x00, None, None)
+        .is_err());
+    assert!(parser
+        .encode_j1939(61444, &HashMap::from([(84, 10.0)]), 0x00, None, None)
+        .is_err());
+}
+
+/// Generated logs must parse back to the generated frames, with periods defaulting to the
+/// transmission rate of the annex.
+#[test]
+fn generated_logs_read_back() {
+    const START: i64 = 1_692_896_400_000_000_000;
+    let config = TrafficConfig::from_json(&format!(
+        r#"{{"start": {}, "duration": 1, "messages": [
+            {{"pgn": 61444, "source_address": 0,
+              "signals": {{"190": {{"profile": "ramp", "start": 0, "end": 1000, "period": 1}}}}}},
+            {{"pgn": 61444, "source_address": 1, "period_ms": 100,
+              "signals": {{"512": {{"profile": "random_walk", "start": 0, "step": 5, "min": -10, "max": 10}}}}}}
+        ]}}"#,
+        START
+    ))
+    .unwrap();
+    let mut parser = parser_with_annex(ErrorPolicy::Warn);
+    let generated = parser.generate_traffic(&config).unwrap();
+    assert_eq!(generated.len(), 50 + 10);
+    assert_eq!(generated[0].id.sa, 0);
+    assert_eq!(generated[1].id.sa, 1);
+    assert!(generated.windows(2).all(|pair| pair[0].ts <= pair[1].ts));
+    // Unbounded traffic is rejected instead of generated until memory runs out
+    for duration in [f64::INFINITY, 1e9] {
+        let config = TrafficConfig {
+            duration,
+            ..config.clone()
+        };
+        assert!(parser.generate_traffic(&config).is_err());
+    }
+
+    let candump_path = temp_path("generated.log");
+    let candump = candump_path.to_str().unwrap();
+    parser
+        .generate_log(&config, candump, LogWriterFormat::Candump)
+        .unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    parser.parse_file(candump).unwrap();
+    let messages: Vec<_> = parser.iter_messages().map(|m| m.into_owned()).collect();
+    assert_eq!(messages.len(), generated.len());
+    let halfway = messages
+        .iter()
+        .find(|m| m.ts == START + 500_000_000 && m.id.sa == 0)
+        .unwrap();
+    assert_eq!(halfway.data.spns[&190], 500.0);
+    for (parsed, generated) in messages.iter().zip(&generated) {
+        assert_eq!(parsed.id, generated.id);
+        assert_eq!(parsed.data.data, generated.data.data);
+    }
+    std::fs::remove_file(&candump_path).unwrap();
+
+    let asc_path = temp_path("generated.asc");
+    let asc = asc_path.to_str().unwrap();
+    parser
+        .generate_log(&config, asc, LogWriterFormat::Asc)
+        .unwrap();
+    parser.set_log_format(LOG_FORMAT_ASC).unwrap();
+    parser.set_timestamp_format(TimestampFormat::Relative { start: START });
+    assert!(parser.parse_file(asc).is_ok());
+    let messages: Vec<_> = parser.iter_messages().map(|m| m.into_owned()).collect();
+    assert_eq!(messages.len(), generated.len());
+    for (parsed, generated) in messages.iter().zip(&generated) {
+        assert_eq!(parsed.ts, generated.ts);
+        assert_eq!(parsed.id, generated.id);
+        assert_eq!(parsed.data.data, generated.data.data);
+    }
+    std::fs::remove_file(&asc_path).unwrap();
+
+    let blf = temp_path("generated.blf");
+    parser
+        .generate_log(&config, blf.to_str().unwrap(), LogWriterFormat::Blf)
+        .unwrap();
+    let bytes = std::fs::read(&blf).unwrap();
+    assert_eq!(&bytes[..4], b"LOGG");
+    assert_eq!(
+        u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+        bytes.len() as u64
+    );
+    assert_eq!(u32::from_le_bytes(bytes[32..36].try_into().unwrap()), 60);
+    std::fs::remove_file(&blf).unwrap();
+}
@@ -0,0 +1,75 @@
+use can_parser::{CANErrorFrame, ErrorPolicy};
+
+mod common;
+use common::candump_parsers;
+
+/// Error frames must be flagged and have their error classes and details decoded.
+#[test]
+fn error_frames_are_decoded() {
+    for parser in candump_parsers(ErrorPolicy::Strict) {
+        // Controller, protocol and counter errors
+        let message = parser
+            .parse_line("(1692896400.000000) can0 2000020C#0024040A00008010".to_string())
+            .unwrap();
+        assert!(message.id.flags.err);
+        assert!(!message.id.flags.rtr);
+        assert!(message.data.spns.is_empty());
+        let error = message.error.unwrap();
+        assert_eq!(error.class, 0x20C);
+        assert!(error.controller && error.protocol_violation);
+        assert!(!error.bus_off && !error.no_ack);
+        assert_eq!(error.controller_status, vec!["rx_warning", "tx_passive"]);
+        assert_eq!(error.protocol_types, vec!["stuff"]);
+        assert_eq!(error.protocol_location, "data");
+        assert_eq!(error.tx_error_count, Some(0x80));
+        assert_eq!(error.rx_error_count, Some(0x10));
+
+        // Bus-off without data
+        let message = parser
+            .parse_line("(1692896400.000100) can0 20000040#".to_string())
+            .unwrap();
+        let error = message.error.unwrap();
+        assert!(error.bus_off);
+        assert_eq!(error.tx_error_count, None);
+
+        let message = parser
+            .parse_line("(1692896400.000200) can0 123#1122".to_string())
+            .unwrap();
+        assert!(!message.id.flags.err);
+        assert!(message.error.is_none());
+    }
+}
+
+/// Missing data bytes of an error frame must be read as zero and unknown locations kept in hex.
+#[test]
+fn error_frame_details_default_to_zero() {
+    let error = CANErrorFrame::from_frame(0x0A, &[0x07]);
+    assert!(error.lost_arbitration && error.protocol_violation);
+    assert_eq!(error.lost_arbitration_bit, 7);
+    assert!(error.protocol_types.is_empty());
+    assert_eq!(error.protocol_location, "unspecified");
+
+    let error = CANErrorFrame::from_frame(0x08, &[0, 0, 0x01, 0x1F]);
+    assert_eq!(error.protocol_types, vec!["bit"]);
+    assert_eq!(error.protocol_location, "0x1F");
+}
+
+/// Remote frames must be flagged, keep their DLC and carry no data.
+#[test]
+fn remote_frames_are_recognized() {
+    for parser in candump_parsers(ErrorPolicy::Strict) {
+        let message = parser
+            .parse_line("(1692896400.000000) can0 123#R".to_string())
+            .unwrap();
+        assert!(message.id.flags.rtr);
+        assert_eq!(message.data.len, 0);
+
+        let message = parser
+            .parse_line("(1692896400.000100) can0 18FEF100#R5".to_string())
+            .unwrap();
+        assert!(message.id.flags.rtr && message.id.flags.ext);
+        assert_eq!(message.data.len, 5);
+        assert!(message.data.data.iter().all(|&b| b == 0));
+        assert!(message.error.is_none());
+    }
+}
@@ -0,0 +1,97 @@
+use can_parser::{
+    CANParser, CANParserError, ErrorPolicy, LogFormat, ERROR_IGNORE, ERROR_WARN, ERROR_WARN_UP_TO,
+};
+
+/// Returns candump lines where every other line cannot be parsed.
+fn lines() -> Vec<String> {
+    (0..8)
+        .map(|n| match n % 2 {
+            0 => format!("(1692896400.{:06}) can0 123#11", n),
+            _ => format!("bad line {}", n),
+        })
+        .collect()
+}
+
+/// Returns a parser splitting candump lines with the given policy.
+fn parser(error_handling: ErrorPolicy) -> Result<CANParser, CANParserError> {
+    CANParser::new(
+        error_handling,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+}
+
+/// Policies must be created from their names, case insensitively, and "warn_up_to" needs a limit.
+#[test]
+fn policies_from_names() {
+    assert_eq!(
+        ErrorPolicy::from_name(ERROR_IGNORE, None).unwrap(),
+        ErrorPolicy::Ignore
+    );
+    assert_eq!(
+        ErrorPolicy::from_name(ERROR_WARN, Some(3)).unwrap(),
+        ErrorPolicy::Warn
+    );
+    assert_eq!(
+        ErrorPolicy::from_name("Strict", None).unwrap(),
+        ErrorPolicy::Strict
+    );
+    assert_eq!(
+        ErrorPolicy::from_name(ERROR_WARN_UP_TO, Some(3)).unwrap(),
+        ErrorPolicy::WarnUpTo(3)
+    );
+    assert!(ErrorPolicy::from_name(ERROR_WARN_UP_TO, None).is_err());
+    assert!(ErrorPolicy::from_name("panic", None).is_err());
+    assert_eq!(ErrorPolicy::default(), ErrorPolicy::Warn);
+}
+
+/// A limit of zero must be rejected by the parser, since it is `Strict` under another name.
+#[test]
+fn policies_are_validated() {
+    assert!(ErrorPolicy::WarnUpTo(0).validate().is_err());
+    assert!(ErrorPolicy::WarnUpTo(1).validate().is_ok());
+    assert!(matches!(
+        parser(ErrorPolicy::WarnUpTo(0)),
+        Err(CANParserError::ParserError(_))
+    ));
+    assert_eq!(ErrorPolicy::Warn.limit(), None);
+    assert_eq!(ErrorPolicy::Strict.limit(), Some(0));
+    assert_eq!(ErrorPolicy::WarnUpTo(2).limit(), Some(2));
+}
+
+/// `Ignore` must skip bad lines silently and `Warn` must report every one of them.
+#[test]
+fn ignore_and_warn_keep_parsing() {
+    let mut parser_ignore = parser(ErrorPolicy::Ignore).unwrap();
+    parser_ignore.parse_lines(&lines()).unwrap();
+    assert_eq!(parser_ignore.messages.len(), 4);
+    assert!(parser_ignore.diagnostics.is_empty());
+
+    let mut parser_warn = parser(ErrorPolicy::Warn).unwrap();
+    match parser_warn.parse_lines(&lines()) {
+        Err(CANParserError::ParserWarning(diagnostics)) => assert_eq!(diagnostics.len(), 4),
+        other => panic!("Expected a warning, got {:?}", other),
+    }
+    assert_eq!(parser_warn.messages.len(), 4);
+}
+
+/// `Strict` must stop at the first bad line and `WarnUpTo` once its limit is exceeded.
+#[test]
+fn strict_and_warn_up_to_abort() {
+    for (error_handling, recorded) in [(ErrorPolicy::Strict, 1), (ErrorPolicy::WarnUpTo(2), 3)] {
+        let mut parser = parser(error_handling).unwrap();
+        match parser.parse_lines(&lines()) {
+            Err(CANParserError::ParseAborted(diagnostics)) => {
+                assert_eq!(diagnostics.len(), recorded)
+            }
+            other => panic!("Expected an abort, got {:?}", other),
+        }
+        assert_eq!(parser.diagnostics.len(), recorded);
+    }
+
+    let mut parser = parser(ErrorPolicy::WarnUpTo(4)).unwrap();
+    assert!(matches!(
+        parser.parse_lines(&lines()),
+        Err(CANParserError::ParserWarning(_))
+    ));
+}
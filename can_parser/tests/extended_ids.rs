@@ -0,0 +1,70 @@
+use can_parser::{
+    parse_id, CANMessage, CANParser, CANParserError, DiagnosticKind, ErrorPolicy, LogFormat,
+};
+
+/// Returns whether `id` is parsed as extended and its ID without flags.
+fn parse(id: &str, extended: Option<bool>) -> Result<(bool, u32), String> {
+    let mut can_id = CANMessage::default().id;
+    parse_id(id, &mut can_id, extended)?;
+    Ok((can_id.flags.ext, can_id.id))
+}
+
+/// Extended IDs must be told from standard IDs by how they are written, not by their value.
+#[test]
+fn extended_ids_are_detected_from_the_log() {
+    assert_eq!(parse("123", None), Ok((false, 0x123)));
+    assert_eq!(parse("7FF", None), Ok((false, 0x7FF)));
+    // Written with 8 digits
+    assert_eq!(parse("00000123", None), Ok((true, 0x123)));
+    // Too large for 11 bits
+    assert_eq!(parse("800", None), Ok((true, 0x800)));
+    // With the CAN_EFF_FLAG set
+    assert_eq!(parse("80000123", None), Ok((true, 0x123)));
+
+    // Overridden
+    assert_eq!(parse("123", Some(true)), Ok((true, 0x123)));
+    assert_eq!(parse("00000123", Some(false)), Ok((false, 0x123)));
+    assert!(parse("18FEF100", Some(false)).is_err());
+    assert!(parse("12G", None).is_err());
+}
+
+/// Parsers must detect extended IDs from the ID digits and the ASC `x` suffix, and report bad IDs.
+#[test]
+fn parsers_report_bad_ids() {
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let message = parser
+        .parse_line("(1692896400.000000) can0 00000123#11".to_string())
+        .unwrap();
+    assert!(message.id.flags.ext);
+
+    parser.set_extended_ids(Some(false));
+    let message = parser
+        .parse_line("(1692896400.000000) can0 00000123#11".to_string())
+        .unwrap();
+    assert!(!message.id.flags.ext);
+    let lines = vec![
+        "(1692896400.000000) can0 123#11".to_string(),
+        "(1692896400.000100) can0 18FEF100#11".to_string(),
+    ];
+    match parser.parse_lines(&lines) {
+        Err(CANParserError::ParserWarning(diagnostics)) => {
+            assert_eq!(diagnostics.count(DiagnosticKind::BadId), 1);
+            assert_eq!(diagnostics.entries[0].line_number, 2);
+        }
+        other => panic!("Expected a warning, got {:?}", other),
+    }
+    assert_eq!(parser.messages.len(), 1);
+
+    // An `ext` group marks extended IDs, like the `x` suffix of Vector ASC logs
+    let line_regex = r"^(?P<id>[0-9A-F]+)(?P<ext>x)?#(?P<data>[0-9A-F]*)".to_string();
+    let parser = CANParser::new(ErrorPolicy::Strict, Some(line_regex), None).unwrap();
+    let message = parser.parse_line("123x#11".to_string()).unwrap();
+    assert!(message.id.flags.ext);
+    let message = parser.parse_line("123#11".to_string()).unwrap();
+    assert!(!message.id.flags.ext);
+}
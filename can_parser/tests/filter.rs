@@ -0,0 +1,144 @@
+use can_parser::{CANParser, CANParserError, ErrorPolicy, IdFilter, LogFile, MessageFilter};
+
+mod common;
+use common::temp_path;
+
+const LINES: [&str; 5] = [
+    "(1692896400.000000) can0 123#1122",
+    "(1692896400.000100) can0 18FEF100#0011223344556677",
+    "(1692896400.000200) can1 18FEF121#0011223344556677",
+    "(1692896400.000300) can0 0CF00400#FFFFFFE02EFFFFFF",
+    "(1692896400.000400) can0 18EA2100#00EE00",
+];
+
+/// Returns the IDs of the messages parsed from `LINES` with `filter`.
+fn parse_ids(filter: MessageFilter) -> Vec<u32> {
+    let lines: Vec<String> = LINES.iter().map(|line| line.to_string()).collect();
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    parser.set_filter(Some(filter));
+    // Filtered lines are not diagnostics, so a strict parse succeeds
+    parser.parse_lines(&lines).unwrap();
+    parser
+        .iter_messages()
+        .map(|message| message.id.id)
+        .collect()
+}
+
+#[test]
+fn filter_drops_messages_before_decoding() {
+    assert_eq!(parse_ids(MessageFilter::default()).len(), LINES.len());
+
+    let ids = vec!["123:7FF".parse::<IdFilter>().unwrap()];
+    assert_eq!(
+        parse_ids(MessageFilter {
+            ids: Some(ids),
+            ..Default::default()
+        }),
+        vec![0x123]
+    );
+    let standard = IdFilter {
+        id: 0,
+        mask: 0x8000_0000,
+        inverted: false,
+    };
+    assert_eq!(
+        parse_ids(MessageFilter {
+            ids: Some(vec![standard]),
+            ..Default::default()
+        }),
+        vec![0x123]
+    );
+    let ids = vec!["18FEF100~1FFFFF00".parse::<IdFilter>().unwrap()];
+    assert_eq!(
+        parse_ids(MessageFilter {
+            ids: Some(ids),
+            ..Default::default()
+        }),
+        vec![0x123, 0x0CF00400, 0x18EA2100]
+    );
+    assert!("18FEF100".parse::<IdFilter>().is_err());
+
+    assert_eq!(
+        parse_ids(MessageFilter {
+            pgns: Some(vec![65265]),
+            ..Default::default()
+        }),
+        vec![0x18FEF100, 0x18FEF121]
+    );
+    assert_eq!(
+        parse_ids(MessageFilter {
+            source_addresses: Some(vec![0x00]),
+            destination_addresses: Some(vec![0x21]),
+            ..Default::default()
+        }),
+        vec![0x18EA2100]
+    );
+    assert_eq!(
+        parse_ids(MessageFilter {
+            start: Some(1_692_896_400_000_100_000),
+            end: Some(1_692_896_400_000_300_000),
+            ..Default::default()
+        }),
+        vec![0x18FEF100, 0x18FEF121]
+    );
+    assert_eq!(
+        parse_ids(MessageFilter {
+            channels: Some(vec!["can1".to_string()]),
+            ..Default::default()
+        }),
+        vec![0x18FEF121]
+    );
+}
+
+/// `parse_line` must tell a filtered line apart from a line that fails to parse.
+#[test]
+fn parse_line_reports_filtered_lines_as_skipped() {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    parser.set_filter(Some(MessageFilter {
+        pgns: Some(vec![65265]),
+        ..Default::default()
+    }));
+    assert!(parser.parse_line(LINES[1].to_string()).is_ok());
+    assert!(matches!(
+        parser.parse_line(LINES[0].to_string()),
+        Err(CANParserError::LineSkipped(_))
+    ));
+    assert!(matches!(
+        parser.parse_line("(1692896400.000500) can0 12G#00".to_string()),
+        Err(CANParserError::ParserError(_))
+    ));
+}
+
+/// Time offsets that move the timestamps of a file out of range must fail the parse instead of
+/// overflowing, while filter bounds they move out of range select none of its messages.
+#[test]
+fn out_of_range_time_offsets_are_reported() {
+    let path = temp_path("filter_time_offsets.log");
+    std::fs::write(&path, LINES.join("\n")).unwrap();
+    let files = |time_offset| {
+        [LogFile {
+            path: path.to_str().unwrap().to_string(),
+            channel: None,
+            time_offset,
+        }]
+    };
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    assert!(matches!(
+        parser.parse_files(&files(i64::MAX)),
+        Err(CANParserError::ParserError(_))
+    ));
+
+    parser.set_filter(Some(MessageFilter {
+        start: Some(0),
+        end: Some(i64::MAX),
+        ..Default::default()
+    }));
+    for time_offset in [i64::MAX, i64::MIN] {
+        parser.parse_files(&files(time_offset)).unwrap();
+        assert_eq!(parser.iter_messages().count(), 0);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
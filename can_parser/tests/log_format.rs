@@ -0,0 +1,161 @@
+use can_parser::{CANParser, CANParserError, ErrorPolicy, LogFormat};
+
+/// The hand-written candump parser must decode lines as the candump regex does.
+#[test]
+fn candump_parser_matches_regex() {
+    let template = LogFormat::Candump.regex();
+    let fast = CANParser::new(ErrorPolicy::Warn, Some(template.to_string()), None).unwrap();
+    assert_eq!(fast.log_format(), Some(LogFormat::Candump));
+    // An equivalent regex that is not the template, so the line regex is used.
+    let slow = CANParser::new(ErrorPolicy::Warn, Some(format!("(?:){}", template)), None).unwrap();
+    assert_eq!(slow.log_format(), None);
+
+    for line in [
+        "(1692896400.000000) can0 123#1122",
+        "(1692896400.000100) can1 18FEF100#0011223344556677 R",
+        "(1692896400.000200) can0 456#R",
+        "(1692896400.000300) can0 456#R5 T",
+        "(1692896400.000400) can0 18FEF100##30011223344556677",
+        "(1692896400.000500) 123#",
+    ] {
+        assert_eq!(
+            serde_json::to_value(fast.parse_line(line.to_string()).unwrap()).unwrap(),
+            serde_json::to_value(slow.parse_line(line.to_string()).unwrap()).unwrap(),
+            "{}",
+            line
+        );
+    }
+    assert!(fast
+        .parse_line("(1692896400.000600) can0 123#11G2".to_string())
+        .is_err());
+    assert!(fast.parse_line("not a frame".to_string()).is_err());
+}
+
+#[test]
+fn asc_and_trc_parsers() {
+    let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+
+    parser.set_log_format("asc").unwrap();
+    let message = parser
+        .parse_line("   1.234500 1  18FEF100x       Rx   d 8 00 11 22 33 44 55 66 77  Length = 0 BitCount = 0".to_string())
+        .unwrap();
+    assert_eq!(message.ts, 1_234_500_000);
+    assert_eq!(message.channel.as_deref(), Some("1"));
+    assert!(message.id.flags.ext);
+    assert_eq!(message.id.pgn, 0xFEF1);
+    assert_eq!(
+        &message.data.data[..8],
+        &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]
+    );
+    let message = parser
+        .parse_line("   2.000000 CANFD   2 Tx   123  EngineData  1 0 9 12 00 11 22 33 44 55 66 77 88 99 AA BB".to_string())
+        .unwrap();
+    assert!(message.id.flags.fd && message.id.flags.brs && !message.id.flags.esi);
+    assert!(!message.id.flags.ext);
+    assert_eq!(message.data.len, 12);
+    assert!(matches!(
+        parser.parse_line("date Thu Aug 24 05:00:00.000 pm 2023".to_string()),
+        Err(CANParserError::LineSkipped(_))
+    ));
+
+    parser.set_log_format("trc").unwrap();
+    let message = parser
+        .parse_line("     1)      1059.9  Rx         0300  2  11 22".to_string())
+        .unwrap();
+    assert_eq!(message.ts, 1_059_900_000);
+    assert_eq!(&message.data.data[..2], &[0x11, 0x22]);
+    let message = parser
+        .parse_line("      2      1060.125 DT 1      18FEF100 Rx -  3    AA BB CC".to_string())
+        .unwrap();
+    assert_eq!(message.ts, 1_060_125_000);
+    assert_eq!(message.channel.as_deref(), Some("1"));
+    assert!(message.id.flags.ext);
+    assert_eq!(message.data.len, 3);
+    let message = parser
+        .parse_line("      3      1061.000 RR 1      0300 Rx -  4".to_string())
+        .unwrap();
+    assert!(message.id.flags.rtr);
+    assert_eq!(message.data.len, 4);
+}
+
+/// The header, comment and trailer lines of ASC and TRC logs must be skipped without a diagnostic,
+/// while other lines that are not frames are still reported.
+#[test]
+fn asc_and_trc_headers_are_skipped() {
+    for (format, lines) in [
+        (
+            "asc",
+            vec![
+                "date Thu Aug 24 05:00:00.000 pm 2023",
+                "base hex  timestamps absolute",
+                "internal events logged",
+                "// version 9.0.0",
+                "Begin Triggerblock Thu Aug 24 05:00:00.000 pm 2023",
+                "   0.000000 Start of measurement",
+                "",
+                "   1.300000 2  123             Tx   d 2 AA BB",
+                "End TriggerBlock",
+            ],
+        ),
+        (
+            "trc",
+            vec![
+                ";$FILEVERSION=2.1",
+                ";$STARTTIME=45162.7083333333",
+                ";   Message   Time    Type ID     Rx/Tx",
+                ";---+-- ------+------ +- --+----- +- +- +- -- -- --",
+                "      2      1060.125 DT 1      18FEF100 Rx -  3    AA BB CC",
+            ],
+        ),
+    ] {
+        let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+        parser.set_log_format(format).unwrap();
+        let lines: Vec<String> = lines.into_iter().map(String::from).collect();
+        parser.parse_lines(&lines).unwrap();
+        assert_eq!(parser.messages.len(), 1, "{}", format);
+        assert!(parser
+            .parse_lines(&vec!["   1.000000 1 Statistic: D 0 R 0".to_string()])
+            .is_err());
+    }
+}
+
+/// The hand-written ASC and TRC parsers must decode classic frames as their regexes do.
+#[test]
+fn asc_and_trc_parsers_match_regex() {
+    for (format, lines) in [
+        (
+            LogFormat::Asc,
+            vec![
+                "   1.234500 1  18FEF100x       Rx   d 8 00 11 22 33 44 55 66 77  Length = 0",
+                "   1.300000 2  123             Tx   d 2 AA BB",
+                "   1.400000 1  456             Rx   r 4",
+            ],
+        ),
+        (
+            LogFormat::Trc,
+            vec![
+                "      2      1060.125 DT 1      18FEF100 Rx -  3    AA BB CC",
+                "      3      1061.500 DT        0300 Tx 2    11 22",
+            ],
+        ),
+    ] {
+        let fast =
+            CANParser::new(ErrorPolicy::Warn, Some(format.regex().to_string()), None).unwrap();
+        assert_eq!(fast.log_format(), Some(format));
+        let slow = CANParser::new(
+            ErrorPolicy::Warn,
+            Some(format!("(?:){}", format.regex())),
+            None,
+        )
+        .unwrap();
+        assert_eq!(slow.log_format(), None);
+        for line in lines {
+            assert_eq!(
+                serde_json::to_value(fast.parse_line(line.to_string()).unwrap()).unwrap(),
+                serde_json::to_value(slow.parse_line(line.to_string()).unwrap()).unwrap(),
+                "{}",
+                line
+            );
+        }
+    }
+}
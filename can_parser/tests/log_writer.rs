@@ -0,0 +1,185 @@
+use can_parser::{CANParser, CANParserError, ErrorPolicy, LogSink, LogWriterFormat, MessageFilter};
+use std::io::Cursor;
+
+const LINES: [&str; 6] = [
+    "(1692896400.000000) can0 123#1122",
+    "(1692896400.000100) can1 18FEF100#0011223344556677 R",
+    "(1692896400.000200) can0 456#R",
+    "(1692896400.000300) can0 456#R5 T",
+    "(1692896400.000400) can0 18FEF100##30011223344556677",
+    "(1692896400.000500) can1 18FEF100#8899AABBCCDDEEFF T",
+];
+
+/// Written candump logs must reproduce the parsed lines, and selections must narrow them.
+#[test]
+fn candump_log_round_trips() {
+    let lines: Vec<String> = LINES.iter().map(|line| line.to_string()).collect();
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    parser.parse_lines(&lines).unwrap();
+
+    let log = parser
+        .write_log(None, LogWriterFormat::Candump, &MessageFilter::default())
+        .unwrap()
+        .unwrap();
+    assert_eq!(String::from_utf8(log).unwrap(), LINES.join("\n") + "\n");
+
+    let selection = MessageFilter {
+        pgns: Some(vec![65265]),
+        start: Some(1_692_896_400_000_100_000),
+        end: Some(1_692_896_400_000_500_000),
+        ..Default::default()
+    };
+    let log = parser
+        .write_log(None, LogWriterFormat::Candump, &selection)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(log).unwrap(),
+        format!("{}\n{}\n", LINES[1], LINES[4])
+    );
+
+    let selection = MessageFilter {
+        channels: Some(vec!["can1".to_string()]),
+        ..Default::default()
+    };
+    let log = parser
+        .write_log(None, LogWriterFormat::Candump, &selection)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(log).unwrap(),
+        format!("{}\n{}\n", LINES[1], LINES[5])
+    );
+
+    // A sink converts a log while it is parsed
+    let mut sink = LogSink::new(Cursor::new(Vec::new()), LogWriterFormat::Asc).unwrap();
+    parser.parse_lines_into(&lines, &mut sink).unwrap();
+    let asc = String::from_utf8(sink.into_inner().into_inner()).unwrap();
+    assert!(asc.contains("0.000100 2  18FEF100x       Rx   d 8 00 11 22 33 44 55 66 77"));
+    assert!(asc.trim_end().ends_with("End TriggerBlock"));
+}
+
+const BLF_LINES: [&str; 3] = [
+    "(1692896400.000000) can0 123#1122",
+    "(1692896400.000100) can1 18FEF100##30011223344556677",
+    "(1692896400.000200) can0 2000020C#0024040A00008010",
+];
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Splits a run of BLF objects into their types, headers and bodies, skipping the padding after
+/// each object.
+fn blf_objects(bytes: &[u8]) -> Vec<(u32, &[u8], &[u8])> {
+    let mut objects = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        assert_eq!(&bytes[offset..offset + 4], b"LOBJ");
+        let size = u32_at(bytes, offset + 8) as usize;
+        objects.push((
+            u32_at(bytes, offset + 12),
+            &bytes[offset..offset + 32],
+            &bytes[offset + 32..offset + size],
+        ));
+        offset += size + size % 4;
+    }
+    assert_eq!(offset, bytes.len());
+    objects
+}
+
+/// Written BLF logs must hold a container of CAN, CAN FD and error objects timed from the first
+/// message.
+#[test]
+fn blf_log_holds_each_object_type() {
+    let lines: Vec<String> = BLF_LINES.iter().map(|line| line.to_string()).collect();
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    parser.parse_lines(&lines).unwrap();
+    let log = parser
+        .write_log(None, LogWriterFormat::Blf, &MessageFilter::default())
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(&log[..4], b"LOGG");
+    assert_eq!(u32_at(&log, 4), 144);
+    assert_eq!(u64_at(&log, 16), log.len() as u64);
+    assert_eq!(u32_at(&log, 32), 3);
+
+    let containers = blf_objects(&log[144..]);
+    assert_eq!(containers.len(), 1);
+    let (container_type, header, container) = containers[0];
+    assert_eq!(container_type, 10);
+    // Uncompressed, then the size of the objects
+    assert_eq!(u16_at(header, 16), 0);
+    assert_eq!(u32_at(header, 24) as usize, container.len());
+
+    let objects = blf_objects(container);
+    let types: Vec<u32> = objects
+        .iter()
+        .map(|(object_type, _, _)| *object_type)
+        .collect();
+    assert_eq!(types, vec![1, 100, 73]);
+    let times: Vec<u64> = objects
+        .iter()
+        .map(|(_, header, _)| u64_at(header, 24))
+        .collect();
+    assert_eq!(times, vec![0, 100_000, 200_000]);
+    for (_, header, _) in &objects {
+        // Nanosecond timestamps
+        assert_eq!(u32_at(header, 16), 2);
+    }
+
+    let can = objects[0].2;
+    assert_eq!(u16_at(can, 0), 1);
+    assert_eq!(can[3], 2);
+    assert_eq!(u32_at(can, 4), 0x123);
+    assert_eq!(&can[8..16], &[0x11, 0x22, 0, 0, 0, 0, 0, 0]);
+
+    let fd = objects[1].2;
+    assert_eq!(u16_at(fd, 0), 2);
+    assert_eq!(u32_at(fd, 4), 0x98FEF100);
+    // EDL, BRS and ESI, then the payload length
+    assert_eq!(fd[13], 0x07);
+    assert_eq!(fd[14], 8);
+    assert_eq!(
+        &fd[20..28],
+        &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]
+    );
+    assert!(fd[28..].iter().all(|byte| *byte == 0));
+
+    let error = objects[2].2;
+    assert_eq!(u16_at(error, 0), 1);
+    assert_eq!(error[10], 8);
+    assert_eq!(u32_at(error, 16), 0x2000020C);
+    assert_eq!(
+        &error[24..32],
+        &[0x00, 0x24, 0x04, 0x0A, 0x00, 0x00, 0x80, 0x10]
+    );
+}
+
+/// BLF logs must reject messages older than the first one rather than misplace them.
+#[test]
+fn blf_log_rejects_unsorted_messages() {
+    let lines: Vec<String> = BLF_LINES
+        .iter()
+        .rev()
+        .map(|line| line.to_string())
+        .collect();
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    parser.set_log_format("candump").unwrap();
+    parser.parse_lines(&lines).unwrap();
+    assert!(matches!(
+        parser.write_log(None, LogWriterFormat::Blf, &MessageFilter::default()),
+        Err(CANParserError::ParserError(_))
+    ));
+}
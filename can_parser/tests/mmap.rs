@@ -0,0 +1,64 @@
+#![cfg(feature = "mmap")]
+
+use can_parser::{CANParser, CANParserError, ErrorPolicy, LogFormat};
+use std::io::Write;
+
+mod common;
+use common::temp_path;
+
+/// A memory-mapped parse spanning several chunks must give the same messages and diagnostics as
+/// `parse_file`.
+#[test]
+fn parse_file_mmap_matches_parse_file() {
+    let path = temp_path("mmap.log");
+    let mut file = std::fs::File::create(&path).unwrap();
+    for i in 0..300_000u32 {
+        match i {
+            100_000 => writeln!(file, "not a frame").unwrap(),
+            200_000 => write!(file, "(1692896600.000000) can0 123#11\r\n").unwrap(),
+            _ => writeln!(
+                file,
+                "({}.{:06}) can0 {:08X}#{:016X}",
+                1_692_896_400 + i / 1000,
+                i % 1000,
+                0x18FEF100 + (i % 256),
+                i
+            )
+            .unwrap(),
+        }
+    }
+    drop(file);
+    let path = path.to_str().unwrap();
+
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let expected = parser.parse_file(path);
+    let expected_messages = serde_json::to_value(&parser.messages).unwrap();
+    let mapped = parser.parse_file_mmap(path);
+    std::fs::remove_file(path).unwrap();
+
+    match (expected, mapped) {
+        (
+            Err(CANParserError::ParserWarning(expected)),
+            Err(CANParserError::ParserWarning(mapped)),
+        ) => {
+            assert_eq!(mapped.len(), 1);
+            assert_eq!(mapped.entries[0].line_number, 100_001);
+            assert_eq!(
+                mapped.entries[0].line_number,
+                expected.entries[0].line_number
+            );
+            assert_eq!(
+                mapped.entries[0].byte_offset,
+                expected.entries[0].byte_offset
+            );
+        }
+        results => panic!("unexpected results {:?}", results),
+    }
+    assert_eq!(parser.messages.len(), 299_999);
+    assert!(serde_json::to_value(&parser.messages).unwrap() == expected_messages);
+}
@@ -0,0 +1,51 @@
+use can_parser::{CANParser, ErrorPolicy, LogFormat};
+use std::io::Write;
+
+mod common;
+use common::temp_path;
+
+/// Parsing a file must give the same messages, in the same order, as parsing its lines one by
+/// one. Run with and without the `parallel` feature to check both builds.
+#[test]
+fn parse_file_preserves_line_order() {
+    let lines: Vec<String> = (0..200_000u32)
+        .map(|i| {
+            format!(
+                "({}.{:06}) can{} {:08X}#{:016X}",
+                1_692_896_400 + i / 1000,
+                i % 1000,
+                i % 2,
+                0x18FEF100 + (i % 256),
+                i
+            )
+        })
+        .collect();
+    let path = temp_path("ordering.log");
+    let mut file = std::fs::File::create(&path).unwrap();
+    for line in &lines {
+        writeln!(file, "{}", line).unwrap();
+    }
+    drop(file);
+
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let result = parser.parse_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    let expected: Vec<serde_json::Value> = lines
+        .iter()
+        .map(|line| serde_json::to_value(parser.parse_line(line.clone()).unwrap()).unwrap())
+        .collect();
+    let actual: Vec<serde_json::Value> = parser
+        .messages
+        .iter()
+        .map(|message| serde_json::to_value(message).unwrap())
+        .collect();
+    assert_eq!(actual.len(), expected.len());
+    assert!(actual == expected, "parsed messages are out of order");
+}
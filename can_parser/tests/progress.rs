@@ -0,0 +1,106 @@
+use can_parser::{CANParser, CANParserError, CancellationToken, ErrorPolicy, LogFormat, Progress};
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::temp_path;
+
+/// Returns `count` candump lines.
+fn lines(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|n| format!("(1692896400.{:06}) can0 123#11", n % 1_000_000))
+        .collect()
+}
+
+/// Returns a candump parser recording every progress report into the returned list.
+fn parser() -> (CANParser, Arc<Mutex<Vec<Progress>>>) {
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let recorded = reports.clone();
+    parser.set_progress_callback(Some(Arc::new(move |progress: &Progress| {
+        recorded.lock().unwrap().push(*progress);
+        ControlFlow::Continue(())
+    })));
+    (parser, reports)
+}
+
+/// Progress must be reported while parsing and once more, complete, when the parse ends.
+#[test]
+fn parse_lines_reports_progress() {
+    let (mut parser, reports) = parser();
+    let lines = lines(100_000);
+    parser.parse_lines(&lines).unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert!(reports.len() > 1);
+    assert!(reports
+        .windows(2)
+        .all(|pair| pair[0].lines_read <= pair[1].lines_read));
+    let last = reports.last().unwrap();
+    assert_eq!(last.lines_read, 100_000);
+    assert_eq!(last.total_lines, Some(100_000));
+    assert_eq!(
+        last.bytes_read,
+        lines.iter().map(|line| line.len() as u64 + 1).sum::<u64>()
+    );
+    assert_eq!(last.messages, 100_000);
+    assert_eq!(last.fraction, Some(1.0));
+    assert_eq!(last.total_bytes, None);
+}
+
+/// The progress of a file must count its bytes against the size of the file.
+#[test]
+fn parse_file_reports_bytes() {
+    let path = temp_path("progress.log");
+    std::fs::write(&path, lines(1000).join("\n") + "\n").unwrap();
+    let size = std::fs::metadata(&path).unwrap().len();
+
+    let (mut parser, reports) = parser();
+    parser.parse_file(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let last = *reports.lock().unwrap().last().unwrap();
+    assert_eq!(last.bytes_read, size);
+    assert_eq!(last.total_bytes, Some(size));
+    assert_eq!(last.lines_read, 1000);
+    assert_eq!(last.fraction, Some(1.0));
+}
+
+/// Breaking from the callback must stop the parse, keeping the messages decoded so far.
+#[test]
+fn callback_cancels_the_parse() {
+    let (mut parser, _) = parser();
+    parser.set_progress_callback(Some(Arc::new(|_: &Progress| ControlFlow::Break(()))));
+    let lines = lines(200_000);
+    match parser.parse_lines(&lines) {
+        Err(CANParserError::ParseCancelled(diagnostics)) => assert!(diagnostics.is_empty()),
+        other => panic!("Expected a cancellation, got {:?}", other),
+    }
+    assert!(!parser.messages.is_empty());
+    assert!(parser.messages.len() < lines.len());
+}
+
+/// A cancelled token must stop every parse until a new token is given.
+#[test]
+fn token_cancels_the_parse() {
+    let (mut parser, reports) = parser();
+    parser.cancellation_token().cancel();
+    assert!(matches!(
+        parser.parse_lines(&lines(10)),
+        Err(CANParserError::ParseCancelled(_))
+    ));
+    assert!(parser.messages.is_empty());
+    assert!(reports.lock().unwrap().is_empty());
+    assert_eq!(
+        CANParserError::ParseCancelled(Default::default()).to_string(),
+        "Parsing Cancelled"
+    );
+
+    parser.set_cancellation_token(CancellationToken::new());
+    parser.parse_lines(&lines(10)).unwrap();
+    assert_eq!(parser.messages.len(), 10);
+}
@@ -0,0 +1,114 @@
+use can_parser::{CANParser, ErrorPolicy, Resampling, LOG_FORMAT_CANDUMP, LONG_CSV_COLUMNS};
+
+mod common;
+use common::eec1_annexes;
+
+/// Returns a parser with three EEC1 frames of 1500, 1600 and 1700 rpm at 0, 50 and 250 ms.
+fn parser() -> CANParser {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, Some(eec1_annexes())).unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    let lines: Vec<String> = [
+        "(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF",
+        "(1692896400.050000) can0 18F00400#FF64FF0032FFFFFF",
+        "(1692896400.250000) can0 18F00400#FF64FF2035FFFFFF",
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect();
+    parser.parse_lines(&lines).unwrap();
+    parser
+}
+
+/// Returns the timestamp and engine speed columns of a wide CSV.
+fn engine_speeds(csv: &str) -> Vec<(String, String)> {
+    csv.lines()
+        .map(|line| {
+            let mut fields = line.split(',');
+            (
+                fields.next().unwrap().to_string(),
+                fields.next().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Wide CSVs must have a column per signal named after its label and units, and a row per interval.
+#[test]
+fn wide_csv_resamples_signals() {
+    let parser = parser();
+    let row = |ts: &str, rpm: &str| (ts.to_string(), rpm.to_string());
+
+    let csv = parser
+        .to_wide_csv(None, 100_000_000, Resampling::ForwardFill)
+        .unwrap()
+        .unwrap();
+    let header = csv.lines().next().unwrap();
+    assert!(header.starts_with("timestamp,EngineSpeed [rpm],"));
+    assert!(header.ends_with(" [%]"));
+    assert_eq!(
+        engine_speeds(&csv),
+        vec![
+            row("timestamp", "EngineSpeed [rpm]"),
+            row("1692896400.000000", "1600"),
+            row("1692896400.100000", "1600"),
+            row("1692896400.200000", "1700"),
+        ]
+    );
+
+    let csv = parser
+        .to_wide_csv(None, 100_000_000, Resampling::Mean)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        engine_speeds(&csv)[1..],
+        [
+            row("1692896400.000000", "1550"),
+            row("1692896400.100000", ""),
+            row("1692896400.200000", "1700"),
+        ]
+    );
+
+    assert!(parser.to_wide_csv(None, 0, Resampling::Mean).is_err());
+    // 250 ms in 1 ns intervals is more rows than allowed
+    assert!(parser.to_wide_csv(None, 1, Resampling::Mean).is_err());
+}
+
+/// Long CSVs must have a row per signal value under the same header, with or without messages.
+#[test]
+fn long_csv_has_a_row_per_signal() {
+    let csv = parser().to_long_csv(None).unwrap().unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], LONG_CSV_COLUMNS.join(","));
+    assert_eq!(lines.len(), 7);
+    assert_eq!(
+        lines[1],
+        "1692896400.000000,18F00400,0,61444,190,EngineSpeed,12000,1500,rpm"
+    );
+    assert!(lines[2].starts_with("1692896400.000000,18F00400,0,61444,512,"));
+    assert!(lines[2].ends_with(",100,-25,%"));
+    assert_eq!(
+        lines[3],
+        "1692896400.050000,18F00400,0,61444,190,EngineSpeed,12800,1600,rpm"
+    );
+
+    let parser = CANParser::new(ErrorPolicy::Strict, None, None).unwrap();
+    let csv = parser.to_long_csv(None).unwrap().unwrap();
+    assert_eq!(csv, LONG_CSV_COLUMNS.join(",") + "\n");
+}
+
+/// The `raw` column must hold the bits of the payload, including "not available" values.
+#[test]
+fn long_csv_raw_values_are_the_payload_bits() {
+    let mut parser = parser();
+    parser
+        .parse_lines(&vec![
+            "(1692896400.500000) can0 18F00400#FFFFFFE02EFFFFFF".to_string()
+        ])
+        .unwrap();
+    let csv = parser.to_long_csv(None).unwrap().unwrap();
+    let torque = csv
+        .lines()
+        .rfind(|line| line.contains(",61444,512,"))
+        .unwrap();
+    assert!(torque.contains(",255,"), "{}", torque);
+}
@@ -0,0 +1,56 @@
+use can_parser::{CANParser, ErrorPolicy, SignalSelection, LOG_FORMAT_CANDUMP};
+
+mod common;
+use common::eec1_annexes;
+
+/// Returns the SPNs decoded from an EEC1 frame with `patterns` selected.
+fn decoded_spns(patterns: &[&str]) -> Vec<u16> {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, Some(eec1_annexes())).unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    parser.set_signal_selection(Some(SignalSelection::new(
+        patterns.iter().map(|p| p.to_string()).collect(),
+    )));
+    let message = parser
+        .parse_line("(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF".to_string())
+        .unwrap();
+    let mut spns: Vec<u16> = message.data.spns.keys().copied().collect();
+    spns.sort();
+    let mut spec_spns: Vec<u16> = parser.filtered_spec.j1939.read().unwrap()[&61444]
+        .spns
+        .keys()
+        .copied()
+        .collect();
+    spec_spns.sort();
+    assert_eq!(spns, spec_spns);
+    spns
+}
+
+#[test]
+fn signal_selection_limits_decoded_spns() {
+    assert_eq!(decoded_spns(&["*"]), vec![190, 512]);
+    assert_eq!(decoded_spns(&["190"]), vec![190]);
+    assert_eq!(decoded_spns(&["engine speed"]), vec![190]);
+    assert_eq!(decoded_spns(&["ENGINE*"]), vec![190]);
+    assert_eq!(decoded_spns(&["51?", "*torque*"]), vec![512]);
+    assert_eq!(decoded_spns(&["wheel*speed"]), Vec::<u16>::new());
+    // Patterns are only matched without their vowels against abbreviated labels
+    assert_eq!(decoded_spns(&["*brake*"]), Vec::<u16>::new());
+    assert_eq!(decoded_spns(&["*speed*"]), vec![190]);
+    assert_eq!(decoded_spns(&["*a*"]), Vec::<u16>::new());
+}
+
+/// Changing the selection between parses must decode the new selection.
+#[test]
+fn signal_selection_can_be_changed_between_parses() {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, None, Some(eec1_annexes())).unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    let line = "(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF".to_string();
+    for (patterns, expected) in [(vec!["190"], vec![190]), (vec!["512"], vec![512])] {
+        parser.set_signal_selection(Some(SignalSelection::new(
+            patterns.iter().map(|p| p.to_string()).collect(),
+        )));
+        let message = parser.parse_line(line.clone()).unwrap();
+        let spns: Vec<u16> = message.data.spns.keys().copied().collect();
+        assert_eq!(spns, expected);
+    }
+}
@@ -0,0 +1,102 @@
+use can_parser::{
+    CANMessage, CANParser, CANParserError, Diagnostic, ErrorPolicy, FilteredSpec, LogFormat,
+    MessageSink,
+};
+
+#[derive(Default)]
+struct Recorder {
+    ids: Vec<u32>,
+    warnings: Vec<u64>,
+    finished: usize,
+}
+
+impl MessageSink for Recorder {
+    fn on_message(&mut self, message: CANMessage) -> Result<(), CANParserError> {
+        self.ids.push(message.id.id);
+        Ok(())
+    }
+
+    fn on_warning(&mut self, diagnostic: &Diagnostic) -> Result<(), CANParserError> {
+        self.warnings.push(diagnostic.line_number);
+        Ok(())
+    }
+
+    fn on_finish(&mut self, _filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        self.finished += 1;
+        Ok(())
+    }
+}
+
+/// A sink receives the messages in input order, then the warnings, then a single finish call,
+/// and the parser does not keep the messages itself.
+#[test]
+fn parse_lines_into_drives_the_sink() {
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let lines = vec![
+        "(1692896400.000000) can0 123#1122".to_string(),
+        "not a candump line".to_string(),
+        "(1692896400.000100) can0 456#3344".to_string(),
+    ];
+    let mut recorder = Recorder::default();
+    let result = parser.parse_lines_into(&lines, &mut recorder);
+
+    assert!(matches!(result, Err(CANParserError::ParserWarning(_))));
+    assert_eq!(recorder.ids, vec![0x123, 0x456]);
+    assert_eq!(recorder.warnings, vec![2]);
+    assert_eq!(recorder.finished, 1);
+    assert!(parser.messages.is_empty());
+}
+
+/// Fails once it has received `limit` messages.
+struct Failing {
+    limit: usize,
+    received: usize,
+    finished: bool,
+}
+
+impl MessageSink for Failing {
+    fn on_message(&mut self, _message: CANMessage) -> Result<(), CANParserError> {
+        if self.received == self.limit {
+            return Err(CANParserError::ParserError("sink is full".to_string()));
+        }
+        self.received += 1;
+        Ok(())
+    }
+
+    fn on_finish(&mut self, _filtered_spec: &FilteredSpec) -> Result<(), CANParserError> {
+        self.finished = true;
+        Ok(())
+    }
+}
+
+/// An error of the sink stops the parse and is returned, but the diagnostics found so far are
+/// still stored.
+#[test]
+fn sink_errors_keep_the_diagnostics() {
+    let mut parser = CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        None,
+    )
+    .unwrap();
+    let mut lines = vec!["not a candump line".to_string()];
+    lines.extend((0..10).map(|n| format!("(1692896400.{:06}) can0 123#11", n)));
+    let mut sink = Failing {
+        limit: 3,
+        received: 0,
+        finished: false,
+    };
+    match parser.parse_lines_into(&lines, &mut sink) {
+        Err(CANParserError::ParserError(message)) => assert_eq!(message, "sink is full"),
+        other => panic!("Expected the sink error, got {:?}", other),
+    }
+    assert_eq!(sink.received, 3);
+    assert!(!sink.finished);
+    assert_eq!(parser.diagnostics.len(), 1);
+    assert_eq!(parser.diagnostics.entries[0].line_number, 1);
+}
@@ -0,0 +1,251 @@
+use can_parser::{
+    CANParser, CANParserError, DiagnosticKind, ErrorPolicy, LogFormat, SPEC_TYPE_J1939,
+};
+use std::collections::HashMap;
+
+/// An EEC1 frame with an engine speed of 1500 rpm.
+const EEC1: &str = "(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF";
+
+/// Returns a J1939 annex for EEC1, with the engine speed given by `spn` and the PGN length by
+/// `pgn_length`.
+fn annex(pgn_length: &str, spn: &str) -> String {
+    format!(
+        r#"{{
+    "J1939PGNdb": {{
+        "61444": {{
+            "Name": "Electronic Engine Controller 1",
+            "Label": "EEC1",
+            "PGNLength": {},
+            "Rate": "engine speed dependent",
+            "SPNs": [190],
+            "SPNStartBits": [[24]]
+        }}
+    }},
+    "J1939SPNdb": {{
+        "190": {}
+    }}
+}}"#,
+        pgn_length, spn
+    )
+}
+
+/// The engine speed SPN with the given resolution and offset.
+fn spn(resolution: &str, offset: &str) -> String {
+    format!(
+        r#"{{
+            "Name": "Engine Speed",
+            "Units": "rpm",
+            "SPNLength": 16,
+            "Resolution": {},
+            "Offset": {},
+            "OperationalHigh": 8031.875
+        }}"#,
+        resolution, offset
+    )
+}
+
+/// Returns a candump parser decoding with the given J1939 annex.
+fn parser(annex: String) -> CANParser {
+    let annexes = HashMap::from([(SPEC_TYPE_J1939.to_string(), annex)]);
+    CANParser::new(
+        ErrorPolicy::Warn,
+        Some(LogFormat::Candump.regex().to_string()),
+        Some(annexes),
+    )
+    .unwrap()
+}
+
+/// Returns the message of the single spec lookup diagnostic of a parse.
+fn lookup_error(result: Result<(), CANParserError>) -> String {
+    match result {
+        Err(CANParserError::ParserWarning(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics.entries[0].kind, DiagnosticKind::SpecLookup);
+            diagnostics.entries[0].message.clone()
+        }
+        other => panic!("Expected a warning, got {:?}", other),
+    }
+}
+
+/// A well-formed annex must decode, with null numbers read as 0 and variable length PGNs as length 0.
+#[test]
+fn valid_annex_decodes() {
+    let mut parser = parser(annex(r#""Variable""#, &spn("0.125", "null")));
+    parser.parse_lines(&vec![EEC1.to_string()]).unwrap();
+    assert_eq!(parser.messages[0].data.spns[&190], 1500.0);
+    let spec = parser.filtered_spec.j1939.read().unwrap();
+    assert_eq!(spec[&61444].length, 0);
+    assert_eq!(spec[&61444].spns[&190].offset, 0.0);
+}
+
+/// SPN fields of the wrong type must fail the lookup with an error naming the field and its
+/// location, instead of being read as 0.
+#[test]
+fn malformed_spn_fields_are_reported() {
+    for field in ["Resolution", "Offset"] {
+        let spn = match field {
+            "Resolution" => spn(r#""0.125""#, "0"),
+            _ => spn("0.125", "[0]"),
+        };
+        let mut parser = parser(annex(r#""8""#, &spn));
+        let message = lookup_error(parser.parse_lines(&vec![EEC1.to_string()]));
+        assert!(message.contains("Expected a number"), "{}", message);
+        assert!(
+            message.contains(&format!("at J1939SPNdb.190.{}", field)),
+            "{}",
+            message
+        );
+        assert!(parser.messages.is_empty());
+    }
+}
+
+/// When skipping invalid entries, the PGN must be decoded without the malformed SPN, which is
+/// reported in `spec_warnings`.
+#[test]
+fn malformed_spns_can_be_skipped() {
+    let mut parser = parser(annex(r#""8""#, &spn("true", "0")));
+    parser.set_skip_invalid_spec_entries(true);
+    parser.parse_lines(&vec![EEC1.to_string()]).unwrap();
+    assert!(parser.messages[0].data.spns.is_empty());
+
+    let warnings = parser.spec_warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].pgn, Some(61444));
+    assert_eq!(warnings[0].spn, Some(190));
+    assert_eq!(warnings[0].field.as_deref(), Some("Resolution"));
+    assert_eq!(
+        warnings[0].location.as_deref(),
+        Some("J1939SPNdb.190.Resolution")
+    );
+}
+
+/// A PGN length that is not a number of bytes must fail the lookup.
+#[test]
+fn malformed_pgn_length_is_reported() {
+    for pgn_length in [r#""eight""#, "8"] {
+        let mut parser = parser(annex(pgn_length, &spn("0.125", "0")));
+        let message = lookup_error(parser.parse_lines(&vec![EEC1.to_string()]));
+        assert!(
+            message.contains("at J1939PGNdb.61444.PGNLength"),
+            "{}",
+            message
+        );
+        assert!(message.contains("PGN 61444"), "{}", message);
+    }
+}
+
+/// In skip mode, an invalid PGN length must be read as 0 and reported in `spec_warnings`.
+#[test]
+fn malformed_pgn_length_can_be_skipped() {
+    let mut parser = parser(annex(r#""eight""#, &spn("0.125", "0")));
+    parser.set_skip_invalid_spec_entries(true);
+    parser.parse_lines(&vec![EEC1.to_string()]).unwrap();
+    assert_eq!(parser.messages[0].data.spns[&190], 1500.0);
+    assert_eq!(parser.filtered_spec.j1939.read().unwrap()[&61444].length, 0);
+    let warnings = parser.spec_warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].field.as_deref(), Some("PGNLength"));
+}
+
+/// SPN lists, start bits and SPN entries that cannot be read must fail the lookup instead of
+/// dropping or zeroing the SPN.
+#[test]
+fn malformed_spn_lists_are_reported() {
+    let engine_speed = spn("0.125", "0");
+    let cases = [
+        (
+            r#"["190"]"#,
+            "[[24]]",
+            engine_speed.clone(),
+            "J1939PGNdb.61444.SPNs[0]",
+        ),
+        (
+            "[190]",
+            "[[24], [8]]",
+            engine_speed.clone(),
+            "J1939PGNdb.61444.SPNStartBits",
+        ),
+        (
+            "[190, 512]",
+            "[[24]]",
+            engine_speed.clone(),
+            "J1939PGNdb.61444.SPNStartBits",
+        ),
+        (
+            "[190]",
+            "[[256]]",
+            engine_speed.clone(),
+            "J1939PGNdb.61444.SPNStartBits[0]",
+        ),
+        (
+            "[190]",
+            r#"[["24"]]"#,
+            engine_speed.clone(),
+            "J1939PGNdb.61444.SPNStartBits[0]",
+        ),
+        ("[512]", "[[24]]", engine_speed.clone(), "J1939SPNdb.512"),
+        (
+            "[190]",
+            "[[24]]",
+            engine_speed.replace("\"SPNLength\": 16", "\"SPNLength\": 272"),
+            "J1939SPNdb.190.SPNLength",
+        ),
+        (
+            "[190]",
+            "[[24]]",
+            engine_speed.replace("\"rpm\"", "1"),
+            "J1939SPNdb.190.Units",
+        ),
+    ];
+    for (spns, start_bits, spn, location) in cases {
+        let annex = annex(r#""8""#, &spn)
+            .replace("[190]", spns)
+            .replace("[[24]]", start_bits);
+        let mut parser = parser(annex);
+        let message = lookup_error(parser.parse_lines(&vec![EEC1.to_string()]));
+        assert!(message.contains(&format!("at {}", location)), "{}", message);
+        assert!(parser.messages.is_empty());
+    }
+
+    let annex =
+        annex(r#""8""#, &engine_speed).replace("\n            \"SPNStartBits\": [[24]]", "");
+    let annex = annex.replace("\"SPNs\": [190],", "\"SPNs\": [190]");
+    let mut parser = parser(annex);
+    let message = lookup_error(parser.parse_lines(&vec![EEC1.to_string()]));
+    assert!(
+        message.contains("at J1939PGNdb.61444.SPNStartBits"),
+        "{}",
+        message
+    );
+}
+
+/// SPNs the annex lists with start bit -1 have no fixed position and must keep start bit 0
+/// without failing the lookup of their PGN.
+#[test]
+fn spns_without_position_are_kept() {
+    let engine_speed = spn("0.125", "0");
+    let annex = annex(r#""8""#, &engine_speed)
+        .replace("[190]", "[190, 512]")
+        .replace("[[24]]", "[24, -1]")
+        .replace(
+            "\"190\": ",
+            &format!("\"512\": {},\n        \"190\": ", engine_speed),
+        );
+    let mut parser = parser(annex);
+    parser.parse_lines(&vec![EEC1.to_string()]).unwrap();
+    assert_eq!(parser.messages[0].data.spns[&190], 1500.0);
+    let spec = parser.filtered_spec.j1939.read().unwrap();
+    assert_eq!(spec[&61444].spns[&512].start_bit, 0);
+}
+
+/// Annexes that are not JSON objects must be rejected when the parser is created.
+#[test]
+fn malformed_annexes_are_rejected() {
+    for annex in ["[]", "{ \"J1939PGNdb\": "] {
+        let annexes = HashMap::from([(SPEC_TYPE_J1939.to_string(), annex.to_string())]);
+        assert!(matches!(
+            CANParser::new(ErrorPolicy::Warn, None, Some(annexes)),
+            Err(CANParserError::SpecError(_))
+        ));
+    }
+}
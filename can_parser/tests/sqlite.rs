@@ -0,0 +1,154 @@
+#![cfg(feature = "sqlite")]
+
+use can_parser::{CANParser, ErrorPolicy, LogFile, SqliteSink, LOG_FORMAT_CANDUMP};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+mod common;
+
+/// A row of the messages table: timestamp, channel, direction, source, ID, length, data and SPN values.
+type MessageRow = (
+    i64,
+    String,
+    Option<String>,
+    String,
+    u32,
+    u8,
+    Vec<u8>,
+    String,
+);
+
+/// Returns the path of a temporary file of the SQLite tests named after `name`.
+fn temp_path(name: &str) -> String {
+    let path = common::temp_path(&format!("sqlite_{}", name));
+    path.to_str().unwrap().to_string()
+}
+
+/// Messages merged from several files must be stored with their source, and the specification
+/// with its SPNs, so that they can be read back.
+#[test]
+fn sqlite_round_trips() {
+    let first = temp_path("first.log");
+    std::fs::write(
+        &first,
+        "(1692896400.000000) can0 18F00400#FF64FFE02EFFFFFF R\n",
+    )
+    .unwrap();
+    let second = temp_path("second.log");
+    std::fs::write(&second, "(1692896400.000000) can0 123#1122\n").unwrap();
+
+    let mut parser =
+        CANParser::new(ErrorPolicy::Strict, None, Some(common::eec1_annexes())).unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    parser
+        .parse_files(&[
+            LogFile {
+                path: first.clone(),
+                ..Default::default()
+            },
+            LogFile {
+                path: second.clone(),
+                channel: Some("can1".to_string()),
+                time_offset: 1000,
+            },
+        ])
+        .unwrap();
+    let database = temp_path("round_trip.db");
+    let _ = std::fs::remove_file(&database);
+    parser.to_sqlite(database.clone()).unwrap();
+
+    let conn = Connection::open(&database).unwrap();
+    let mut statement = conn
+        .prepare(
+            "SELECT timestamp, channel, direction, source, can_id, length, data, spn_values
+            FROM messages ORDER BY id",
+        )
+        .unwrap();
+    let rows: Vec<MessageRow> = statement
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+            ))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    let (ts, channel, direction, source, can_id, length, data, spns) = &rows[0];
+    assert_eq!(*ts, 1_692_896_400_000_000_000);
+    assert_eq!(channel, "can0");
+    assert!(direction.is_some());
+    assert_eq!(source, &first);
+    assert_eq!(*can_id, 0x18F00400);
+    assert_eq!(*length, 8);
+    assert_eq!(data, &vec![0xFF, 0x64, 0xFF, 0xE0, 0x2E, 0xFF, 0xFF, 0xFF]);
+    let spns: HashMap<String, f64> = serde_json::from_str(spns).unwrap();
+    assert_eq!(
+        spns,
+        HashMap::from([("190".to_string(), 1500.0), ("512".to_string(), -25.0)])
+    );
+    let (ts, channel, direction, source, can_id, _, data, _) = &rows[1];
+    assert_eq!(*ts, 1_692_896_400_000_001_000);
+    assert_eq!(channel, "can1");
+    assert!(direction.is_none());
+    assert_eq!(source, &second);
+    assert_eq!(*can_id, 0x123);
+    assert_eq!(data, &vec![0x11, 0x22]);
+
+    let (pgn, resolution, start_bit): (u16, f64, u32) = conn
+        .query_row(
+            "SELECT pgn, resolution, start_bit FROM SpecSPNs WHERE id = 190",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .unwrap();
+    assert_eq!((pgn, resolution, start_bit), (61444, 0.125, 24));
+    let label: Vec<u8> = conn
+        .query_row("SELECT label FROM SpecPGNs WHERE id = 61444", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert!(label.iter().any(|&byte| byte != 0));
+}
+
+/// A `SqliteSink` must write the messages of a parse straight to the database, over several
+/// transactions, and replace any existing file.
+#[test]
+fn sqlite_sink_streams_messages() {
+    let database = temp_path("sink.db");
+    std::fs::write(&database, "not a database").unwrap();
+    let lines: Vec<String> = (0..20_000)
+        .map(|n| {
+            format!(
+                "({}.{:06}) can0 123#{:04X}",
+                1_692_896_400 + n / 1000,
+                n % 1000,
+                n
+            )
+        })
+        .collect();
+
+    let mut parser = CANParser::new(ErrorPolicy::Warn, None, None).unwrap();
+    parser.set_log_format(LOG_FORMAT_CANDUMP).unwrap();
+    let mut sink = SqliteSink::create(database.clone()).unwrap();
+    parser.parse_lines_into(&lines, &mut sink).unwrap();
+    drop(sink);
+
+    let conn = Connection::open(&database).unwrap();
+    let (count, last): (u32, Vec<u8>) = conn
+        .query_row(
+            "SELECT COUNT(*), (SELECT data FROM messages ORDER BY id DESC LIMIT 1) FROM messages",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(count, 20_000);
+    assert_eq!(last, vec![0x4E, 0x1F]);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "async")]
+
+use can_parser::{CANParser, CANParserError, ErrorPolicy, LogFormat};
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+/// Reading from an `AsyncRead` yields the messages in order and records the bad lines.
+#[tokio::test]
+async fn stream_reader_decodes_lines() {
+    let parser = Arc::new(
+        CANParser::new(
+            ErrorPolicy::Warn,
+            Some(LogFormat::Candump.regex().to_string()),
+            None,
+        )
+        .unwrap(),
+    );
+    let log: &[u8] =
+        b"(1692896400.000000) can0 123#1122\r\nnot a candump line\n(1692896400.000100) can0 456#3344\n";
+    let mut stream = parser.stream_reader(log);
+
+    let mut ids = Vec::new();
+    while let Some(message) = stream.next().await {
+        ids.push(message.unwrap().id.id);
+    }
+    assert_eq!(ids, vec![0x123, 0x456]);
+    assert_eq!(stream.diagnostics().len(), 1);
+    assert_eq!(stream.diagnostics().entries[0].line_number, 2);
+}
+
+/// A strict parser ends the stream at the first bad line.
+#[tokio::test]
+async fn stream_lines_stops_when_aborted() {
+    let parser = Arc::new(
+        CANParser::new(
+            ErrorPolicy::Strict,
+            Some(LogFormat::Candump.regex().to_string()),
+            None,
+        )
+        .unwrap(),
+    );
+    let lines = futures_util::stream::iter(vec![
+        Ok::<_, std::io::Error>("(1692896400.000000) can0 123#1122"),
+        Ok("not a candump line"),
+        Ok("(1692896400.000100) can0 456#3344"),
+    ]);
+    let results: Vec<_> = parser.stream_lines(lines).collect().await;
+
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(CANParserError::ParseAborted(_))));
+}
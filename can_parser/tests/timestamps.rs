@@ -0,0 +1,181 @@
+use can_parser::{
+    parse_seconds, parse_utc_offset, CANParser, ErrorPolicy, TimestampFormat, NANOS_PER_SEC,
+};
+
+/// 2023-08-24T17:00:00Z in nanoseconds since the Unix epoch.
+const TS: i64 = 1_692_896_400 * NANOS_PER_SEC;
+
+/// Returns the timestamp parsed from `line` by `line_regex` in `format`.
+fn parse(line_regex: &str, format: TimestampFormat, line: &str) -> Result<i64, String> {
+    let mut parser = CANParser::new(ErrorPolicy::Strict, Some(line_regex.to_string()), None)
+        .map_err(|e| e.to_string())?;
+    parser.set_timestamp_format(format);
+    parser
+        .parse_line(line.to_string())
+        .map(|message| message.ts)
+        .map_err(|e| e.to_string())
+}
+
+/// Decimal seconds must keep nanosecond precision and reject anything but digits.
+#[test]
+fn seconds_are_parsed_exactly() {
+    assert_eq!(parse_seconds("1692896400.123456789"), Ok(TS + 123_456_789));
+    assert_eq!(parse_seconds("1692896400.1234567891"), Ok(TS + 123_456_789));
+    assert_eq!(parse_seconds("1692896400"), Ok(TS));
+    assert_eq!(parse_seconds("1.5"), Ok(1_500_000_000));
+    assert_eq!(parse_seconds(".25"), Ok(250_000_000));
+    assert_eq!(parse_seconds("-1.25"), Ok(-1_250_000_000));
+    for bad in [
+        "",
+        ".",
+        "-",
+        "1.2.3",
+        "1e3",
+        "+1",
+        "abc",
+        "99999999999999999999",
+    ] {
+        assert!(parse_seconds(bad).is_err(), "{}", bad);
+    }
+}
+
+/// UTC offsets must be read in seconds ahead of UTC, with or without a colon.
+#[test]
+fn utc_offsets_are_parsed() {
+    assert_eq!(parse_utc_offset("Z"), Ok(0));
+    assert_eq!(parse_utc_offset("UTC"), Ok(0));
+    assert_eq!(parse_utc_offset("+02:00"), Ok(7200));
+    assert_eq!(parse_utc_offset("-0530"), Ok(-19_800));
+    assert_eq!(parse_utc_offset("+01"), Ok(3600));
+    for bad in ["02:00", "+2", "+24:00", "+02:60", "+02:0", "+0a:00", ""] {
+        assert!(parse_utc_offset(bad).is_err(), "{}", bad);
+    }
+}
+
+/// Formats must be created from their names, with their UTC offset and start.
+#[test]
+fn formats_are_created_from_names() {
+    assert_eq!(
+        TimestampFormat::from_name("epoch", None, None).unwrap(),
+        TimestampFormat::Epoch
+    );
+    assert_eq!(TimestampFormat::default(), TimestampFormat::Epoch);
+    assert_eq!(
+        TimestampFormat::from_name("Relative", None, Some(TS)).unwrap(),
+        TimestampFormat::Relative { start: TS }
+    );
+    assert_eq!(
+        TimestampFormat::from_name("iso8601", Some("+02:00"), None).unwrap(),
+        TimestampFormat::Iso8601 { utc_offset: 7200 }
+    );
+    assert_eq!(
+        TimestampFormat::from_name("datetime", None, None).unwrap(),
+        TimestampFormat::DateTime { utc_offset: 0 }
+    );
+    assert!(TimestampFormat::from_name("unix", None, None).is_err());
+    assert!(TimestampFormat::from_name("iso8601", Some("+25:00"), None).is_err());
+}
+
+/// Epoch and relative timestamps must be read in seconds, or in milliseconds from `timestamp_ms`.
+#[test]
+fn epoch_and_relative_timestamps() {
+    let seconds = r"^(?P<timestamp>[0-9.]+) (?P<id>[0-9A-F]+)#(?P<data>[0-9A-F]*)";
+    let millis = r"^(?P<timestamp_ms>[0-9.]+) (?P<id>[0-9A-F]+)#(?P<data>[0-9A-F]*)";
+    let epoch = TimestampFormat::Epoch;
+    let relative = TimestampFormat::Relative { start: TS };
+
+    assert_eq!(
+        parse(seconds, epoch, "1692896400.000001 123#"),
+        Ok(TS + 1000)
+    );
+    assert_eq!(
+        parse(millis, epoch, "1692896400000.5 123#"),
+        Ok(TS + 500_000)
+    );
+    assert_eq!(parse(seconds, relative, "1.5 123#"), Ok(TS + 1_500_000_000));
+    assert_eq!(
+        parse(millis, relative, "1059.9 123#"),
+        Ok(TS + 1_059_900_000)
+    );
+    assert!(parse(seconds, epoch, "1.2.3 123#").is_err());
+    assert!(parse(seconds, relative, "9223372036.854775807 123#").is_err());
+}
+
+/// ISO-8601 timestamps must honour their time zone, or the format's UTC offset without one.
+#[test]
+fn iso8601_timestamps() {
+    let regex = r"^(?P<timestamp>[^ ]+ ?[0-9:.]*[^ ]*) (?P<id>[0-9A-F]+)#(?P<data>[0-9A-F]*)";
+    let utc = TimestampFormat::Iso8601 { utc_offset: 0 };
+    let cest = TimestampFormat::Iso8601 { utc_offset: 7200 };
+
+    assert_eq!(parse(regex, utc, "2023-08-24T17:00:00Z 123#"), Ok(TS));
+    assert_eq!(
+        parse(regex, utc, "2023-08-24T19:00:00.25+02:00 123#"),
+        Ok(TS + 250_000_000)
+    );
+    assert_eq!(parse(regex, cest, "2023-08-24T19:00:00 123#"), Ok(TS));
+    assert_eq!(parse(regex, cest, "2023-08-24T17:00:00z 123#"), Ok(TS));
+    assert_eq!(
+        parse(regex, utc, "1969-12-31T23:59:59Z 123#"),
+        Ok(-NANOS_PER_SEC)
+    );
+    assert_eq!(
+        parse(regex, utc, "2024-02-29T00:00:00Z 123#"),
+        Ok(1_709_164_800 * NANOS_PER_SEC)
+    );
+    for bad in [
+        "2023-08-24 123#",
+        "2023-02-29T00:00:00Z 123#",
+        "2023-02-31T00:00:00Z 123#",
+        "2023-04-31T00:00:00Z 123#",
+        "2023-13-01T00:00:00Z 123#",
+        "2023-00-01T00:00:00Z 123#",
+        "2023-08-24-01T00:00:00Z 123#",
+        "2023-08-24T24:00:00Z 123#",
+        "2023-08-24T17:60:00Z 123#",
+        "2023-08-24T17:00:60Z 123#",
+        "2023-08-24T17:00:61Z 123#",
+        "2023-08-24T17:00:00+25:00 123#",
+        "9999-01-01T00:00:00Z 123#",
+        "1600-01-01T00:00:00Z 123#",
+        "99999-01-01T00:00:00Z 123#",
+    ] {
+        assert!(parse(regex, utc, bad).is_err(), "{}", bad);
+    }
+}
+
+/// Date and time groups must be combined, and a time alone read as the time of day.
+#[test]
+fn date_and_time_timestamps() {
+    let regex = r"^(?P<date>[0-9/-]+) (?P<time>[0-9:.]+) (?P<id>[0-9A-F]+)#(?P<data>[0-9A-F]*)";
+    let time_only = r"^(?P<time>[0-9:.]+) (?P<id>[0-9A-F]+)#(?P<data>[0-9A-F]*)";
+    let utc = TimestampFormat::DateTime { utc_offset: 0 };
+    let cest = TimestampFormat::DateTime { utc_offset: 7200 };
+
+    assert_eq!(
+        parse(regex, utc, "2023-08-24 17:00:00.000001 123#"),
+        Ok(TS + 1000)
+    );
+    assert_eq!(parse(regex, utc, "2023/08/24 17:00 123#"), Ok(TS));
+    assert_eq!(parse(regex, cest, "2023-08-24 19:00:00 123#"), Ok(TS));
+    assert_eq!(
+        parse(time_only, utc, "01:00:01.5 123#"),
+        Ok(3_601_500_000_000)
+    );
+    for bad in [
+        "2023-02-31 17:00:00 123#",
+        "2100-02-29 17:00:00 123#",
+        "2023-6-31 17:00:00 123#",
+        "2023-08 17:00:00 123#",
+        "2023-08-24 17 123#",
+        "2023-08-24 24:00:00 123#",
+        "2023-08-24 17:00:61 123#",
+        "9999-01-01 00:00:00 123#",
+    ] {
+        assert!(parse(regex, utc, bad).is_err(), "{}", bad);
+    }
+    assert_eq!(
+        parse(regex, utc, "2000-02-29 00:00:00 123#"),
+        Ok(951_782_400 * NANOS_PER_SEC)
+    );
+}
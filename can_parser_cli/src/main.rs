@@ -1,9 +1,17 @@
 use clap::{arg, builder::{PossibleValue, ArgPredicate}, Arg, ArgGroup, Command};
 use std::collections::HashMap;
-use std::fs;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::ops::ControlFlow;
+use std::sync::Arc;
 
-use can_parser::{CANParser, CANParserError, ERROR_WARN, LOG_TYPE_BINARY, LOG_TYPE_TEXT};
+use can_parser::{
+    open_log_reader, parse_seconds, CANParser, CANParserError, Diagnostics, ErrorPolicy, IdFilter,
+    LogFile, LogFormat, LogWriterFormat, MessageFilter, Progress, ProgressCallback, Resampling,
+    SignalSelection, TimestampFormat, TrafficConfig, LOG_TYPE_BINARY, LOG_TYPE_TEXT,
+};
+
+/// The width of the progress bar in characters.
+const PROGRESS_BAR_WIDTH: usize = 30;
 
 fn cli() -> Command {
     Command::new("can_parser_cli")
@@ -13,28 +21,123 @@ fn cli() -> Command {
         .arg_required_else_help(true)
         .group(
             ArgGroup::new("input")
-                .args(&["file", "message"])
+                .args(["file", "message", "generate"])
                 .required(true)
                 .multiple(false),
         )
         .next_help_heading(Some("Input Options"))
         .args([
-            arg!(-f --file <FILE_PATH> "CAN log file to parse."),
+            arg!(-f --file <FILE_PATH> "CAN log file to parse. Gzip, zstd, xz and bzip2 compressed logs are decompressed on the fly. Repeat to merge several logs into one time ordered decode.")
+                .action(clap::ArgAction::Append),
             arg!(-m --message <MSG> "Single CAN message to parse."),
+            arg!(-g --generate <CONFIG> "JSON traffic configuration to generate a log from with the --specs annex instead of parsing. The log is written to --output in the candump, asc or blf --format.")
+                .requires("output"),
+            Arg::new("file_channels")
+                .long("file_channels")
+                .value_name("CHANNEL")
+                .help("Comma separated list of channels to tag the messages of each --file with. Length and order must match --file.")
+                .value_delimiter(',')
+                .requires("file"),
+            Arg::new("time_offsets")
+                .long("time_offsets")
+                .value_name("SECONDS")
+                .help("Comma separated list of seconds added to the timestamps of each --file. Length and order must match --file.")
+                .value_delimiter(',')
+                .allow_hyphen_values(true)
+                .requires("file"),
         ])
         .group(
             ArgGroup::new("parsing")
                 .args(&["template", "custom_regex"])
-                .required(true)
+                .required(false)
                 .multiple(false),
         )
         .next_help_heading(Some("Parsing Options"))
         .args([
             arg!(-t --template <TEMPLATE> "Regex template for parsing.")
-                .value_parser([PossibleValue::new("candump").help("candump format")])
+                .value_parser([
+                    PossibleValue::new("candump").help("candump format"),
+                    PossibleValue::new("asc").help("Vector ASC format"),
+                    PossibleValue::new("trc").help("PEAK TRC format"),
+                ])
                 .conflicts_with("custom_regex"),
             arg!(-r --custom_regex <REGEX> "Custom regex expression. Must start with r\" and end with \".")
                 .conflicts_with("template"),
+            arg!(--id_format <ID_FORMAT> "How to tell extended IDs from standard IDs.")
+                .value_parser([
+                    PossibleValue::new("auto").help("Detect from how each ID is written in the log"),
+                    PossibleValue::new("standard").help("Treat every ID as a standard 11-bit ID"),
+                    PossibleValue::new("extended").help("Treat every ID as an extended 29-bit ID"),
+                ])
+                .default_value("auto"),
+            arg!(--timestamp_format <TIMESTAMP_FORMAT> "Format of the captured timestamps.")
+                .value_parser([
+                    PossibleValue::new("epoch").help("Seconds since the Unix epoch in the timestamp group"),
+                    PossibleValue::new("relative").help("Seconds since the start of the log in the timestamp group"),
+                    PossibleValue::new("iso8601").help("ISO-8601 date and time in the timestamp group"),
+                    PossibleValue::new("datetime").help("Date and time of day in the date and time groups"),
+                ])
+                .default_value("epoch"),
+            arg!(--utc_offset <OFFSET> "UTC offset such as +02:00 for timestamps without a time zone."),
+            arg!(--error_handling <MODE> "How to handle lines that cannot be parsed.")
+                .value_parser([
+                    PossibleValue::new("ignore").help("Skip them silently"),
+                    PossibleValue::new("warn").help("Skip them and report them once parsing is done"),
+                    PossibleValue::new("strict").help("Stop at the first one"),
+                    PossibleValue::new("warn_up_to").help("Report up to --error_limit of them, then stop"),
+                ])
+                .default_value("warn"),
+            arg!(--error_limit <COUNT> "Number of failed lines warn_up_to reports before stopping.")
+                .value_parser(clap::value_parser!(usize))
+                .required_if_eq("error_handling", "warn_up_to"),
+            Arg::new("mmap")
+                .long("mmap")
+                .help("Memory map the log file and parse it in place, which is faster for large uncompressed logs. The file must not change while it is parsed. Only a single file without --file_channels or --time_offsets can be mapped.")
+                .action(clap::ArgAction::SetTrue),
+            Arg::new("columnar")
+                .long("columnar")
+                .help("Keep the decoded messages in per-ID column tables, which takes several times less memory for large logs.")
+                .action(clap::ArgAction::SetTrue),
+        ])
+        .next_help_heading(Some("Filter Options"))
+        .args([
+            Arg::new("filter_ids")
+                .long("filter_ids")
+                .value_name("ID:MASK")
+                .help("Comma separated list of candump style ID filters in hex, of which one must match. ID~MASK inverts a filter, and IDs with 8 digits are extended.")
+                .value_delimiter(','),
+            Arg::new("pgns")
+                .long("pgns")
+                .value_name("PGN")
+                .help("Comma separated list of the PGNs to parse.")
+                .value_delimiter(',')
+                .value_parser(clap::value_parser!(u16)),
+            Arg::new("source_addresses")
+                .long("source_addresses")
+                .value_name("SA")
+                .help("Comma separated list of the J1939 source addresses to parse.")
+                .value_delimiter(',')
+                .value_parser(clap::value_parser!(u8)),
+            Arg::new("destination_addresses")
+                .long("destination_addresses")
+                .value_name("DA")
+                .help("Comma separated list of the J1939 destination addresses to parse.")
+                .value_delimiter(',')
+                .value_parser(clap::value_parser!(u8)),
+            arg!(--start <SECONDS> "First timestamp to parse, in seconds since the Unix epoch.")
+                .allow_hyphen_values(true),
+            arg!(--end <SECONDS> "Timestamp to stop parsing before, in seconds since the Unix epoch.")
+                .allow_hyphen_values(true),
+            Arg::new("channels")
+                .long("channels")
+                .value_name("CHANNEL")
+                .help("Comma separated list of the channels to parse.")
+                .value_delimiter(','),
+            Arg::new("signals")
+                .long("signals")
+                .value_name("PATTERN")
+                .help("Comma separated list of the signals to decode and output, by SPN number or label. * and ? are wildcards, e.g. 190,*brake switch*.")
+                .value_delimiter(','),
         ])
         .group(
             ArgGroup::new("specification")
@@ -64,6 +167,15 @@ fn cli() -> Command {
                     PossibleValue::new("transport").help("Transport specification"),
                 ])
                 .requires_if(ArgPredicate::IsPresent, "specs"),
+            Arg::new("channel_specs")
+                .long("channel_specs")
+                .value_name("CHANNEL=TYPE[+TYPE]")
+                .help("Comma separated list of channels and the specification types used to decode them, e.g. can0=j1939,can1=. Channels not listed are decoded with every specification.")
+                .value_delimiter(','),
+            Arg::new("skip_invalid_spec_entries")
+                .long("skip_invalid_spec_entries")
+                .help("Skip specification entries with missing or malformed fields, with a warning, instead of failing to decode their messages.")
+                .action(clap::ArgAction::SetTrue),
         ])
         .group(
             ArgGroup::new("output options")
@@ -79,17 +191,33 @@ fn cli() -> Command {
                 .long("force")
                 .help("Forcefully overwrite the output file if it exists.")
                 .action(clap::ArgAction::SetTrue),
+            Arg::new("progress")
+                .long("progress")
+                .help("Show a progress bar on stderr while parsing.")
+                .action(clap::ArgAction::SetTrue),
+            arg!(--diagnostics_output <PATH> "File path to write every line that could not be parsed, with its line number, byte offset and error kind, as JSON."),
             arg!(-'F' --format <FORMAT> "Output format. CSV format outputs the parsed artifacts in multiple files.").value_parser([
                 PossibleValue::new("json").help("JSON format"),
                 PossibleValue::new("csv").help("CSV format"),
                 PossibleValue::new("sqlite").help("SQLite format"),
+                PossibleValue::new("candump").help("candump -L log, replayable with canplayer"),
+                PossibleValue::new("asc").help("Vector ASC log"),
+                PossibleValue::new("blf").help("Vector BLF log"),
+                PossibleValue::new("long_csv").help("CSV of the decoded signals with a row per signal value"),
+                PossibleValue::new("wide_csv").help("CSV of the decoded signals with a row per --interval and a column per signal"),
             ]).default_value("json"),
+            arg!(--interval <SECONDS> "Interval between the rows of the wide_csv format, in seconds.")
+                .default_value("0.1"),
+            arg!(--resampling <RESAMPLING> "How the signals of the wide_csv format are resampled onto its rows.").value_parser([
+                PossibleValue::new("ffill").help("Last value, carried forward through rows without a value"),
+                PossibleValue::new("mean").help("Mean of the values of each row, empty without a value"),
+            ]).default_value("ffill"),
         ])
 }
 
 fn detect_file_type(path: &str) -> Result<String, String> {
-    // Read the first few bytes
-    let mut file = fs::File::open(path).map_err(|e| format!("Error: {}", e))?;
+    // Read the first few bytes, decompressing them first if the log is compressed
+    let mut file = open_log_reader(path).map_err(|e| format!("Error: {}", e))?;
     let mut buffer = [0; 5];
     file.read(&mut buffer)
         .map_err(|e| format!("Error: {}", e))?;
@@ -98,12 +226,55 @@ fn detect_file_type(path: &str) -> Result<String, String> {
     if content.chars().all(|c| c.is_ascii() && !c.is_control()) {
         return Ok(LOG_TYPE_TEXT.to_string());
     }
-    match path.split('.').last().unwrap_or_default() {
+    match path
+        .trim_end_matches(".gz")
+        .trim_end_matches(".zst")
+        .trim_end_matches(".xz")
+        .trim_end_matches(".bz2")
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+    {
         "txt" | "log" => Ok(LOG_TYPE_TEXT.to_string()),
         _ => Ok(LOG_TYPE_BINARY.to_string()),
     }
 }
 
+fn write_diagnostics(matches: &clap::ArgMatches, diagnostics: &Diagnostics) -> Result<(), String> {
+    if let Some(path) = matches.get_one::<String>("diagnostics_output") {
+        let json =
+            serde_json::to_string_pretty(diagnostics).map_err(|e| format!("Error: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Error: {}", e))?;
+    }
+    Ok(())
+}
+
+fn draw_progress(progress: &Progress) -> ControlFlow<()> {
+    let mut line = String::new();
+    if let Some(fraction) = progress.fraction {
+        let filled = (fraction * PROGRESS_BAR_WIDTH as f64) as usize;
+        line.push_str(&format!(
+            "[{}{}] {:5.1}% ",
+            "#".repeat(filled),
+            ".".repeat(PROGRESS_BAR_WIDTH - filled),
+            fraction * 100.0
+        ));
+    }
+    line.push_str(&format!(
+        "{:.1} MB, {} lines, {} messages, {:.1}s",
+        progress.bytes_read as f64 / 1_000_000.0,
+        progress.lines_read,
+        progress.messages,
+        progress.elapsed
+    ));
+    if let Some(eta) = progress.eta {
+        line.push_str(&format!(", ETA {:.0}s", eta));
+    }
+    eprint!("\r{}", line);
+    let _ = std::io::stderr().flush();
+    ControlFlow::Continue(())
+}
+
 fn write_results(matches: &clap::ArgMatches, parser: &mut CANParser) -> Result<(), String> {
     match matches.get_one::<String>("output") {
         Some(output) => {
@@ -136,6 +307,31 @@ fn write_results(matches: &clap::ArgMatches, parser: &mut CANParser) -> Result<(
                             .to_sqlite(output.clone())
                             .map_err(|e| format!("Error: {}", e))?;
                     }
+                    "candump" | "asc" | "blf" => {
+                        let format = LogWriterFormat::from_name(format)
+                            .map_err(|e| format!("Error: {}", e))?;
+                        println!("Writing {} log...", format.name());
+                        parser
+                            .write_log(Some(output.clone()), format, &MessageFilter::default())
+                            .map_err(|e| format!("Error: {}", e))?;
+                    }
+                    "long_csv" => {
+                        println!("Writing long csv file...");
+                        parser
+                            .to_long_csv(Some(output.clone()))
+                            .map_err(|e| format!("Error: {}", e))?;
+                    }
+                    "wide_csv" => {
+                        let interval =
+                            parse_seconds(matches.get_one::<String>("interval").unwrap())?;
+                        let resampling =
+                            Resampling::from_name(matches.get_one::<String>("resampling").unwrap())
+                                .map_err(|e| format!("Error: {}", e))?;
+                        println!("Writing wide csv file...");
+                        parser
+                            .to_wide_csv(Some(output.clone()), interval, resampling)
+                            .map_err(|e| format!("Error: {}", e))?;
+                    }
                     _ => return Err("Invalid format".to_string()),
                 },
                 None => {
@@ -156,25 +352,73 @@ fn write_results(matches: &clap::ArgMatches, parser: &mut CANParser) -> Result<(
     Ok(())
 }
 
-fn parse_input(matches: &clap::ArgMatches) -> Result<(), String> {
-    let _file_type = match matches.get_one::<String>("file") {
-        Some(path) => detect_file_type(path)?,
-        None => LOG_TYPE_TEXT.to_string(),
+fn message_filter(matches: &clap::ArgMatches) -> Result<Option<MessageFilter>, String> {
+    let ids = matches
+        .get_many::<String>("filter_ids")
+        .map(|ids| {
+            ids.map(|id| id.parse::<IdFilter>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(|e| format!("Error: {}", e))?;
+    let seconds = |name: &str| {
+        matches
+            .get_one::<String>(name)
+            .map(|s| parse_seconds(s))
+            .transpose()
     };
-    let line_regex = match matches.get_one::<String>("template") {
-        Some(template) => match template.as_str() {
-            "candump" => {
-                r"^\((?P<timestamp>[0-9]+\.[0-9]+)\).*?(?P<id>[0-9A-F]{3,8})#(?P<data>[0-9A-F]+)"
-            }
-            _ => return Err("Invalid template".to_string()),
-        },
-        None => match matches.get_one::<String>("custom_regex") {
-            Some(regex) => regex,
-            None => return Err("No regex template or custom regex provided".to_string()),
-        },
+    let filter = MessageFilter {
+        ids,
+        pgns: matches
+            .get_many::<u16>("pgns")
+            .map(|v| v.copied().collect()),
+        source_addresses: matches
+            .get_many::<u8>("source_addresses")
+            .map(|v| v.copied().collect()),
+        destination_addresses: matches
+            .get_many::<u8>("destination_addresses")
+            .map(|v| v.copied().collect()),
+        start: seconds("start")?,
+        end: seconds("end")?,
+        channels: matches
+            .get_many::<String>("channels")
+            .map(|v| v.cloned().collect()),
     };
-    let specs_map: Option<HashMap<String, String>> = match matches.get_many::<String>("specs_types")
-    {
+    Ok((filter != MessageFilter::default()).then_some(filter))
+}
+
+fn log_files(
+    paths: Vec<String>,
+    channels: Option<clap::parser::ValuesRef<String>>,
+    time_offsets: Option<clap::parser::ValuesRef<String>>,
+) -> Result<Vec<LogFile>, String> {
+    let channels: Vec<String> = channels.map(|c| c.cloned().collect()).unwrap_or_default();
+    if !channels.is_empty() && channels.len() != paths.len() {
+        return Err("The number of file channels and files must be equal".to_string());
+    }
+    let time_offsets = time_offsets
+        .map(|t| {
+            t.map(|s| parse_seconds(s))
+                .collect::<Result<Vec<i64>, String>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    if !time_offsets.is_empty() && time_offsets.len() != paths.len() {
+        return Err("The number of time offsets and files must be equal".to_string());
+    }
+    Ok(paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| LogFile {
+            path,
+            channel: channels.get(i).cloned(),
+            time_offset: time_offsets.get(i).copied().unwrap_or_default(),
+        })
+        .collect())
+}
+
+fn specs_map(matches: &clap::ArgMatches) -> Result<Option<HashMap<String, String>>, String> {
+    let specs_map = match matches.get_many::<String>("specs_types") {
         Some(specs_types) => {
             let specs_types: Vec<String> = specs_types.cloned().collect();
 
@@ -192,35 +436,164 @@ fn parse_input(matches: &clap::ArgMatches) -> Result<(), String> {
         }
         None => None,
     };
-    let mut parser = CANParser::new(
-        ERROR_WARN.to_string(),
-        Some(line_regex.to_string()),
-        specs_map,
+    Ok(specs_map)
+}
+
+fn generate_log(matches: &clap::ArgMatches, config_path: &str) -> Result<(), String> {
+    let output = matches
+        .get_one::<String>("output")
+        .ok_or_else(|| "No output file provided".to_string())?;
+    if !matches.get_flag("force") && std::path::Path::new(output).exists() {
+        return Err(format!(
+            "Output file {} already exists. Use --force to overwrite",
+            output
+        ));
+    }
+    let format = matches
+        .get_one::<String>("format")
+        .map(|s| s.as_str())
+        .unwrap_or_default();
+    let format = LogWriterFormat::from_name(format)
+        .map_err(|_| format!("Invalid log format {}. Use candump, asc or blf", format))?;
+    let config = std::fs::read_to_string(config_path).map_err(|e| format!("Error: {}", e))?;
+    let config = TrafficConfig::from_json(&config).map_err(|e| format!("Error: {}", e))?;
+    let parser = CANParser::new(ErrorPolicy::Strict, None, specs_map(matches)?)
+        .map_err(|e| format!("Error: {}", e))?;
+    println!("Writing {} log...", format.name());
+    parser
+        .generate_log(&config, output, format)
+        .map_err(|e| format!("Error: {}", e))
+}
+
+fn parse_input(matches: &clap::ArgMatches) -> Result<(), String> {
+    let _file_type = match matches
+        .get_many::<String>("file")
+        .and_then(|mut f| f.next())
+    {
+        Some(path) => detect_file_type(path)?,
+        None => LOG_TYPE_TEXT.to_string(),
+    };
+    let line_regex = match matches.get_one::<String>("template") {
+        Some(template) => LogFormat::from_name(template)
+            .map_err(|_| "Invalid template".to_string())?
+            .regex(),
+        None => match matches.get_one::<String>("custom_regex") {
+            Some(regex) => regex,
+            None => return Err("No regex template or custom regex provided".to_string()),
+        },
+    };
+    let specs_map = specs_map(matches)?;
+    let error_handling = ErrorPolicy::from_name(
+        matches
+            .get_one::<String>("error_handling")
+            .map(|s| s.as_str())
+            .unwrap_or_default(),
+        matches.get_one::<usize>("error_limit").copied(),
     )
     .map_err(|e| format!("Error: {}", e))?;
+    let mut parser = CANParser::new(error_handling, Some(line_regex.to_string()), specs_map)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    if let Some(channel_specs) = matches.get_many::<String>("channel_specs") {
+        for channel_spec in channel_specs {
+            let (channel, spec_types) = channel_spec.split_once('=').ok_or_else(|| {
+                format!(
+                    "Invalid channel specification {}. Expected CHANNEL=TYPE[+TYPE]",
+                    channel_spec
+                )
+            })?;
+            let spec_types = spec_types
+                .split('+')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect();
+            parser
+                .set_channel_specs(channel.to_string(), spec_types)
+                .map_err(|e| format!("Error: {}", e))?;
+        }
+    }
+
+    if let Some(timestamp_format) = matches.get_one::<String>("timestamp_format") {
+        let timestamp_format = TimestampFormat::from_name(
+            timestamp_format,
+            matches.get_one::<String>("utc_offset").map(|s| s.as_str()),
+            None,
+        )
+        .map_err(|e| format!("Error: {}", e))?;
+        parser.set_timestamp_format(timestamp_format);
+    }
+
+    parser.set_filter(message_filter(matches)?);
+    parser.set_signal_selection(
+        matches
+            .get_many::<String>("signals")
+            .map(|patterns| SignalSelection::new(patterns.cloned().collect())),
+    );
+    parser.set_skip_invalid_spec_entries(matches.get_flag("skip_invalid_spec_entries"));
+    parser.set_columnar(matches.get_flag("columnar"));
+
+    match matches.get_one::<String>("id_format").map(|s| s.as_str()) {
+        Some("standard") => parser.set_extended_ids(Some(false)),
+        Some("extended") => parser.set_extended_ids(Some(true)),
+        _ => parser.set_extended_ids(None),
+    }
+
+    if matches.get_flag("progress") {
+        let callback: ProgressCallback = Arc::new(draw_progress);
+        parser.set_progress_callback(Some(callback));
+    }
 
     let results;
-    if let Some(path) = matches.get_one::<String>("file") {
-        results = parser.parse_file(path);
+    if let Some(paths) = matches.get_many::<String>("file") {
+        let paths: Vec<String> = paths.cloned().collect();
+        let channels = matches.get_many::<String>("file_channels");
+        let time_offsets = matches.get_many::<String>("time_offsets");
+        if paths.len() == 1 && channels.is_none() && time_offsets.is_none() {
+            results = if matches.get_flag("mmap") {
+                parser.parse_file_mmap(&paths[0])
+            } else {
+                parser.parse_file(&paths[0])
+            };
+        } else if matches.get_flag("mmap") {
+            return Err(
+                "--mmap parses a single file without --file_channels or --time_offsets".to_string(),
+            );
+        } else {
+            results = parser.parse_files(&log_files(paths, channels, time_offsets)?);
+        }
     } else if let Some(message) = matches.get_one::<String>("message") {
         let messages = vec![message.clone()];
         results = parser.parse_lines(&messages);
     } else {
         return Err("No input provided".to_string());
     }
+    if matches.get_flag("progress") {
+        eprintln!();
+    }
+    for warning in parser.spec_warnings.lock().unwrap().iter() {
+        eprintln!("Skipped specification entry: {}", warning);
+    }
     match results {
         Ok(_) => write_results(matches, &mut parser),
         Err(e) => match e {
             CANParserError::ParserError(e) => {
                 return Err(e);
             }
-            CANParserError::ParserWarning(e) => {
+            CANParserError::ParserWarning(diagnostics) => {
                 eprintln!(
-                    "The parser threw some warning(s) while parsing the file: {:?}",
-                    e
+                    "The parser threw some warning(s) while parsing the file: {}",
+                    diagnostics
                 );
+                write_diagnostics(matches, &diagnostics)?;
                 write_results(matches, &mut parser)
             }
+            CANParserError::ParseAborted(diagnostics) => {
+                write_diagnostics(matches, &diagnostics)?;
+                Err(format!(
+                    "Parsing stopped by --error_handling: {}",
+                    diagnostics
+                ))
+            }
             _ => Err(format!("Error: {}", e)),
         },
     }
@@ -228,7 +601,11 @@ fn parse_input(matches: &clap::ArgMatches) -> Result<(), String> {
 
 fn main() {
     let matches = cli().get_matches();
-    if let Err(e) = parse_input(&matches) {
+    let result = match matches.get_one::<String>("generate") {
+        Some(config_path) => generate_log(&matches, config_path),
+        None => parse_input(&matches),
+    };
+    if let Err(e) = result {
         eprintln!("😓 Oops! An error occurred: {}", e);
     }
 }
@@ -1,13 +1,19 @@
 extern crate can_parser;
 
 use can_parser::{
-    CANMessage, CANParser, FileFlags, FilteredSpec, ERROR_IGNORE, ERROR_WARN, LOG_TYPE_BINARY,
-    LOG_TYPE_TEXT, SPEC_TYPE_CAN, SPEC_TYPE_J1939, SPEC_TYPE_TRANSPORT, SPEC_TYPE_UDS,
+    CANMessage, CANParser, Diagnostic, DiagnosticKind, ErrorPolicy, FileFlags, FilteredSpec,
+    IdFilter, LogFile, LogWriterFormat, MessageFilter, Progress, ProgressCallback, Resampling,
+    SignalSelection, TimestampFormat, TrafficConfig, ERROR_IGNORE, ERROR_STRICT, ERROR_WARN,
+    ERROR_WARN_UP_TO, LOG_FORMAT_ASC, LOG_FORMAT_BLF, LOG_FORMAT_CANDUMP, LOG_FORMAT_TRC,
+    LOG_TYPE_BINARY, LOG_TYPE_TEXT, RESAMPLING_FFILL, RESAMPLING_MEAN, SPEC_TYPE_CAN,
+    SPEC_TYPE_J1939, SPEC_TYPE_TRANSPORT, SPEC_TYPE_UDS, TIMESTAMP_DATETIME, TIMESTAMP_EPOCH,
+    TIMESTAMP_ISO8601, TIMESTAMP_RELATIVE,
 };
 use pyo3::exceptions;
 use pyo3::prelude::*;
 use pyo3::Python;
 use std::collections::HashMap;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 
 /// This function is a PyO3 entry point that initializes the CANParserPython module.
@@ -15,6 +21,9 @@ use std::sync::Arc;
 #[pymodule]
 fn can_parser_python(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<CANParserPython>()?;
+    m.add_class::<Diagnostic>()?;
+    m.add_class::<DiagnosticKind>()?;
+    m.add_class::<Progress>()?;
     m.add("SPEC_TYPE_CAN", SPEC_TYPE_CAN)?;
     m.add("SPEC_TYPE_J1939", SPEC_TYPE_J1939)?;
     m.add("SPEC_TYPE_UDS", SPEC_TYPE_UDS)?;
@@ -23,6 +32,18 @@ fn can_parser_python(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add("LOG_TYPE_BINARY", LOG_TYPE_BINARY)?;
     m.add("ERROR_IGNORE", ERROR_IGNORE)?;
     m.add("ERROR_WARN", ERROR_WARN)?;
+    m.add("ERROR_STRICT", ERROR_STRICT)?;
+    m.add("ERROR_WARN_UP_TO", ERROR_WARN_UP_TO)?;
+    m.add("TIMESTAMP_EPOCH", TIMESTAMP_EPOCH)?;
+    m.add("TIMESTAMP_RELATIVE", TIMESTAMP_RELATIVE)?;
+    m.add("TIMESTAMP_ISO8601", TIMESTAMP_ISO8601)?;
+    m.add("TIMESTAMP_DATETIME", TIMESTAMP_DATETIME)?;
+    m.add("LOG_FORMAT_CANDUMP", LOG_FORMAT_CANDUMP)?;
+    m.add("LOG_FORMAT_ASC", LOG_FORMAT_ASC)?;
+    m.add("LOG_FORMAT_TRC", LOG_FORMAT_TRC)?;
+    m.add("LOG_FORMAT_BLF", LOG_FORMAT_BLF)?;
+    m.add("RESAMPLING_FFILL", RESAMPLING_FFILL)?;
+    m.add("RESAMPLING_MEAN", RESAMPLING_MEAN)?;
     Ok(())
 }
 
@@ -38,29 +59,228 @@ impl CANParserPython {
     ///
     /// # Arguments
     ///
-    /// * `error_handling` - A string representing the error handling mode.
+    /// * `error_handling` - One of `ERROR_IGNORE`, `ERROR_WARN`, `ERROR_STRICT` or `ERROR_WARN_UP_TO`.
     /// * `line_regex` - An optional string representing the line regex.
     /// * `specs_annexes` - An optional hashmap containing the specs annexes.
+    /// * `error_limit` - The number of failed lines `ERROR_WARN_UP_TO` warns about before stopping the parse.
     ///
     /// # Errors
     ///
-    /// Returns a `PyValueError` if there is an error creating the `CANParser` instance.
+    /// Returns a `PyValueError` if the error handling mode is invalid or there is an error creating the `CANParser` instance.
     ///
     /// # Returns
     ///
     /// Returns a `PyResult` containing the new `CANParserPython` instance.
     #[new]
-    #[pyo3(signature=(error_handling=ERROR_WARN.to_string(), line_regex=None, specs_annexes=None))]
+    #[pyo3(signature=(error_handling=ERROR_WARN.to_string(), line_regex=None, specs_annexes=None, error_limit=None))]
     pub fn new(
         error_handling: String,
         line_regex: Option<String>,
         specs_annexes: Option<HashMap<String, String>>,
+        error_limit: Option<usize>,
     ) -> PyResult<Self> {
+        let error_handling = ErrorPolicy::from_name(&error_handling, error_limit)
+            .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))?;
         let inner = CANParser::new(error_handling, line_regex, specs_annexes)
             .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))?;
         Ok(CANParserPython { inner })
     }
 
+    /// Sets the format of the timestamps captured by the line regex.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_format` - One of `TIMESTAMP_EPOCH`, `TIMESTAMP_RELATIVE`, `TIMESTAMP_ISO8601` or `TIMESTAMP_DATETIME`.
+    /// * `utc_offset` - An optional UTC offset such as "+02:00" for times without a time zone.
+    /// * `start` - The start of a relative log in nanoseconds since the Unix epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PyValueError` if the format or UTC offset is invalid.
+    #[pyo3(signature=(timestamp_format, utc_offset=None, start=None))]
+    pub fn set_timestamp_format(
+        &mut self,
+        timestamp_format: &str,
+        utc_offset: Option<&str>,
+        start: Option<i64>,
+    ) -> PyResult<()> {
+        let timestamp_format = TimestampFormat::from_name(timestamp_format, utc_offset, start)
+            .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))?;
+        self.inner.set_timestamp_format(timestamp_format);
+        Ok(())
+    }
+
+    /// Selects a built-in log format, split by a hand-written parser instead of a regex.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_format` - One of `LOG_FORMAT_CANDUMP`, `LOG_FORMAT_ASC` or `LOG_FORMAT_TRC`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PyValueError` if the format is unknown.
+    pub fn set_log_format(&mut self, log_format: &str) -> PyResult<()> {
+        self.inner
+            .set_log_format(log_format)
+            .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))
+    }
+
+    /// Overrides whether parsed IDs are treated as extended or standard.
+    ///
+    /// # Arguments
+    ///
+    /// * `extended` - `True` for all extended IDs, `False` for all standard IDs, or `None` to detect them from the log.
+    pub fn set_extended_ids(&mut self, extended: Option<bool>) -> PyResult<()> {
+        self.inner.set_extended_ids(extended);
+        Ok(())
+    }
+
+    /// Restricts the messages that are parsed. Lines whose message does not match are dropped right after their ID is parsed, before any specification lookup. A message must match every criterion that is given; calling it without arguments parses every message again.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - An optional `list` of candump style ID filters, `"<id>:<mask>"` or inverted `"<id>~<mask>"` in hex, of which one must match.
+    /// * `pgns` - An optional `list` of PGNs.
+    /// * `source_addresses` - An optional `list` of source addresses.
+    /// * `destination_addresses` - An optional `list` of destination addresses.
+    /// * `start` - The optional first timestamp parsed, in nanoseconds since the Unix epoch.
+    /// * `end` - The optional timestamp parsing ends before, in nanoseconds since the Unix epoch.
+    /// * `channels` - An optional `list` of channels.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PyValueError` if an ID filter is invalid.
+    #[pyo3(signature=(ids=None, pgns=None, source_addresses=None, destination_addresses=None, start=None, end=None, channels=None))]
+    // Each criterion is a Python keyword argument
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_filter(
+        &mut self,
+        ids: Option<Vec<String>>,
+        pgns: Option<Vec<u16>>,
+        source_addresses: Option<Vec<u8>>,
+        destination_addresses: Option<Vec<u8>>,
+        start: Option<i64>,
+        end: Option<i64>,
+        channels: Option<Vec<String>>,
+    ) -> PyResult<()> {
+        let ids = match ids {
+            Some(ids) => Some(
+                ids.iter()
+                    .map(|id| id.parse::<IdFilter>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))?,
+            ),
+            None => None,
+        };
+        let filter = MessageFilter {
+            ids,
+            pgns,
+            source_addresses,
+            destination_addresses,
+            start,
+            end,
+            channels,
+        };
+        self.inner
+            .set_filter((filter != MessageFilter::default()).then_some(filter));
+        Ok(())
+    }
+
+    /// Restricts the signals that are decoded and serialized. Changing the selection clears the PGNs already looked up, so the next parse decodes the new selection.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - A `list` of SPN numbers or labels, where `*` and `?` are wildcards, e.g. `["190", "*brake switch*"]`, or `None` to decode every signal.
+    #[pyo3(signature=(patterns=None))]
+    pub fn set_signal_selection(&mut self, patterns: Option<Vec<String>>) -> PyResult<()> {
+        self.inner
+            .set_signal_selection(patterns.map(SignalSelection::new));
+        Ok(())
+    }
+
+    /// Sets whether parse calls add to the results of the previous calls instead of replacing them.
+    ///
+    /// # Arguments
+    ///
+    /// * `append` - Whether to append the messages, flags and diagnostics of each parse call.
+    pub fn set_append(&mut self, append: bool) -> PyResult<()> {
+        self.inner.set_append(append);
+        Ok(())
+    }
+
+    /// Clears the messages, diagnostics, flags and filtered specification of previous parse calls.
+    pub fn reset(&mut self) -> PyResult<()> {
+        self.inner.reset();
+        Ok(())
+    }
+
+    /// Sets whether parsed messages are kept in a columnar store, which takes several times less memory for large logs.
+    ///
+    /// # Arguments
+    ///
+    /// * `columnar` - Whether to store the messages in columns.
+    pub fn set_columnar(&mut self, columnar: bool) -> PyResult<()> {
+        self.inner.set_columnar(columnar);
+        Ok(())
+    }
+
+    /// Sets whether specification entries that cannot be read are skipped, and listed in `spec_warnings`, instead of failing the lookup.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - Whether to skip specification entries that cannot be read.
+    pub fn set_skip_invalid_spec_entries(&mut self, skip: bool) -> PyResult<()> {
+        self.inner.set_skip_invalid_spec_entries(skip);
+        Ok(())
+    }
+
+    /// Sets a callable receiving the progress of each parse as a `Progress` object.
+    ///
+    /// The callable is called every few thousand lines and once more when the parse ends. Returning
+    /// `True` cancels the parse, which then raises an error while keeping the messages decoded so
+    /// far. An exception raised by the callable is printed and also cancels the parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The callable, or `None` to stop reporting progress.
+    pub fn set_progress_callback(&mut self, callback: Option<PyObject>) -> PyResult<()> {
+        let callback = callback.map(|callback| -> ProgressCallback {
+            Arc::new(move |progress: &Progress| {
+                Python::with_gil(|py| {
+                    match callback
+                        .call1(py, (*progress,))
+                        .and_then(|result| result.is_true(py))
+                    {
+                        Ok(false) => ControlFlow::Continue(()),
+                        Ok(true) => ControlFlow::Break(()),
+                        Err(err) => {
+                            err.print(py);
+                            ControlFlow::Break(())
+                        }
+                    }
+                })
+            })
+        });
+        self.inner.set_progress_callback(callback);
+        Ok(())
+    }
+
+    /// Restricts the specifications used to decode messages logged on a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel or interface name as it appears in the log, e.g. `can0`.
+    /// * `spec_types` - The specification types to decode the channel with. An empty list disables decoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PyValueError` if any of the specification types are unknown.
+    pub fn set_channel_specs(&mut self, channel: String, spec_types: Vec<String>) -> PyResult<()> {
+        self.inner
+            .set_channel_specs(channel, spec_types)
+            .map_err(|e| exceptions::PyValueError::new_err(format!("{}", e)))
+    }
+
     /// Parses a file given its file path.
     ///
     /// # Arguments
@@ -71,8 +291,56 @@ impl CANParserPython {
     ///
     /// Returns a `PyResult` that contains either `Ok(())` if the file was parsed successfully or
     /// `Err(exceptions::PyValueError)` if there was an error while parsing the file.
-    pub fn parse_file(&mut self, file_path: &str) -> PyResult<()> {
-        let result = self.inner.parse_file(file_path);
+    pub fn parse_file(&mut self, py: Python<'_>, file_path: &str) -> PyResult<()> {
+        // The GIL is released so that the progress callback can be called from the worker threads.
+        let inner = &mut self.inner;
+        let result = py.allow_threads(|| inner.parse_file(file_path));
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
+    /// Parses several log files into a single timestamp ordered list of messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_paths` - The paths of the files to be parsed.
+    /// * `channels` - An optional list of channels to tag each file's messages with. Must match `file_paths` in length.
+    /// * `time_offsets` - An optional list of nanoseconds added to each file's timestamps. Must match `file_paths` in length.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `PyResult` that contains either `Ok(())` if the files were parsed successfully or
+    /// `Err(exceptions::PyValueError)` if there was an error while parsing the files.
+    #[pyo3(signature=(file_paths, channels=None, time_offsets=None))]
+    pub fn parse_files(
+        &mut self,
+        py: Python<'_>,
+        file_paths: Vec<String>,
+        channels: Option<Vec<String>>,
+        time_offsets: Option<Vec<i64>>,
+    ) -> PyResult<()> {
+        let channels = channels.unwrap_or_default();
+        let time_offsets = time_offsets.unwrap_or_default();
+        if (!channels.is_empty() && channels.len() != file_paths.len())
+            || (!time_offsets.is_empty() && time_offsets.len() != file_paths.len())
+        {
+            return Err(exceptions::PyValueError::new_err(
+                "channels and time_offsets must have one entry per file",
+            ));
+        }
+        let files: Vec<LogFile> = file_paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| LogFile {
+                path,
+                channel: channels.get(i).cloned(),
+                time_offset: time_offsets.get(i).copied().unwrap_or_default(),
+            })
+            .collect();
+        let inner = &mut self.inner;
+        let result = py.allow_threads(|| inner.parse_files(&files));
         match result {
             Ok(_) => Ok(()),
             Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
@@ -90,8 +358,9 @@ impl CANParserPython {
     ///
     /// * `Ok(())` if the parsing is successful.
     /// * `Err(exceptions::PyValueError)` if there is an error during parsing.
-    pub fn parse_lines(&mut self, lines: Vec<String>) -> PyResult<()> {
-        let result = self.inner.parse_lines(&lines);
+    pub fn parse_lines(&mut self, py: Python<'_>, lines: Vec<String>) -> PyResult<()> {
+        let inner = &mut self.inner;
+        let result = py.allow_threads(|| inner.parse_lines(&lines));
         match result {
             Ok(_) => Ok(()),
             Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
@@ -132,6 +401,49 @@ impl CANParserPython {
         }
     }
 
+    /// Converts the decoded signals to a wide, time-aligned CSV with a row per interval and a column per signal, named `label [units]`, for Excel and pandas. If the `file_path` argument is provided, the CSV is written to the file at the specified path. Otherwise, the CSV is returned as a `String`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - An optional `String` representing the path to the file where the CSV should be written.
+    /// * `interval` - The interval between rows in nanoseconds. Defaults to 100 ms.
+    /// * `resampling` - `RESAMPLING_FFILL` to carry the last value of each signal forward, or `RESAMPLING_MEAN` to average the values of each interval. Defaults to `RESAMPLING_FFILL`.
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<String>` containing either the CSV string or an error message if the conversion fails.
+    #[pyo3(signature=(file_path=None, interval=100_000_000, resampling=RESAMPLING_FFILL.to_string()))]
+    pub fn to_wide_csv(
+        &self,
+        file_path: Option<String>,
+        interval: i64,
+        resampling: String,
+    ) -> PyResult<String> {
+        let result = Resampling::from_name(&resampling)
+            .and_then(|resampling| self.inner.to_wide_csv(file_path, interval, resampling));
+        match result {
+            Ok(result) => Ok(result.unwrap_or("".to_string())),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
+    /// Converts the decoded signals to a long, tidy CSV with a row per signal value and the columns timestamp, id, sa, pgn, spn, label, raw, value and units. If the `file_path` argument is provided, the CSV is written to the file at the specified path. Otherwise, the CSV is returned as a `String`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_path` - An optional `String` representing the path to the file where the CSV should be written.
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<String>` containing either the CSV string or an error message if the conversion fails.
+    #[pyo3(signature=(file_path=None))]
+    pub fn to_long_csv(&self, file_path: Option<String>) -> PyResult<String> {
+        match self.inner.to_long_csv(file_path) {
+            Ok(result) => Ok(result.unwrap_or("".to_string())),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
     /// Converts the CAN data to a SQLite database. If the `file_path` argument is provided, the database is written to the file at the specified path. Otherwise, the database is returned as a `Vec<u8>`.
     /// 
     /// # Arguments
@@ -149,19 +461,195 @@ impl CANParserPython {
         }
     }
 
+    /// Encodes physical SPN values into a J1939 message of the loaded J1939 specification. SPNs without a value are set to "not available".
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn` - The PGN of the message.
+    /// * `values` - A `dict` of the physical value of each SPN to encode, by SPN.
+    /// * `source_address` - The source address of the message.
+    /// * `destination_address` - The destination address of a PDU1 message. Defaults to the global address.
+    /// * `priority` - The priority of the message. Defaults to the priority of the PGN in the specification.
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<CANMessage>` containing the encoded message or an error message if the values cannot be encoded.
+    #[pyo3(signature=(pgn, values, source_address, destination_address=None, priority=None))]
+    pub fn encode_j1939(
+        &self,
+        pgn: u16,
+        values: HashMap<u16, f64>,
+        source_address: u8,
+        destination_address: Option<u8>,
+        priority: Option<u8>,
+    ) -> PyResult<CANMessage> {
+        let result =
+            self.inner
+                .encode_j1939(pgn, &values, source_address, destination_address, priority);
+        match result {
+            Ok(message) => Ok(message),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
+    /// Encodes physical signal values into a message of the loaded DBC annex.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The name of the message in the DBC annex.
+    /// * `values` - A `dict` of the physical value of each signal to encode, by signal name.
+    /// * `source_address` - Replaces the source address of messages with an extended ID.
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<CANMessage>` containing the encoded message or an error message if the values cannot be encoded.
+    #[pyo3(signature=(message, values, source_address=None))]
+    pub fn encode_dbc(
+        &self,
+        message: &str,
+        values: HashMap<String, f64>,
+        source_address: Option<u8>,
+    ) -> PyResult<CANMessage> {
+        let result = self.inner.encode_dbc(message, &values, source_address);
+        match result {
+            Ok(message) => Ok(message),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
+    /// Writes the selected parsed messages back out to a log file, losslessly, so it can be replayed with can-utils or loaded into other tools. A message is selected if it matches every criterion that is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - A `String` representing the path of the log file.
+    /// * `log_format` - One of "candump", "asc" or "blf". Defaults to "candump".
+    /// * `pgns` - An optional `list` of the PGNs of the selected messages.
+    /// * `channels` - An optional `list` of the channels of the selected messages.
+    /// * `start` - The optional first timestamp selected, in nanoseconds since the Unix epoch.
+    /// * `end` - The optional timestamp the selection ends before, in nanoseconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<()>` indicating whether the log was written.
+    #[pyo3(signature=(output_path, log_format=LOG_FORMAT_CANDUMP.to_string(), pgns=None, channels=None, start=None, end=None))]
+    pub fn write_log(
+        &self,
+        output_path: String,
+        log_format: String,
+        pgns: Option<Vec<u16>>,
+        channels: Option<Vec<String>>,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> PyResult<()> {
+        let selection = MessageFilter {
+            pgns,
+            channels,
+            start,
+            end,
+            ..Default::default()
+        };
+        let result = LogWriterFormat::from_name(&log_format)
+            .and_then(|format| self.inner.write_log(Some(output_path), format, &selection));
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
+    /// Generates periodic traffic from the loaded J1939 or DBC annex and writes it to a log file.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A JSON `String` with the `duration` in seconds, optional `start` in nanoseconds and `seed`, and the `messages` to send, each with a `pgn` or DBC `message`, `source_address`, optional `period_ms` and the `signals` profiles (`constant`, `ramp`, `sine` or `random_walk`).
+    /// * `output_path` - A `String` representing the path of the log file.
+    /// * `log_format` - One of "candump", "asc" or "blf". Defaults to "candump".
+    ///
+    /// # Returns
+    ///
+    /// A `PyResult<()>` indicating whether the log was written.
+    #[pyo3(signature=(config, output_path, log_format=LOG_FORMAT_CANDUMP.to_string()))]
+    pub fn generate_log(
+        &self,
+        py: Python<'_>,
+        config: String,
+        output_path: String,
+        log_format: String,
+    ) -> PyResult<()> {
+        let inner = &self.inner;
+        let result = py.allow_threads(|| {
+            let config = TrafficConfig::from_json(&config)?;
+            let format = LogWriterFormat::from_name(&log_format)?;
+            inner.generate_log(&config, &output_path, format)
+        });
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => Err(exceptions::PyValueError::new_err(format!("{}", err))),
+        }
+    }
+
     // Getters and setters
     /// Returns a copy of the list of CAN messages.
     #[getter]
     pub fn get_messages(&self) -> PyResult<Vec<CANMessage>> {
-        Ok(self.inner.messages.clone())
+        Ok(self
+            .inner
+            .iter_messages()
+            .map(|message| message.into_owned())
+            .collect())
     }
 
     /// Clears all messages from the CAN parser.
     pub fn clear_messages(&mut self) -> PyResult<()> {
-        self.inner.messages.clear();
+        self.inner.clear_messages();
         Ok(())
     }
 
+    /// Returns the number of parsed messages.
+    #[getter]
+    pub fn get_message_count(&self) -> PyResult<usize> {
+        Ok(self.inner.message_count())
+    }
+
+    /// Returns the timestamps in nanoseconds and the values of a signal across every message carrying it, ordered by timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `spn` - The SPN of the signal.
+    pub fn get_signal(&self, spn: u16) -> PyResult<(Vec<i64>, Vec<f32>)> {
+        Ok(self.inner.signal(spn))
+    }
+
+    /// Returns the lines that could not be parsed by the last parse, with their line numbers, byte offsets and error kinds.
+    #[getter]
+    pub fn get_diagnostics(&self) -> PyResult<Vec<Diagnostic>> {
+        Ok(self.inner.diagnostics.entries.clone())
+    }
+
+    /// Returns the number of lines that could not be parsed by the last parse, per error kind.
+    #[getter]
+    pub fn get_diagnostic_counts(&self) -> PyResult<HashMap<String, usize>> {
+        Ok(self
+            .inner
+            .diagnostics
+            .counts
+            .iter()
+            .map(|(kind, count)| (kind.to_string(), *count))
+            .collect())
+    }
+
+    /// Returns the specification entries that were skipped because they could not be read.
+    #[getter]
+    pub fn get_spec_warnings(&self) -> PyResult<Vec<String>> {
+        Ok(self
+            .inner
+            .spec_warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.to_string())
+            .collect())
+    }
+
     /// Returns a copy of the `FilteredSpec` struct that contains the current filter settings.
     #[getter]
     pub fn get_filtered_spec(&self) -> PyResult<FilteredSpec> {
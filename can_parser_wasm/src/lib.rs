@@ -1,8 +1,16 @@
 extern crate can_parser;
 
-use can_parser::{CANParser, FileFlags, FilteredSpec};
-use serde_wasm_bindgen::{from_value, to_value};
+use can_parser::{
+    CANParser, ErrorPolicy, FileFlags, FilteredSpec, LogWriterFormat, MessageFilter, Resampling,
+    SignalSelection, TimestampFormat,
+};
+#[cfg(not(target_feature = "atomics"))]
+use can_parser::{Progress, ProgressCallback};
+use serde::Serialize;
+use serde_wasm_bindgen::{from_value, to_value, Serializer};
 use std::collections::HashMap;
+#[cfg(not(target_feature = "atomics"))]
+use std::ops::ControlFlow;
 use std::sync::{Arc, RwLock};
 use wasm_bindgen::prelude::*;
 // pub use wasm_bindgen_rayon::init_thread_pool;
@@ -15,17 +23,55 @@ pub struct CANParserWasm {
     inner: CANParser,
 }
 
+/// A JavaScript function receiving the progress of each parse. Only available without the `atomics`
+/// target feature, as JavaScript values cannot leave the thread that created them.
+#[cfg(not(target_feature = "atomics"))]
+struct JsProgressCallback(js_sys::Function);
+
+// SAFETY: Without the `atomics` target feature WebAssembly has a single thread, so the function
+// is only ever called from the thread that created it.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for JsProgressCallback {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for JsProgressCallback {}
+
+#[cfg(not(target_feature = "atomics"))]
+impl JsProgressCallback {
+    /// Calls the function with the progress, cancelling the parse if it returns a truthy value or throws.
+    fn call(&self, progress: &Progress) -> ControlFlow<()> {
+        let progress = progress
+            .serialize(&Serializer::json_compatible())
+            .unwrap_or(JsValue::NULL);
+        match self.0.call1(&JsValue::NULL, &progress) {
+            Ok(result) if !result.is_truthy() => ControlFlow::Continue(()),
+            _ => ControlFlow::Break(()),
+        }
+    }
+}
+
 /// This is a custom TypeScript section that exports constants used in the CANParser WebAssembly module.
 #[wasm_bindgen(typescript_custom_section)]
 const _TS_APPEND_FILE_TYPE: &'static str = r#"
 export const ERROR_IGNORE = "ignore";
 export const ERROR_WARN = "warn";
+export const ERROR_STRICT = "strict";
+export const ERROR_WARN_UP_TO = "warn_up_to";
 export const LOG_TYPE_TEXT = "text";
 export const LOG_TYPE_BINARY = "binary";
 export const SPEC_TYPE_CAN = "can";
 export const SPEC_TYPE_J1939 = "j1939";
 export const SPEC_TYPE_UDS = "uds";
 export const SPEC_TYPE_TRANSPORT = "transport";
+export const TIMESTAMP_EPOCH = "epoch";
+export const TIMESTAMP_RELATIVE = "relative";
+export const TIMESTAMP_ISO8601 = "iso8601";
+export const TIMESTAMP_DATETIME = "datetime";
+export const LOG_FORMAT_CANDUMP = "candump";
+export const LOG_FORMAT_ASC = "asc";
+export const LOG_FORMAT_TRC = "trc";
+export const LOG_FORMAT_BLF = "blf";
+export const RESAMPLING_FFILL = "ffill";
+export const RESAMPLING_MEAN = "mean";
 "#;
 
 #[wasm_bindgen]
@@ -34,22 +80,28 @@ impl CANParserWasm {
     ///
     /// # Arguments
     ///
-    /// * `error_handling` - A string that specifies the error handling mode of the parser.
+    /// * `error_handling` - One of `ERROR_IGNORE`, `ERROR_WARN`, `ERROR_STRICT` or `ERROR_WARN_UP_TO`.
     /// * `line_regex` - An optional string that specifies the regular expression used to match lines in the input.
     /// * `specs_annexes` - A `JsValue` that contains a JSON object with the specifications and annexes used by the parser.
+    /// * `error_limit` - The number of failed lines `ERROR_WARN_UP_TO` warns about before stopping the parse.
     ///
     /// # Errors
     ///
-    /// Returns a `JsError` if the creation of the `CANParser` fails.
+    /// Returns a `JsError` if the error handling mode is invalid or the creation of the `CANParser` fails.
     #[wasm_bindgen(constructor)]
     pub fn new(
         error_handling: String,
         line_regex: Option<String>,
         specs_annexes: JsValue,
+        error_limit: Option<usize>,
     ) -> Result<CANParserWasm, JsError> {
         console_error_panic_hook::set_once();
         let specs_annexes: Option<HashMap<String, String>> =
             from_value(specs_annexes).expect("Failed to parse specs_annexes");
+        let error_handling = ErrorPolicy::from_name(&error_handling, error_limit).map_err(|e| {
+            let msg = format!("Failed to create CANParser: {}", e);
+            JsError::new(&msg)
+        })?;
         let inner = CANParser::new(error_handling, line_regex, specs_annexes).map_err(|e| {
             let msg = format!("Failed to create CANParser: {}", e);
             JsError::new(&msg)
@@ -57,6 +109,171 @@ impl CANParserWasm {
         Ok(CANParserWasm { inner })
     }
 
+    /// Sets the format of the timestamps captured by the line regex.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestamp_format` - One of `TIMESTAMP_EPOCH`, `TIMESTAMP_RELATIVE`, `TIMESTAMP_ISO8601` or `TIMESTAMP_DATETIME`.
+    /// * `utc_offset` - An optional UTC offset such as "+02:00" for times without a time zone.
+    /// * `start` - The start of a relative log in nanoseconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the format was set, otherwise returns an error as a `JsValue`.
+    pub fn set_timestamp_format(
+        &mut self,
+        timestamp_format: String,
+        utc_offset: Option<String>,
+        start: Option<i64>,
+    ) -> Result<(), JsValue> {
+        let timestamp_format =
+            TimestampFormat::from_name(&timestamp_format, utc_offset.as_deref(), start)
+                .map_err(|err| -> JsValue { err.into() })?;
+        self.inner.set_timestamp_format(timestamp_format);
+        Ok(())
+    }
+
+    /// Selects a built-in log format, split by a hand-written parser instead of a regex.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_format` - One of `LOG_FORMAT_CANDUMP`, `LOG_FORMAT_ASC` or `LOG_FORMAT_TRC`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the format was set, otherwise returns an error as a `JsValue`.
+    pub fn set_log_format(&mut self, log_format: String) -> Result<(), JsValue> {
+        self.inner
+            .set_log_format(&log_format)
+            .map_err(|err| -> JsValue { err.into() })
+    }
+
+    /// Overrides whether parsed IDs are treated as extended or standard.
+    ///
+    /// # Arguments
+    ///
+    /// * `extended` - `true` for all extended IDs, `false` for all standard IDs, or `undefined` to detect them from the log.
+    pub fn set_extended_ids(&mut self, extended: Option<bool>) {
+        self.inner.set_extended_ids(extended);
+    }
+
+    /// Restricts the messages that are parsed. Lines whose message does not match are dropped right after their ID is
+    /// parsed, before any specification lookup.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - An object with optional `ids` (SocketCAN style `{ id, mask, inverted }` filters of which one must
+    ///   match), `pgns`, `source_addresses`, `destination_addresses`, `channels`, and the `start` and exclusive `end`
+    ///   of a time window as nanosecond timestamps. Every criterion given must match. `undefined` parses every message.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the filter was set, otherwise returns an error as a `JsValue`.
+    pub fn set_filter(&mut self, filter: JsValue) -> Result<(), JsValue> {
+        let filter: Option<MessageFilter> = if filter.is_undefined() || filter.is_null() {
+            None
+        } else {
+            Some(from_value(filter)?)
+        };
+        self.inner.set_filter(filter);
+        Ok(())
+    }
+
+    /// Restricts the signals that are decoded and serialized. Changing the selection clears the PGNs already looked
+    /// up, so the next parse decodes the new selection.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - An array of SPN numbers or labels, where `*` and `?` are wildcards, e.g.
+    ///   `["190", "*brake switch*"]`, or `undefined` to decode every signal.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the selection was set, otherwise returns an error as a `JsValue`.
+    pub fn set_signal_selection(&mut self, patterns: JsValue) -> Result<(), JsValue> {
+        let patterns: Option<Vec<String>> = if patterns.is_undefined() || patterns.is_null() {
+            None
+        } else {
+            Some(from_value(patterns)?)
+        };
+        self.inner
+            .set_signal_selection(patterns.map(SignalSelection::new));
+        Ok(())
+    }
+
+    /// Sets whether parse calls add to the results of the previous calls instead of replacing them,
+    /// e.g. to feed a file in chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `append` - Whether to append the messages, flags and diagnostics of each parse call.
+    pub fn set_append(&mut self, append: bool) {
+        self.inner.set_append(append);
+    }
+
+    /// Clears the messages, diagnostics, flags and filtered specification of previous parse calls.
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Sets whether parsed messages are kept in a columnar store, which takes several times less memory for large logs.
+    ///
+    /// # Arguments
+    ///
+    /// * `columnar` - Whether to store the messages in columns.
+    pub fn set_columnar(&mut self, columnar: bool) {
+        self.inner.set_columnar(columnar);
+    }
+
+    /// Sets whether specification entries that cannot be read are skipped, and listed in `spec_warnings`, instead of failing the lookup.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - Whether to skip specification entries that cannot be read.
+    pub fn set_skip_invalid_spec_entries(&mut self, skip: bool) {
+        self.inner.set_skip_invalid_spec_entries(skip);
+    }
+
+    /// Sets a function receiving the progress of each parse, e.g. to post it from a web worker.
+    ///
+    /// The function is called every few thousand lines and once more when the parse ends with
+    /// `{ bytes_read, total_bytes, lines_read, total_lines, messages, elapsed, fraction, eta }`.
+    /// Returning a truthy value, or throwing, cancels the parse, which then fails while keeping
+    /// the messages decoded so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function, or `undefined` to stop reporting progress.
+    #[cfg(not(target_feature = "atomics"))]
+    pub fn set_progress_callback(&mut self, callback: Option<js_sys::Function>) {
+        let callback = callback.map(|callback| -> ProgressCallback {
+            let callback = JsProgressCallback(callback);
+            Arc::new(move |progress: &Progress| callback.call(progress))
+        });
+        self.inner.set_progress_callback(callback);
+    }
+
+    /// Restricts the specifications used to decode messages logged on a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The channel or interface name as it appears in the log, e.g. `can0`.
+    /// * `spec_types` - A `JsValue` containing an array of specification types. An empty array disables decoding.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the channel was configured, otherwise returns an error as a `JsValue`.
+    pub fn set_channel_specs(
+        &mut self,
+        channel: String,
+        spec_types: JsValue,
+    ) -> Result<(), JsValue> {
+        let spec_types: Vec<String> = from_value(spec_types)?;
+        self.inner
+            .set_channel_specs(channel, spec_types)
+            .map_err(|err| err.into())
+    }
+
     /// Parses a vector of strings representing lines of CAN data.
     ///
     /// # Arguments
@@ -109,16 +326,189 @@ impl CANParserWasm {
         }
     }
 
+    /// Converts the decoded signals to a long, tidy CSV with a row per signal value and the columns timestamp, id,
+    /// sa, pgn, spn, label, raw, value and units.
+    ///
+    /// # Returns
+    ///
+    /// Returns the CSV, or an error as a `JsValue` if it cannot be written.
+    pub fn to_long_csv(&self) -> Result<String, JsValue> {
+        let csv = self
+            .inner
+            .to_long_csv(None)
+            .map_err(|err| -> JsValue { err.into() })?;
+        Ok(csv.unwrap_or_default())
+    }
+
+    /// Converts the decoded signals to a wide, time-aligned CSV with a row per interval and a column per signal,
+    /// named `label [units]`.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval between rows in nanoseconds.
+    /// * `resampling` - `RESAMPLING_FFILL` to carry the last value of each signal forward, or `RESAMPLING_MEAN` to
+    ///   average the values of each interval.
+    ///
+    /// # Returns
+    ///
+    /// Returns the CSV, or an error as a `JsValue` if the interval is not positive or the resampling is unknown.
+    pub fn to_wide_csv(&self, interval: i64, resampling: &str) -> Result<String, JsValue> {
+        let resampling =
+            Resampling::from_name(resampling).map_err(|err| -> JsValue { err.into() })?;
+        let csv = self
+            .inner
+            .to_wide_csv(None, interval, resampling)
+            .map_err(|err| -> JsValue { err.into() })?;
+        Ok(csv.unwrap_or_default())
+    }
+
+    /// Encodes physical SPN values into a J1939 message of the loaded J1939 specification. SPNs without a value
+    /// are set to "not available".
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn` - The PGN of the message.
+    /// * `values` - An object mapping each SPN to encode to its physical value.
+    /// * `source_address` - The source address of the message.
+    /// * `destination_address` - The destination address of a PDU1 message. Defaults to the global address.
+    /// * `priority` - The priority of the message. Defaults to the priority of the PGN in the specification.
+    ///
+    /// # Returns
+    ///
+    /// Returns the encoded message, or an error as a `JsValue` if the values cannot be encoded.
+    pub fn encode_j1939(
+        &self,
+        pgn: u16,
+        values: JsValue,
+        source_address: u8,
+        destination_address: Option<u8>,
+        priority: Option<u8>,
+    ) -> Result<JsValue, JsValue> {
+        // Object keys are strings in JS
+        let values: HashMap<String, f64> = from_value(values)?;
+        let values = values
+            .into_iter()
+            .map(|(spn, value)| {
+                spn.parse::<u16>()
+                    .map(|spn| (spn, value))
+                    .map_err(|_| JsValue::from(format!("Invalid SPN: {}", spn)))
+            })
+            .collect::<Result<HashMap<u16, f64>, JsValue>>()?;
+        let message = self
+            .inner
+            .encode_j1939(pgn, &values, source_address, destination_address, priority)
+            .map_err(|err| -> JsValue { err.into() })?;
+        Ok(message.serialize(&Serializer::new().serialize_large_number_types_as_bigints(true))?)
+    }
+
+    /// Encodes physical signal values into a message of the loaded DBC annex.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The name of the message in the DBC annex.
+    /// * `values` - An object mapping each signal name to encode to its physical value.
+    /// * `source_address` - Replaces the source address of messages with an extended ID.
+    ///
+    /// # Returns
+    ///
+    /// Returns the encoded message, or an error as a `JsValue` if the values cannot be encoded.
+    pub fn encode_dbc(
+        &self,
+        message: &str,
+        values: JsValue,
+        source_address: Option<u8>,
+    ) -> Result<JsValue, JsValue> {
+        let values: HashMap<String, f64> = from_value(values)?;
+        let message = self
+            .inner
+            .encode_dbc(message, &values, source_address)
+            .map_err(|err| -> JsValue { err.into() })?;
+        Ok(message.serialize(&Serializer::new().serialize_large_number_types_as_bigints(true))?)
+    }
+
+    /// Writes the selected parsed messages back out as a log, losslessly, so it can be replayed with can-utils or
+    /// loaded into other tools.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_format` - One of `LOG_FORMAT_CANDUMP`, `LOG_FORMAT_ASC` or `LOG_FORMAT_BLF`.
+    /// * `selection` - An optional object selecting the messages to write, with the fields of `set_filter`, e.g. the
+    ///   `pgns` or the `start` and exclusive `end` of a time window. Every criterion given must match.
+    ///
+    /// # Returns
+    ///
+    /// Returns the bytes of the log, or an error as a `JsValue` if it cannot be written.
+    pub fn write_log(&self, log_format: &str, selection: JsValue) -> Result<Vec<u8>, JsValue> {
+        let selection: MessageFilter = if selection.is_undefined() || selection.is_null() {
+            MessageFilter::default()
+        } else {
+            from_value(selection)?
+        };
+        let format =
+            LogWriterFormat::from_name(log_format).map_err(|err| -> JsValue { err.into() })?;
+        let log = self
+            .inner
+            .write_log(None, format, &selection)
+            .map_err(|err| -> JsValue { err.into() })?;
+        Ok(log.unwrap_or_default())
+    }
+
     // Getters and setters
     /// Returns a `Result` containing a `JsValue` representation of the `messages` field of the inner `CANParser` struct.
+    /// Nanosecond timestamps are returned as `BigInt`s since they exceed the safe integer range of a JS number.
     #[wasm_bindgen(getter)]
     pub fn messages(&self) -> Result<JsValue, serde_wasm_bindgen::Error> {
-        to_value(&self.inner.messages)
+        let messages: Vec<_> = self.inner.iter_messages().collect();
+        messages.serialize(&Serializer::new().serialize_large_number_types_as_bigints(true))
     }
 
     /// Clears all messages from the CAN parser's message buffer.
     pub fn clear_messages(&mut self) {
-        self.inner.messages.clear();
+        self.inner.clear_messages();
+    }
+
+    /// Returns the number of parsed messages.
+    #[wasm_bindgen(getter)]
+    pub fn message_count(&self) -> usize {
+        self.inner.message_count()
+    }
+
+    /// Returns `{ timestamps, values }` for a signal across every message carrying it, ordered by timestamp.
+    /// Timestamps are nanosecond `BigInt`s and values are numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `spn` - The SPN of the signal.
+    pub fn signal(&self, spn: u16) -> Result<JsValue, serde_wasm_bindgen::Error> {
+        #[derive(Serialize)]
+        struct Signal {
+            timestamps: Vec<i64>,
+            values: Vec<f32>,
+        }
+        let (timestamps, values) = self.inner.signal(spn);
+        Signal { timestamps, values }
+            .serialize(&Serializer::new().serialize_large_number_types_as_bigints(true))
+    }
+
+    /// Returns the lines that could not be parsed by the last parse as `{ entries, counts }`, where each entry
+    /// carries its line number, byte offset, raw line, error kind and message, and `counts` maps each kind to its count.
+    #[wasm_bindgen(getter)]
+    pub fn diagnostics(&self) -> Result<JsValue, serde_wasm_bindgen::Error> {
+        self.inner
+            .diagnostics
+            .serialize(&Serializer::json_compatible())
+    }
+
+    /// Returns the specification entries that were skipped because they could not be read.
+    #[wasm_bindgen(getter)]
+    pub fn spec_warnings(&self) -> Vec<JsValue> {
+        self.inner
+            .spec_warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.to_string().into())
+            .collect()
     }
 
     /// Returns the filtered specification as a `JsValue`.
@@ -9,7 +9,7 @@ specs = {
     can_parser.SPEC_TYPE_J1939: spec,
 }
 
-parser = can_parser.CANParserPython(can_parser.ERROR_WARN, r"^\((?P<timestamp>[0-9]+\.[0-9]+)\).*?(?P<id>[0-9A-F]{3,8})#(?P<data>[0-9A-F]+)", specs)
+parser = can_parser.CANParserPython(can_parser.ERROR_WARN, r"^\((?P<timestamp>[0-9]+\.[0-9]+)\)[ \t]*(?:(?P<channel>[A-Za-z0-9_-]+)[ \t]+)?.*?(?P<id>[0-9A-F]{3,8})#(?:#(?P<fd_flags>[0-9A-F]))?(?:(?P<rtr>R)(?P<rtr_dlc>[0-9A-F])?|(?P<data>[0-9A-F]*))(?:[ \t]+(?P<direction>[RT])(?-u:\b))?", specs)
 
 with open("/home/user_name/CANParser/highway2City.log", "r") as f:
     parser.parse_lines(f.readlines())
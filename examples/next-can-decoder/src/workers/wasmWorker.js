@@ -6,7 +6,7 @@ function wrapExports(lib) {
     return async (fileText, specText) => {
         let can_parser = new lib.CANParserWasm(
             "Warn", 
-            "^\\((?P<timestamp>[0-9]+\\.[0-9]+)\\).*?(?P<id>[0-9A-F]{3,8})#(?P<data>[0-9A-F]+)", 
+            "^\\((?P<timestamp>[0-9]+\\.[0-9]+)\\)[ \\t]*(?:(?P<channel>[A-Za-z0-9_-]+)[ \\t]+)?.*?(?P<id>[0-9A-F]{3,8})#(?:#(?P<fd_flags>[0-9A-F]))?(?:(?P<rtr>R)(?P<rtr_dlc>[0-9A-F])?|(?P<data>[0-9A-F]*))(?:[ \\t]+(?P<direction>[RT])(?-u:\\b))?", 
             { "J1939": specText }  // replace the hardcoded path with specText
         );
         can_parser.parse_lines(fileText);